@@ -0,0 +1,195 @@
+// Textures and fonts shared by every screen
+//
+// assets/manifest.toml maps the ids used in code to files:
+//     [textures]
+//     obstacle/statue = "assets/obstacles/statue.png"
+//     [fonts]
+//     ui = "assets/DroidSansMono.ttf", 128, bold
+// Everything in it is loaded once at startup, so a missing or broken file is
+// reported before the game opens instead of when a screen first needs it.
+
+use sdl2::image::LoadTexture;
+use sdl2::render::Texture;
+use sdl2::render::TextureCreator;
+use sdl2::ttf::Font;
+use sdl2::ttf::FontStyle;
+use sdl2::ttf::Sdl2TtfContext;
+use sdl2::video::WindowContext;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const MANIFEST_PATH: &str = "assets/manifest.toml";
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct FontEntry {
+    pub path: String,
+    pub size: u16,
+    pub style: FontStyle,
+}
+
+// Contents of the manifest, entries are kept in file order
+#[derive(Clone, Default, Debug)]
+pub struct AssetManifest {
+    pub textures: Vec<(String, String)>, // (id, path)
+    pub fonts: Vec<(String, FontEntry)>,
+}
+
+impl AssetManifest {
+    pub fn load(path: &Path) -> Result<AssetManifest, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let mut manifest = AssetManifest::default();
+        let mut section = String::new();
+        for (i, line) in contents.lines().enumerate() {
+            // Strip comments and blank lines
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let bad_line = |e: &str| format!("{}: line {}: {}", path.display(), i + 1, e);
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len() - 1].trim().to_string();
+                continue;
+            }
+
+            let (id, value) = line
+                .split_once('=')
+                .ok_or_else(|| bad_line("expected id = \"file\""))?;
+            let id = id.trim().to_string();
+            if manifest.contains(&id) {
+                return Err(bad_line(&format!("{:?} is listed twice", id)));
+            }
+
+            match section.as_str() {
+                "textures" => manifest.textures.push((id, unquote(value).to_string())),
+                "fonts" => {
+                    let font = parse_font(value).map_err(|e| bad_line(&e))?;
+                    manifest.fonts.push((id, font));
+                }
+                "" => return Err(bad_line("entry before any [section]")),
+                _ => return Err(bad_line(&format!("unknown section [{}]", section))),
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        self.textures.iter().any(|(t, _)| t == id) || self.fonts.iter().any(|(f, _)| f == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len() + self.fonts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Everything in the manifest, loaded and ready to draw with
+pub struct Assets<'a> {
+    textures: HashMap<String, Texture<'a>>,
+    fonts: HashMap<String, Font<'a, 'static>>,
+}
+
+impl<'a> Assets<'a> {
+    // Loads every entry in the manifest, carrying on past failures so that
+    // all the problems are reported together
+    // Params: manifest, texture creator of the window the textures are drawn
+    //         to, ttf context to open fonts with
+    // Returns: the loaded assets, or a report of every missing or broken file
+    pub fn load(
+        manifest: &AssetManifest,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        ttf_context: &'a Sdl2TtfContext,
+    ) -> Result<Assets<'a>, String> {
+        let mut textures = HashMap::new();
+        let mut fonts = HashMap::new();
+        let mut problems = Vec::new();
+
+        for (id, path) in manifest.textures.iter() {
+            if !Path::new(path).exists() {
+                problems.push(format!("missing  {} ({})", id, path));
+                continue;
+            }
+            match texture_creator.load_texture(path) {
+                Ok(texture) => {
+                    textures.insert(id.clone(), texture);
+                }
+                Err(e) => problems.push(format!("broken   {} ({}): {}", id, path, e)),
+            }
+        }
+
+        for (id, entry) in manifest.fonts.iter() {
+            if !Path::new(&entry.path).exists() {
+                problems.push(format!("missing  {} ({})", id, entry.path));
+                continue;
+            }
+            match ttf_context.load_font(&entry.path, entry.size) {
+                Ok(mut font) => {
+                    font.set_style(entry.style);
+                    fonts.insert(id.clone(), font);
+                }
+                Err(e) => problems.push(format!("broken   {} ({}): {}", id, entry.path, e)),
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(format!(
+                "{} of {} assets in {} couldn't be loaded:\n\t\t\t{}",
+                problems.len(),
+                manifest.len(),
+                MANIFEST_PATH,
+                problems.join("\n\t\t\t")
+            ));
+        }
+
+        Ok(Assets { textures, fonts })
+    }
+
+    pub fn texture(&self, id: &str) -> Result<&Texture<'a>, String> {
+        self.textures
+            .get(id)
+            .ok_or(format!("no texture {:?} in {}", id, MANIFEST_PATH))
+    }
+
+    pub fn font(&self, id: &str) -> Result<&Font<'a, 'static>, String> {
+        self.fonts
+            .get(id)
+            .ok_or(format!("no font {:?} in {}", id, MANIFEST_PATH))
+    }
+}
+
+// Reads a font entry: "file", size[, style]
+fn parse_font(value: &str) -> Result<FontEntry, String> {
+    let parts: Vec<&str> = value.split(',').map(|part| part.trim()).collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(String::from("expected \"file\", size[, style]"));
+    }
+
+    let size = parts[1]
+        .parse::<u16>()
+        .map_err(|_| format!("invalid size {:?}", parts[1]))?;
+    let style = match parts.get(2).copied() {
+        None | Some("normal") => FontStyle::NORMAL,
+        Some("bold") => FontStyle::BOLD,
+        Some("italic") => FontStyle::ITALIC,
+        Some(other) => return Err(format!("unknown style {:?}", other)),
+    };
+
+    Ok(FontEntry {
+        path: unquote(parts[0]).to_string(),
+        size,
+        style,
+    })
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"')
+}
@@ -0,0 +1,267 @@
+// Music and sound effects
+//
+// Everything is loaded once when the audio device is opened. If there is no
+// audio device (a headless CI box, a machine with sound disabled) or a file
+// fails to load, the matching sounds are silently skipped so the game still
+// runs.
+
+use sdl2::mixer;
+use sdl2::mixer::Channel;
+use sdl2::mixer::Chunk;
+use sdl2::mixer::Music;
+use sdl2::AudioSubsystem;
+use sdl2::Sdl;
+
+// Number of effects that can play over each other
+const EFFECT_CHANNELS: i32 = 16;
+
+// Starting volumes, from 0.0 (silent) to 1.0 (full)
+pub const DEFAULT_MASTER_VOLUME: f32 = 1.0;
+pub const DEFAULT_MUSIC_VOLUME: f32 = 0.6;
+pub const DEFAULT_EFFECTS_VOLUME: f32 = 0.8;
+
+// Looping background music, one per screen
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MusicTrack {
+    Title,
+    Run,
+    Credits,
+}
+
+impl MusicTrack {
+    const ALL: [MusicTrack; 3] = [MusicTrack::Title, MusicTrack::Run, MusicTrack::Credits];
+
+    fn path(&self) -> &'static str {
+        match self {
+            MusicTrack::Title => "assets/audio/music/title.wav",
+            MusicTrack::Run => "assets/audio/music/run.wav",
+            MusicTrack::Credits => "assets/audio/music/credits.wav",
+        }
+    }
+}
+
+// One-shot effects fired by game events
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SoundEffect {
+    Jump,
+    Coin,
+    PowerUp,
+    Flip,
+    Crash,
+    Splash,
+    GameOver,
+}
+
+impl SoundEffect {
+    const ALL: [SoundEffect; 7] = [
+        SoundEffect::Jump,
+        SoundEffect::Coin,
+        SoundEffect::PowerUp,
+        SoundEffect::Flip,
+        SoundEffect::Crash,
+        SoundEffect::Splash,
+        SoundEffect::GameOver,
+    ];
+
+    fn path(&self) -> &'static str {
+        match self {
+            SoundEffect::Jump => "assets/audio/sfx/jump.wav",
+            SoundEffect::Coin => "assets/audio/sfx/coin.wav",
+            SoundEffect::PowerUp => "assets/audio/sfx/power_up.wav",
+            SoundEffect::Flip => "assets/audio/sfx/flip.wav",
+            SoundEffect::Crash => "assets/audio/sfx/crash.wav",
+            SoundEffect::Splash => "assets/audio/sfx/splash.wav",
+            SoundEffect::GameOver => "assets/audio/sfx/game_over.wav",
+        }
+    }
+}
+
+pub struct SoundManager {
+    output: Option<AudioOutput>, // None when there is nothing to play sound on
+
+    // Volumes from 0.0 (silent) to 1.0 (full)
+    master_volume: f32,
+    music_volume: f32,
+    effects_volume: f32,
+
+    playing: Option<MusicTrack>,
+}
+
+// An open audio device and everything loaded for it
+struct AudioOutput {
+    _audio: AudioSubsystem,
+    // Indexed by MusicTrack / SoundEffect, None if the file failed to load
+    music: Vec<Option<Music<'static>>>,
+    effects: Vec<Option<Chunk>>,
+}
+
+impl Drop for AudioOutput {
+    fn drop(&mut self) {
+        Music::halt();
+        mixer::close_audio();
+    }
+}
+
+impl SoundManager {
+    // Opens the audio device and loads every track and effect. Never fails,
+    // any problem is reported and leaves the game silent instead
+    pub fn init(sdl_cxt: &Sdl) -> SoundManager {
+        let output = match AudioOutput::open(sdl_cxt) {
+            Ok(output) => Some(output),
+            Err(e) => {
+                println!("\n\t\tNo audio, continuing without sound: {}", e);
+                None
+            }
+        };
+
+        let mut manager = SoundManager {
+            output,
+            master_volume: DEFAULT_MASTER_VOLUME,
+            music_volume: DEFAULT_MUSIC_VOLUME,
+            effects_volume: DEFAULT_EFFECTS_VOLUME,
+            playing: None,
+        };
+        manager.apply_volumes();
+        manager
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.output.is_none()
+    }
+
+    /* ~~~~~~ Playback ~~~~~~ */
+
+    // Starts looping a track. If it's already playing it just keeps going
+    // (unpausing it if needed), so screens can call this every time they start
+    pub fn play_music(&mut self, track: MusicTrack) {
+        if self.playing == Some(track) {
+            self.resume_music();
+            return;
+        }
+        self.playing = Some(track);
+
+        if let Some(Some(music)) = self
+            .output
+            .as_ref()
+            .map(|output| &output.music[track as usize])
+        {
+            if let Err(e) = music.play(-1) {
+                println!("\n\t\tFailed to play {}: {}", track.path(), e);
+            }
+        }
+    }
+
+    pub fn stop_music(&mut self) {
+        if self.output.is_some() {
+            Music::halt();
+        }
+        self.playing = None;
+    }
+
+    pub fn pause_music(&self) {
+        if self.output.is_some() {
+            Music::pause();
+        }
+    }
+
+    pub fn resume_music(&self) {
+        if self.output.is_some() {
+            Music::resume();
+        }
+    }
+
+    // Plays an effect once on the first free channel
+    pub fn play(&self, effect: SoundEffect) {
+        if let Some(Some(chunk)) = self
+            .output
+            .as_ref()
+            .map(|output| &output.effects[effect as usize])
+        {
+            // No free channel just means this effect is skipped
+            let _ = Channel::all().play(chunk, 0);
+        }
+    }
+
+    /* ~~~~~~ Volume ~~~~~~ */
+
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        self.music_volume
+    }
+
+    pub fn effects_volume(&self) -> f32 {
+        self.effects_volume
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+        self.apply_volumes();
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.music_volume = volume.clamp(0.0, 1.0);
+        self.apply_volumes();
+    }
+
+    pub fn set_effects_volume(&mut self, volume: f32) {
+        self.effects_volume = volume.clamp(0.0, 1.0);
+        self.apply_volumes();
+    }
+
+    // Pushes the current volumes to the mixer
+    fn apply_volumes(&mut self) {
+        let music = mixer_volume(self.master_volume * self.music_volume);
+        let effects = mixer_volume(self.master_volume * self.effects_volume);
+        if let Some(output) = self.output.as_mut() {
+            Music::set_volume(music);
+            for chunk in output.effects.iter_mut().flatten() {
+                chunk.set_volume(effects);
+            }
+        }
+    }
+}
+
+impl AudioOutput {
+    fn open(sdl_cxt: &Sdl) -> Result<AudioOutput, String> {
+        let audio = sdl_cxt.audio()?;
+        mixer::open_audio(
+            mixer::DEFAULT_FREQUENCY,
+            mixer::DEFAULT_FORMAT,
+            mixer::DEFAULT_CHANNELS,
+            1024,
+        )?;
+        mixer::allocate_channels(EFFECT_CHANNELS);
+
+        // A missing file only silences that one sound
+        let music = MusicTrack::ALL
+            .iter()
+            .map(|track| {
+                Music::from_file(track.path())
+                    .map_err(|e| println!("\n\t\tFailed to load {}: {}", track.path(), e))
+                    .ok()
+            })
+            .collect();
+        let effects = SoundEffect::ALL
+            .iter()
+            .map(|effect| {
+                Chunk::from_file(effect.path())
+                    .map_err(|e| println!("\n\t\tFailed to load {}: {}", effect.path(), e))
+                    .ok()
+            })
+            .collect();
+
+        Ok(AudioOutput {
+            _audio: audio,
+            music,
+            effects,
+        })
+    }
+}
+
+// Converts a 0.0 - 1.0 volume to the mixer's 0 - 128 range
+fn mixer_volume(volume: f32) -> i32 {
+    (volume.clamp(0.0, 1.0) * mixer::MAX_VOLUME as f32).round() as i32
+}
@@ -0,0 +1,193 @@
+// Cubic Bezier curves the terrain is built from
+//
+// A curve is sampled once into an arc length table, which maps between the
+// curve's parameter t, distance along the curve and x. Terrain only ever
+// uses curves whose control points go left to right, so every x is on the
+// curve exactly once and can be looked up by binary search.
+
+// Samples per pixel of a curve's width when building its table. Enough that
+// the straight lines between samples are shorter than a pixel
+const SAMPLES_PER_PIXEL: f64 = 2.0;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CubicBezier {
+    pub p0: (f64, f64), // Start point
+    pub p1: (f64, f64), // Pulls the start of the curve towards it
+    pub p2: (f64, f64), // Pulls the end of the curve towards it
+    pub p3: (f64, f64), // End point
+}
+
+impl CubicBezier {
+    pub fn new(p0: (f64, f64), p1: (f64, f64), p2: (f64, f64), p3: (f64, f64)) -> CubicBezier {
+        CubicBezier { p0, p1, p2, p3 }
+    }
+
+    // Params: how far along the curve from 0.0 (p0) to 1.0 (p3)
+    pub fn point(&self, t: f64) -> (f64, f64) {
+        let u = 1.0 - t;
+        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        (
+            a * self.p0.0 + b * self.p1.0 + c * self.p2.0 + d * self.p3.0,
+            a * self.p0.1 + b * self.p1.1 + c * self.p2.1 + d * self.p3.1,
+        )
+    }
+
+    // Direction the curve is heading in at t, not normalised
+    pub fn tangent(&self, t: f64) -> (f64, f64) {
+        let u = 1.0 - t;
+        let (a, b, c) = (3.0 * u * u, 6.0 * u * t, 3.0 * t * t);
+        (
+            a * (self.p1.0 - self.p0.0) + b * (self.p2.0 - self.p1.0) + c * (self.p3.0 - self.p2.0),
+            a * (self.p1.1 - self.p0.1) + b * (self.p2.1 - self.p1.1) + c * (self.p3.1 - self.p2.1),
+        )
+    }
+
+    // Angle of the curve at t, in radians. With y growing downwards, a
+    // positive angle is going downhill
+    pub fn slope(&self, t: f64) -> f64 {
+        let (dx, dy) = self.tangent(t);
+        dy.atan2(dx)
+    }
+}
+
+// Distance along a curve at evenly spaced values of t
+pub struct ArcLengthTable {
+    xs: Vec<f64>,      // x of the curve at each sample
+    lengths: Vec<f64>, // Distance along the curve up to each sample
+}
+
+impl ArcLengthTable {
+    // Params: curve to measure, its control points left to right
+    pub fn new(curve: &CubicBezier) -> ArcLengthTable {
+        let width = (curve.p3.0 - curve.p0.0).abs();
+        let steps = ((width * SAMPLES_PER_PIXEL).ceil() as usize).max(1);
+
+        let mut xs = Vec::with_capacity(steps + 1);
+        let mut lengths = Vec::with_capacity(steps + 1);
+        let mut prev = curve.p0;
+        let mut length = 0.0;
+        for i in 0..=steps {
+            let point = curve.point(i as f64 / steps as f64);
+            length += ((point.0 - prev.0).powi(2) + (point.1 - prev.1).powi(2)).sqrt();
+            xs.push(point.0);
+            lengths.push(length);
+            prev = point;
+        }
+        ArcLengthTable { xs, lengths }
+    }
+
+    // Total length of the curve
+    pub fn length(&self) -> f64 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    // Params: t to measure up to
+    // Returns: distance along the curve from p0
+    pub fn length_at(&self, t: f64) -> f64 {
+        let steps = self.steps();
+        let at = t.clamp(0.0, 1.0) * steps as f64;
+        // The last sample only starts a step past the end of the curve
+        let i = (at as usize).min(steps - 1);
+        let frac = at - i as f64;
+        self.lengths[i] + (self.lengths[i + 1] - self.lengths[i]) * frac
+    }
+
+    // Params: distance along the curve from p0
+    // Returns: t that far along, for stepping along the curve evenly
+    pub fn t_at_length(&self, length: f64) -> f64 {
+        self.t_where(&self.lengths, length)
+    }
+
+    // Params: x between the curve's two ends
+    // Returns: t the curve reaches that x at
+    pub fn t_at_x(&self, x: f64) -> f64 {
+        self.t_where(&self.xs, x)
+    }
+
+    fn steps(&self) -> usize {
+        self.xs.len() - 1
+    }
+
+    // Finds where an increasing column of the table reaches a value, blending
+    // between the samples either side
+    fn t_where(&self, column: &[f64], value: f64) -> f64 {
+        let steps = self.steps();
+        let i = column.partition_point(|v| *v < value);
+        if i == 0 {
+            return 0.0;
+        }
+        if i > steps {
+            return 1.0;
+        }
+        let (from, to) = (column[i - 1], column[i]);
+        let frac = if to > from {
+            (value - from) / (to - from)
+        } else {
+            0.0
+        };
+        (i as f64 - 1.0 + frac) / steps as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Straight from (0, 0) to (300, 400), 500 long, with the middle control
+    // points where they'd be for a straight line at even speed
+    fn straight() -> CubicBezier {
+        CubicBezier::new(
+            (0.0, 0.0),
+            (100.0, 400.0 / 3.0),
+            (200.0, 800.0 / 3.0),
+            (300.0, 400.0),
+        )
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn straight_curve_is_as_long_as_the_line() {
+        let table = ArcLengthTable::new(&straight());
+        assert!(close(table.length(), 500.0), "{}", table.length());
+        assert!(close(table.length_at(0.0), 0.0));
+        assert!(close(table.length_at(0.5), 250.0));
+        assert!(close(table.length_at(1.0), 500.0));
+    }
+
+    #[test]
+    fn flat_curve_is_as_long_as_it_is_wide() {
+        let curve = CubicBezier::new((50.0, 10.0), (150.0, 10.0), (250.0, 10.0), (350.0, 10.0));
+        let table = ArcLengthTable::new(&curve);
+        assert!(close(table.length(), 300.0));
+        assert!(close(curve.slope(0.3), 0.0));
+    }
+
+    #[test]
+    fn lengths_and_xs_map_back_to_t() {
+        let curve = straight();
+        let table = ArcLengthTable::new(&curve);
+        for t in [0.0, 0.25, 0.5, 0.9, 1.0] {
+            let x = curve.point(t).0;
+            assert!((table.t_at_x(x) - t).abs() < 1e-3, "t {}", t);
+            let length = table.length_at(t);
+            assert!((table.t_at_length(length) - t).abs() < 1e-3, "t {}", t);
+        }
+    }
+
+    #[test]
+    fn uneven_control_points_still_step_evenly_by_length() {
+        // Same line, but bunched up towards the start so t doesn't move
+        // along it at an even speed
+        let curve = CubicBezier::new((0.0, 0.0), (30.0, 40.0), (60.0, 80.0), (300.0, 400.0));
+        let table = ArcLengthTable::new(&curve);
+        assert!(close(table.length(), 500.0));
+        for length in [100.0, 250.0, 400.0] {
+            let (x, y) = curve.point(table.t_at_length(length));
+            let along = (x * x + y * y).sqrt();
+            assert!((along - length).abs() < 0.5, "{} at {}", along, length);
+        }
+    }
+}
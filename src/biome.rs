@@ -0,0 +1,352 @@
+// Stretches of a run with their own look and feel
+//
+// A biome lasts for a few terrain segments and decides which terrain types
+// and objects are generated, the colours of the background and the wind
+// blowing on the player. Between two biomes there is a flat transition
+// segment that can have the ground of either.
+
+use crate::parallax::Generator;
+use crate::parallax::Layer;
+
+use crate::ObstacleType;
+use crate::StaticObject;
+use crate::TerrainType;
+use crate::CAM_H;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use sdl2::pixels::Color;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Biome {
+    Park,
+    Beach,
+    Downtown,
+    Harbour,
+}
+
+// Colours behind every background layer of a biome
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Palette {
+    pub sky: Color,    // Behind everything
+    pub skybox: Color, // Lower part of the sky, under the hills
+}
+
+// Images every biome shares at the very back. A new biome's layers fade in
+// over the old ones, but textures can't be faded, so these stay the same
+// from one biome to the next
+const SKY: Layer = image("background/sky", 0.01, 0, CAM_H / 3);
+const SUNSET: Layer = image("background/sunset", 0.0, -128, CAM_H);
+const FAR_HILLS: Layer = image("background/hills", 0.01, -150, CAM_H);
+
+// Layers of each biome, from the back to the front
+const PARK_LAYERS: [Layer; 5] = [
+    SKY,
+    SUNSET,
+    FAR_HILLS,
+    hills(Color::RGB(81, 65, 67), 0.1, 400.0, 2.0, 1.0, 820.0),
+    hills(Color::RGB(195, 133, 96), 0.17, 300.0, 2.5, 0.5, 600.0),
+];
+
+const BEACH_LAYERS: [Layer; 6] = [
+    SKY,
+    SUNSET,
+    FAR_HILLS,
+    clouds(Color::RGBA(245, 245, 250, 220), 0.05, 320, 40, 200),
+    hills(Color::RGB(168, 140, 98), 0.1, 600.0, 1.2, 1.0, 700.0),
+    hills(Color::RGB(236, 208, 150), 0.17, 500.0, 1.5, 0.5, 480.0),
+];
+
+const DOWNTOWN_LAYERS: [Layer; 5] = [
+    SKY,
+    SUNSET,
+    FAR_HILLS,
+    skyline(Color::RGB(52, 56, 68), 0.1, 520, (60, 140), (120, 320), 20),
+    skyline(
+        Color::RGB(110, 114, 126),
+        0.17,
+        600,
+        (80, 180),
+        (60, 200),
+        60,
+    ),
+];
+
+const HARBOUR_LAYERS: [Layer; 6] = [
+    SKY,
+    SUNSET,
+    FAR_HILLS,
+    clouds(Color::RGBA(170, 180, 190, 200), 0.05, 400, 60, 240),
+    hills(Color::RGB(38, 60, 76), 0.1, 500.0, 1.5, 1.0, 760.0),
+    skyline(
+        Color::RGB(70, 108, 120),
+        0.17,
+        610,
+        (160, 280),
+        (60, 140),
+        80,
+    ),
+];
+
+// Shorthands for declaring layers above
+const fn image(texture: &'static str, depth: f64, y: i32, h: u32) -> Layer {
+    Layer {
+        depth,
+        color: Color::RGB(255, 255, 255),
+        generator: Generator::Image { texture, y, h },
+    }
+}
+
+const fn hills(color: Color, depth: f64, freq: f32, amp: f32, modifier: f32, mul: f32) -> Layer {
+    Layer {
+        depth,
+        color,
+        generator: Generator::Hills {
+            freq,
+            amp,
+            modifier,
+            mul,
+        },
+    }
+}
+
+const fn skyline(
+    color: Color,
+    depth: f64,
+    horizon: i32,
+    (min_w, max_w): (u32, u32),
+    (min_h, max_h): (u32, u32),
+    max_gap: u32,
+) -> Layer {
+    Layer {
+        depth,
+        color,
+        generator: Generator::Skyline {
+            horizon,
+            min_w,
+            max_w,
+            min_h,
+            max_h,
+            max_gap,
+        },
+    }
+}
+
+const fn clouds(color: Color, depth: f64, spacing: u32, top: i32, bottom: i32) -> Layer {
+    Layer {
+        depth,
+        color,
+        generator: Generator::Clouds {
+            spacing,
+            top,
+            bottom,
+        },
+    }
+}
+
+impl Palette {
+    // Blends between two palettes
+    // Params: palette to blend towards, how far from 0.0 (self) to 1.0 (other)
+    // Returns: the blended palette
+    pub fn lerp(&self, other: &Palette, t: f64) -> Palette {
+        Palette {
+            sky: lerp_color(self.sky, other.sky, t),
+            skybox: lerp_color(self.skybox, other.skybox, t),
+        }
+    }
+}
+
+impl Biome {
+    // Name shown on the HUD on entering the biome
+    pub fn name(&self) -> &'static str {
+        match self {
+            Biome::Park => "Park",
+            Biome::Beach => "Beach",
+            Biome::Downtown => "Downtown",
+            Biome::Harbour => "Harbour",
+        }
+    }
+
+    // Range of how many terrain segments the biome lasts for
+    pub fn length(&self) -> (u32, u32) {
+        match self {
+            Biome::Park => (4, 7),
+            Biome::Beach => (3, 5),
+            Biome::Downtown => (4, 6),
+            Biome::Harbour => (3, 5),
+        }
+    }
+
+    // Biomes that can come next, so e.g. the beach never leads straight
+    // into downtown
+    pub fn neighbours(&self) -> [Biome; 2] {
+        match self {
+            Biome::Park => [Biome::Downtown, Biome::Beach],
+            Biome::Beach => [Biome::Park, Biome::Harbour],
+            Biome::Downtown => [Biome::Park, Biome::Harbour],
+            Biome::Harbour => [Biome::Beach, Biome::Downtown],
+        }
+    }
+
+    // Picks the biome after this one
+    // Params: the run's seeded generator
+    // Returns: one of the neighbouring biomes
+    pub fn next(&self, rng: &mut StdRng) -> Biome {
+        let neighbours = self.neighbours();
+        neighbours[rng.gen_range(0..neighbours.len())]
+    }
+
+    // Relative chance of each type of terrain segment
+    pub fn terrain_weights(&self) -> [(TerrainType, u32); 8] {
+        match self {
+            Biome::Park => [
+                (TerrainType::Grass, 8),
+                (TerrainType::Asphalt, 1),
+                (TerrainType::Sand, 1),
+                (TerrainType::Water, 1),
+                (TerrainType::Ice, 0),
+                (TerrainType::Mud, 1),
+                (TerrainType::Ramp, 1),
+                (TerrainType::Conveyor, 0),
+            ],
+            Biome::Beach => [
+                (TerrainType::Grass, 1),
+                (TerrainType::Asphalt, 0),
+                (TerrainType::Sand, 6),
+                (TerrainType::Water, 3),
+                (TerrainType::Ice, 0),
+                (TerrainType::Mud, 1),
+                (TerrainType::Ramp, 0),
+                (TerrainType::Conveyor, 1),
+            ],
+            Biome::Downtown => [
+                (TerrainType::Grass, 1),
+                (TerrainType::Asphalt, 8),
+                (TerrainType::Sand, 0),
+                (TerrainType::Water, 0),
+                (TerrainType::Ice, 1),
+                (TerrainType::Mud, 0),
+                (TerrainType::Ramp, 2),
+                (TerrainType::Conveyor, 1),
+            ],
+            Biome::Harbour => [
+                (TerrainType::Grass, 0),
+                (TerrainType::Asphalt, 4),
+                (TerrainType::Sand, 1),
+                (TerrainType::Water, 4),
+                (TerrainType::Ice, 1),
+                (TerrainType::Mud, 1),
+                (TerrainType::Ramp, 0),
+                (TerrainType::Conveyor, 2),
+            ],
+        }
+    }
+
+    // Relative chance of each object spawned on the ground
+    pub fn object_weights(&self) -> [(StaticObject, u32); 7] {
+        match self {
+            Biome::Park => [
+                (StaticObject::Statue, 1),
+                (StaticObject::Balloon, 2),
+                (StaticObject::Chest, 1),
+                (StaticObject::Coin, 1),
+                (StaticObject::Power, 1),
+                (StaticObject::Bench, 2),
+                (StaticObject::Rail, 1),
+            ],
+            Biome::Beach => [
+                (StaticObject::Statue, 0),
+                (StaticObject::Balloon, 2),
+                (StaticObject::Chest, 2),
+                (StaticObject::Coin, 2),
+                (StaticObject::Power, 1),
+                (StaticObject::Bench, 0),
+                (StaticObject::Rail, 1),
+            ],
+            Biome::Downtown => [
+                (StaticObject::Statue, 2),
+                (StaticObject::Balloon, 1),
+                (StaticObject::Chest, 0),
+                (StaticObject::Coin, 1),
+                (StaticObject::Power, 1),
+                (StaticObject::Bench, 2),
+                (StaticObject::Rail, 2),
+            ],
+            Biome::Harbour => [
+                (StaticObject::Statue, 0),
+                (StaticObject::Balloon, 1),
+                (StaticObject::Chest, 3),
+                (StaticObject::Coin, 2),
+                (StaticObject::Power, 1),
+                (StaticObject::Bench, 0),
+                (StaticObject::Rail, 1),
+            ],
+        }
+    }
+
+    // Kinds of rail a rail spawned here can be
+    pub fn rails(&self) -> &'static [ObstacleType] {
+        match self {
+            Biome::Park => &[ObstacleType::Handrail, ObstacleType::Ledge],
+            Biome::Beach => &[ObstacleType::Ledge],
+            Biome::Downtown => &[
+                ObstacleType::Handrail,
+                ObstacleType::Ledge,
+                ObstacleType::PowerLine,
+            ],
+            Biome::Harbour => &[ObstacleType::Ledge, ObstacleType::PowerLine],
+        }
+    }
+
+    // Horizontal push of the wind as an acceleration, positive is a tailwind
+    pub fn wind(&self) -> f64 {
+        match self {
+            Biome::Beach => 0.02,
+            Biome::Harbour => -0.02,
+            _ => 0.0,
+        }
+    }
+
+    // Scrolling background layers, from the back to the front
+    pub fn backdrop(&self) -> &'static [Layer] {
+        match self {
+            Biome::Park => &PARK_LAYERS,
+            Biome::Beach => &BEACH_LAYERS,
+            Biome::Downtown => &DOWNTOWN_LAYERS,
+            Biome::Harbour => &HARBOUR_LAYERS,
+        }
+    }
+
+    pub fn palette(&self) -> Palette {
+        match self {
+            Biome::Park => Palette {
+                sky: Color::RGB(3, 120, 206),
+                skybox: Color::RGB(0, 0, 0),
+            },
+            Biome::Beach => Palette {
+                sky: Color::RGB(64, 180, 230),
+                skybox: Color::RGB(20, 40, 70),
+            },
+            Biome::Downtown => Palette {
+                sky: Color::RGB(96, 104, 128),
+                skybox: Color::RGB(20, 20, 28),
+            },
+            Biome::Harbour => Palette {
+                sky: Color::RGB(44, 84, 128),
+                skybox: Color::RGB(6, 18, 36),
+            },
+        }
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Color::RGBA(
+        mix(from.r, to.r),
+        mix(from.g, to.g),
+        mix(from.b, to.b),
+        mix(from.a, to.a),
+    )
+}
@@ -0,0 +1,430 @@
+// Shapes things collide as, and the separating axis test between them
+//
+// A shape is one or more convex polygons in screen space, fitted to a
+// hitbox and turned with it the same way copy_ex turns the picture drawn
+// there: clockwise about the middle of the box. Two convex polygons overlap
+// unless some edge of one has both polygons on opposite sides of it, so the
+// edge the overlap is shallowest across gives the direction to push them
+// apart and how far.
+//
+// Shapes can also be swept, for things moving far enough in a tick to pass
+// right through each other. Along every one of those edges a moving shape
+// overlaps the other for a stretch of time, and it's only touching the
+// other shape while it's within all of those stretches at once.
+
+use crate::ObstacleType;
+
+use sdl2::rect::Point;
+use sdl2::rect::Rect;
+
+// Corners of a convex polygon, as shares of the box it's fitted to. (0, 0)
+// is the top left of the box and (1, 1) the bottom right
+type Outline = &'static [(f64, f64)];
+
+const BOX: Outline = &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+// Statue is a batter on a round plinth, too irregular for one convex
+// polygon, so it's split into the plinth and the figure on it
+const STATUE_PLINTH: Outline = &[
+    (0.08, 0.86),
+    (0.2, 0.78),
+    (0.8, 0.78),
+    (0.92, 0.86),
+    (0.8, 0.97),
+    (0.2, 0.97),
+];
+const STATUE_FIGURE: Outline = &[
+    (0.1, 0.05),
+    (0.45, 0.08),
+    (0.7, 0.3),
+    (0.72, 0.8),
+    (0.3, 0.8),
+    (0.25, 0.3),
+];
+
+// Just the balloon, the string can be run through
+const BALLOON: Outline = &[
+    (0.35, 0.02),
+    (0.65, 0.02),
+    (0.8, 0.17),
+    (0.8, 0.43),
+    (0.65, 0.58),
+    (0.35, 0.58),
+    (0.2, 0.43),
+    (0.2, 0.17),
+];
+
+// Seat and back, fitted to the bench's hitbox rather than its picture
+const BENCH: Outline = &[(0.05, 0.1), (0.95, 0.1), (0.95, 1.0), (0.05, 1.0)];
+
+// The bar along the top of a rail, posts and poles can be run through
+const RAIL: Outline = &[(0.0, 0.0), (1.0, 0.0), (1.0, 0.1), (0.0, 0.1)];
+
+impl ObstacleType {
+    // Params: none
+    // Returns: the polygons an obstacle collides as, fitted to its hitbox
+    pub fn outline(&self) -> &'static [Outline] {
+        match self {
+            ObstacleType::Statue => &[STATUE_PLINTH, STATUE_FIGURE],
+            ObstacleType::Balloon => &[BALLOON],
+            ObstacleType::Chest => &[BOX],
+            ObstacleType::Bench => &[BENCH],
+            ObstacleType::Handrail | ObstacleType::PowerLine => &[RAIL],
+            ObstacleType::Ledge => &[BOX],
+        }
+    }
+}
+
+// Where two shapes overlap
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Contact {
+    pub normal: (f64, f64), // Unit direction that pushes the first shape out of the second
+    pub depth: f64,         // How far it has to be pushed
+}
+
+// When a moving shape first touches another
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Impact {
+    pub time: f64,          // Share of the move made before touching, 0.0 - 1.0
+    pub normal: (f64, f64), // Unit direction out of the surface it touched
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Shape {
+    parts: Vec<Vec<(f64, f64)>>, // Convex polygons, corners in screen space
+}
+
+impl Shape {
+    /*  Fits outlines to a box and turns them with it
+     *
+     *  - Takes in `outlines` which are the convex polygons making up the shape
+     *  - Takes in `bounds` which is the box they're fitted to
+     *  - Takes in `theta` which is how far the box is turned clockwise about
+     *    its middle, in radians
+     *
+     *  - Returns the shape in screen space
+     */
+    pub fn fitted(outlines: &[Outline], bounds: Rect, theta: f64) -> Shape {
+        let (w, h) = (bounds.width() as f64, bounds.height() as f64);
+        let (mid_x, mid_y) = (bounds.x() as f64 + w / 2.0, bounds.y() as f64 + h / 2.0);
+        let (sin, cos) = theta.sin_cos();
+
+        let parts = outlines
+            .iter()
+            .map(|outline| {
+                outline
+                    .iter()
+                    .map(|(u, v)| {
+                        let (x, y) = ((u - 0.5) * w, (v - 0.5) * h);
+                        (mid_x + x * cos - y * sin, mid_y + x * sin + y * cos)
+                    })
+                    .collect()
+            })
+            .collect();
+        Shape { parts }
+    }
+
+    // Params: box, how far it's turned clockwise about its middle in radians
+    // Returns: the box as an oriented bounding box
+    pub fn rect(bounds: Rect, theta: f64) -> Shape {
+        Shape::fitted(&[BOX], bounds, theta)
+    }
+
+    // Params: points along a line, None where it's broken
+    // Returns: the line as a shape, one part for each piece between two points
+    pub fn polyline(points: &[Option<(f64, f64)>]) -> Shape {
+        let parts = points
+            .windows(2)
+            .filter_map(|pair| match pair {
+                [Some(a), Some(b)] => Some(vec![*a, *b]),
+                _ => None,
+            })
+            .collect();
+        Shape { parts }
+    }
+
+    // Params: how far to move the shape, (right, down)
+    // Returns: the shape moved over by that much
+    pub fn translated(&self, by: (f64, f64)) -> Shape {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| part.iter().map(|(x, y)| (x + by.0, y + by.1)).collect())
+            .collect();
+        Shape { parts }
+    }
+
+    // Returns: y of the highest point of the shape, None if it has no parts
+    pub fn top(&self) -> Option<f64> {
+        self.parts
+            .iter()
+            .flatten()
+            .map(|(_, y)| *y)
+            .reduce(f64::min)
+    }
+
+    // Params: point in screen space
+    // Returns: true if the point is inside or on the edge of the shape
+    pub fn contains_point(&self, point: Point) -> bool {
+        let (px, py) = (point.x() as f64, point.y() as f64);
+        self.parts.iter().any(|part| {
+            let mut side = 0.0;
+            for (i, a) in part.iter().enumerate() {
+                let b = part[(i + 1) % part.len()];
+                let cross = (b.0 - a.0) * (py - a.1) - (b.1 - a.1) * (px - a.0);
+                // Outside once it's on both sides of the edges
+                if cross * side < 0.0 {
+                    return false;
+                }
+                if cross != 0.0 {
+                    side = cross;
+                }
+            }
+            true
+        })
+    }
+
+    // Params: shape to test against
+    // Returns: the deepest overlap between any part of this shape and any
+    //          part of the other, None if they don't overlap
+    pub fn collide(&self, other: &Shape) -> Option<Contact> {
+        let mut deepest: Option<Contact> = None;
+        for a in self.parts.iter() {
+            for b in other.parts.iter() {
+                if let Some(contact) = separating_axis(a, b) {
+                    if deepest.is_none_or(|d| contact.depth > d.depth) {
+                        deepest = Some(contact);
+                    }
+                }
+            }
+        }
+        deepest
+    }
+
+    // Params: how far this shape moves, (right, down), shape it could hit,
+    //         which stays still
+    // Returns: the first time any part of this shape touches any part of the
+    //          other, None if it gets through without touching. Shapes already
+    //          overlapping touch at time 0
+    pub fn sweep(&self, motion: (f64, f64), other: &Shape) -> Option<Impact> {
+        let mut first: Option<Impact> = None;
+        for a in self.parts.iter() {
+            for b in other.parts.iter() {
+                if let Some(impact) = swept_separating_axis(a, b, motion) {
+                    if first.is_none_or(|f| impact.time < f.time) {
+                        first = Some(impact);
+                    }
+                }
+            }
+        }
+        first
+    }
+}
+
+// Which way is up for something turned clockwise by theta, as a unit vector
+pub fn up(theta: f64) -> (f64, f64) {
+    (theta.sin(), -theta.cos())
+}
+
+// Separating axis test between two convex polygons
+// Params: polygons a and b
+// Returns: the overlap, pushing a out of b, None if there's a gap between them
+fn separating_axis(a: &[(f64, f64)], b: &[(f64, f64)]) -> Option<Contact> {
+    let mut best: Option<Contact> = None;
+    for polygon in [a, b] {
+        for (i, p) in polygon.iter().enumerate() {
+            let q = polygon[(i + 1) % polygon.len()];
+            let (ex, ey) = (q.0 - p.0, q.1 - p.1);
+            let len = (ex * ex + ey * ey).sqrt();
+            if len == 0.0 {
+                continue;
+            }
+            let axis = (-ey / len, ex / len);
+
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            // a is pushed whichever way along the axis gets it out sooner
+            let (depth, normal) = if a_max - b_min < b_max - a_min {
+                (a_max - b_min, (-axis.0, -axis.1))
+            } else {
+                (b_max - a_min, axis)
+            };
+            // Touching edges don't count, same as Rect::has_intersection
+            if depth <= 0.0 {
+                return None;
+            }
+
+            if best.is_none_or(|c| depth < c.depth) {
+                best = Some(Contact { normal, depth });
+            }
+        }
+    }
+    best
+}
+
+// Separating axis test between a moving and a still convex polygon
+// Params: polygon a, how far it moves, polygon b
+// Returns: when a first touches b, None if it doesn't during the move
+fn swept_separating_axis(a: &[(f64, f64)], b: &[(f64, f64)], motion: (f64, f64)) -> Option<Impact> {
+    let mut enter = f64::MIN;
+    let mut exit = f64::MAX;
+    let mut normal = (0.0, 0.0);
+    for polygon in [a, b] {
+        for (i, p) in polygon.iter().enumerate() {
+            let q = polygon[(i + 1) % polygon.len()];
+            let (ex, ey) = (q.0 - p.0, q.1 - p.1);
+            let len = (ex * ex + ey * ey).sqrt();
+            if len == 0.0 {
+                continue;
+            }
+            let axis = (-ey / len, ex / len);
+
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            let speed = motion.0 * axis.0 + motion.1 * axis.1;
+            if speed == 0.0 {
+                // Never closes the gap along this axis
+                if a_max <= b_min || b_max <= a_min {
+                    return None;
+                }
+                continue;
+            }
+
+            // Stretch of the move a overlaps b for along this axis
+            let (from, to) = ((b_min - a_max) / speed, (b_max - a_min) / speed);
+            let (from, to) = if from < to { (from, to) } else { (to, from) };
+            if from > enter {
+                enter = from;
+                normal = if speed > 0.0 {
+                    (-axis.0, -axis.1)
+                } else {
+                    axis
+                };
+            }
+            exit = exit.min(to);
+        }
+    }
+
+    if enter >= exit || enter > 1.0 || exit <= 0.0 {
+        return None;
+    }
+    if enter < 0.0 {
+        // Already overlapping before moving
+        return separating_axis(a, b).map(|contact| Impact {
+            time: 0.0,
+            normal: contact.normal,
+        });
+    }
+    Some(Impact {
+        time: enter,
+        normal,
+    })
+}
+
+// Params: polygon, unit axis
+// Returns: lowest and highest the polygon reaches along the axis
+fn project(polygon: &[(f64, f64)], axis: (f64, f64)) -> (f64, f64) {
+    polygon
+        .iter()
+        .map(|(x, y)| x * axis.0 + y * axis.1)
+        .fold((f64::MAX, f64::MIN), |(min, max), d| {
+            (min.min(d), max.max(d))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::f64::consts::FRAC_PI_4;
+
+    // Two directions differ by less than rounding
+    fn same_direction(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    fn square(x: i32, y: i32) -> Rect {
+        Rect::new(x, y, 100, 100)
+    }
+
+    #[test]
+    fn turned_box_reaches_further() {
+        // 20 apart square on, a turned box's corner reaches over the gap
+        let still = Shape::rect(square(120, 0), 0.0);
+        assert_eq!(Shape::rect(square(0, 0), 0.0).collide(&still), None);
+        assert!(Shape::rect(square(0, 0), FRAC_PI_4)
+            .collide(&still)
+            .is_some());
+    }
+
+    #[test]
+    fn turned_boxes_apart() {
+        // Half a turned square's diagonal is about 70.7, so 30 apart is clear
+        let a = Shape::rect(square(0, 0), FRAC_PI_4);
+        let b = Shape::rect(square(130, 0), 0.0);
+        assert_eq!(a.collide(&b), None);
+        assert_eq!(b.collide(&a), None);
+    }
+
+    #[test]
+    fn side_hit_pushes_back() {
+        let a = Shape::rect(square(0, 0), 0.0);
+        let b = Shape::rect(square(90, 0), 0.0);
+        let contact = a.collide(&b).unwrap();
+        assert!(same_direction(contact.normal, (-1.0, 0.0)), "{:?}", contact);
+        assert!((contact.depth - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn top_hit_pushes_up() {
+        let a = Shape::rect(square(0, -90), 0.0);
+        let b = Shape::rect(square(0, 0), 0.0);
+        let contact = a.collide(&b).unwrap();
+        assert!(same_direction(contact.normal, (0.0, -1.0)), "{:?}", contact);
+        assert!((contact.depth - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn touching_edges_dont_collide() {
+        let a = Shape::rect(square(0, 0), 0.0);
+        assert_eq!(a.collide(&Shape::rect(square(100, 0), 0.0)), None);
+        assert_eq!(a.collide(&Shape::rect(square(0, 100), 0.0)), None);
+        assert_eq!(a.collide(&Shape::rect(square(100, 100), 0.0)), None);
+    }
+
+    #[test]
+    fn fast_sweep_hits_thin_obstacle() {
+        // Clear of the post both before and after the move, but not during
+        let a = Shape::rect(square(0, 0), 0.0);
+        let post = Shape::rect(Rect::new(300, 0, 10, 100), 0.0);
+        let motion = (500.0, 0.0);
+        assert_eq!(a.collide(&post), None);
+        assert_eq!(a.translated(motion).collide(&post), None);
+
+        let impact = a.sweep(motion, &post).unwrap();
+        assert!(impact.time > 0.0 && impact.time < 1.0, "{:?}", impact);
+        assert!((impact.time - 0.4).abs() < 1e-9);
+        assert!(same_direction(impact.normal, (-1.0, 0.0)), "{:?}", impact);
+    }
+
+    #[test]
+    fn sweep_past_obstacle_misses() {
+        let a = Shape::rect(square(0, 0), 0.0);
+        let post = Shape::rect(Rect::new(300, 150, 10, 100), 0.0);
+        assert_eq!(a.sweep((500.0, 0.0), &post), None);
+        // Stops short of it
+        assert_eq!(
+            a.sweep((150.0, 0.0), &Shape::rect(square(300, 0), 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn sweep_from_overlapping_hits_at_start() {
+        let a = Shape::rect(square(0, 0), 0.0);
+        let b = Shape::rect(square(90, 0), 0.0);
+        let impact = a.sweep((50.0, 0.0), &b).unwrap();
+        assert_eq!(impact.time, 0.0);
+        assert!(same_direction(impact.normal, (-1.0, 0.0)), "{:?}", impact);
+    }
+}
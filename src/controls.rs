@@ -0,0 +1,222 @@
+use inf_runner::rect;
+
+use inf_runner::assets::Assets;
+
+use inf_runner::audio::MusicTrack;
+
+use inf_runner::input::Action;
+use inf_runner::input::Binding;
+use inf_runner::input::Bindings;
+
+use inf_runner::utils::menu_row_at;
+
+use inf_runner::Game;
+use inf_runner::GameState;
+use inf_runner::GameStatus;
+use inf_runner::SDLCore;
+
+use inf_runner::timestep::FixedTimestep;
+use inf_runner::timestep::TICK_RATE;
+
+use sdl2::event::Event;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::TextureQuery;
+
+const ORANGE: Color = Color::RGBA(230, 150, 25, 255);
+const SILVER: Color = Color::RGBA(119, 120, 123, 255);
+const WHITE: Color = Color::RGBA(255, 255, 255, 255);
+
+const ROW_H: u32 = 34;
+const ROW_SPACING: i32 = 39;
+const FIRST_ROW_Y: i32 = 110;
+
+// Rebinding screen, one row per action plus a reset row at the bottom
+pub struct Controls;
+
+impl Game for Controls {
+    fn init() -> Result<Self, String> {
+        Ok(Controls {})
+    }
+
+    fn run(&mut self, core: &mut SDLCore, assets: &Assets) -> Result<GameState, String> {
+        core.wincan.set_blend_mode(sdl2::render::BlendMode::Blend);
+
+        let texture_creator = core.wincan.texture_creator();
+        let font = assets.font("ui")?;
+
+        core.audio.play_music(MusicTrack::Title);
+
+        let rows = Action::ALL.len() + 1;
+        let mut selected = 0;
+        // True while waiting for the new input for the selected action
+        let mut waiting = false;
+
+        let next_status;
+
+        let mut timestep = FixedTimestep::new(TICK_RATE);
+        timestep.set_frame_cap(core.settings.frame_cap());
+
+        'gameloop: loop {
+            timestep.begin_frame();
+
+            for event in core.poll_events() {
+                if let Event::Quit { .. } = event {
+                    next_status = Some(GameStatus::Credits);
+                    break 'gameloop;
+                }
+
+                if waiting {
+                    // The next key or button pressed becomes the binding
+                    let input = match event {
+                        Event::KeyDown {
+                            keycode: Some(k),
+                            repeat: false,
+                            ..
+                        } => Some(Binding::Key(k)),
+                        Event::ControllerButtonDown { button, .. } => Some(Binding::Button(button)),
+                        _ => None,
+                    };
+                    if let Some(input) = input {
+                        let mut bindings = core.input.bindings().clone();
+                        bindings.rebind(Action::ALL[selected], input);
+                        if let Err(e) = core.input.set_bindings(bindings) {
+                            println!("\n\t\tFailed to save controls: {}", e);
+                        }
+                        waiting = false;
+                    }
+                    // Still let the input manager see controllers connecting
+                    core.input.actions(&event);
+                    continue;
+                }
+
+                // Hovering the mouse over a row selects it, clicking confirms it
+                let mut clicked = false;
+                match event {
+                    Event::MouseMotion { y, .. } => {
+                        if let Some(row) = menu_row_at(y, FIRST_ROW_Y, ROW_SPACING, ROW_H, rows) {
+                            selected = row;
+                        }
+                    }
+                    Event::MouseButtonDown {
+                        mouse_btn: MouseButton::Left,
+                        y,
+                        ..
+                    } => {
+                        if let Some(row) = menu_row_at(y, FIRST_ROW_Y, ROW_SPACING, ROW_H, rows) {
+                            selected = row;
+                            clicked = true;
+                        }
+                    }
+                    _ => {}
+                }
+                let mut actions = core.input.pressed_actions(&event);
+                if clicked {
+                    actions.push(Action::Confirm);
+                }
+
+                for action in actions {
+                    match action {
+                        Action::Up => {
+                            selected = (selected + rows - 1) % rows;
+                            break;
+                        }
+                        Action::Down => {
+                            selected = (selected + 1) % rows;
+                            break;
+                        }
+                        Action::Confirm => {
+                            if selected < Action::ALL.len() {
+                                waiting = true;
+                            } else if let Err(e) = core.input.set_bindings(Bindings::default()) {
+                                println!("\n\t\tFailed to save controls: {}", e);
+                            }
+                            break;
+                        }
+                        Action::Back => {
+                            next_status = Some(GameStatus::Main);
+                            break 'gameloop;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            /* ~~~~~~ Draw ~~~~~~ */
+            core.wincan.set_draw_color(SILVER);
+            core.wincan.clear();
+
+            let bindings = core.input.bindings();
+
+            let mut lines = vec![(String::from("Controls"), ORANGE, 100, 10, 80)];
+            for (i, action) in Action::ALL.iter().enumerate() {
+                let inputs: Vec<String> = bindings
+                    .get(*action)
+                    .iter()
+                    .map(|binding| binding.display_name())
+                    .collect();
+                let text = if waiting && i == selected {
+                    format!("{:<13} Press a key or button...", action.label())
+                } else {
+                    format!("{:<13} {}", action.label(), inputs.join(", "))
+                };
+                let color = if i == selected { WHITE } else { ORANGE };
+                lines.push((
+                    text,
+                    color,
+                    100,
+                    FIRST_ROW_Y + i as i32 * ROW_SPACING,
+                    ROW_H,
+                ));
+            }
+            let reset_color = if selected == Action::ALL.len() {
+                WHITE
+            } else {
+                ORANGE
+            };
+            lines.push((
+                String::from("Reset to defaults"),
+                reset_color,
+                100,
+                FIRST_ROW_Y + Action::ALL.len() as i32 * ROW_SPACING,
+                ROW_H,
+            ));
+            lines.push((
+                format!(
+                    "{} - Rebind   {} - Main menu",
+                    bindings.describe(Action::Confirm),
+                    bindings.describe(Action::Back)
+                ),
+                ORANGE,
+                100,
+                670,
+                ROW_H,
+            ));
+
+            for (text, color, x, y, h) in lines.iter() {
+                let surface = font
+                    .render(text)
+                    .blended(*color)
+                    .map_err(|e| e.to_string())?;
+                let texture = texture_creator
+                    .create_texture_from_surface(&surface)
+                    .map_err(|e| e.to_string())?;
+                let TextureQuery { width, height, .. } = texture.query();
+                core.wincan
+                    .copy(&texture, None, Some(rect!(*x, *y, width * *h / height, *h)))?;
+            }
+
+            core.wincan.present();
+
+            // Nothing here animates, ticks are only used to pace frames
+            while timestep.tick() {}
+            timestep.end_frame();
+        }
+
+        Ok(GameState {
+            status: next_status,
+            score: 0,
+        })
+    }
+}
@@ -1,27 +1,30 @@
-use crate::rect;
+use inf_runner::rect;
+
+use inf_runner::assets::Assets;
 
 use inf_runner::Game;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
 use inf_runner::SDLCore;
+use inf_runner::CAM_H;
+use inf_runner::CAM_W;
+
+use inf_runner::input::Action;
 
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use inf_runner::audio::MusicTrack;
+
+use inf_runner::timestep::FixedTimestep;
+use inf_runner::timestep::TICK_RATE;
 
 use sdl2::event::Event;
-use sdl2::image::LoadTexture;
-use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::Texture;
 use sdl2::render::TextureQuery;
 
-const FPS: f64 = 60.0;
-const FRAME_TIME: f64 = 1.0 / FPS as f64;
-
-const CAM_W: u32 = 1280;
-const CAM_H: u32 = 720;
-const MOVE_PER_FRAME: u32 = 2;
+const MOVE_PER_TICK: u32 = 2;
+// Ticks each name stays on screen after scrolling up
+const HOLD_TICKS: u32 = 120;
 const ORANGE: Color = Color::RGBA(230, 150, 25, 255);
 const SILVER: Color = Color::RGBA(119, 120, 123, 255);
 
@@ -30,11 +33,11 @@ pub struct Credits;
 struct Headshot<'a> {
     pos: Rect,
     src: Rect,
-    texture: Texture<'a>,
+    texture: &'a Texture<'a>,
 }
 
 impl<'a> Headshot<'a> {
-    fn new(pos: Rect, texture: Texture<'a>) -> Headshot {
+    fn new(pos: Rect, texture: &'a Texture<'a>) -> Headshot<'a> {
         let src = rect!(0, 0, 400, 400);
         Headshot { pos, src, texture }
     }
@@ -47,8 +50,8 @@ impl<'a> Headshot<'a> {
         self.src
     }
 
-    fn texture(&self) -> &Texture {
-        &self.texture
+    fn texture(&self) -> &Texture<'_> {
+        self.texture
     }
 }
 
@@ -57,15 +60,11 @@ impl Game for Credits {
         Ok(Credits {})
     }
 
-    fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String> {
+    fn run(&mut self, core: &mut SDLCore, assets: &Assets) -> Result<GameState, String> {
         let mut count = CAM_H;
 
         /********************* TEXTURES AND HEADSHOTS ******************/
-
-        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
-
-        let mut font = ttf_context.load_font("./assets/DroidSansMono.ttf", 128)?;
-        font.set_style(sdl2::ttf::FontStyle::BOLD);
+        let font = assets.font("ui")?;
 
         let texture_creator = core.wincan.texture_creator();
 
@@ -79,7 +78,7 @@ impl Game for Credits {
 
         let caleb_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/caleb_hs.jpg")?,
+            assets.texture("headshot/caleb")?,
         );
 
         let surface = font
@@ -92,7 +91,7 @@ impl Game for Credits {
 
         let dane_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/dane_hs.jpg")?,
+            assets.texture("headshot/dane")?,
         );
 
         let surface = font
@@ -105,7 +104,7 @@ impl Game for Credits {
 
         let andrew_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/andrew_hs.png")?,
+            assets.texture("headshot/andrew")?,
         );
 
         let surface = font
@@ -118,7 +117,7 @@ impl Game for Credits {
 
         let benjamin_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/benjamin_hs.jpg")?,
+            assets.texture("headshot/benjamin")?,
         );
 
         let surface = font
@@ -131,7 +130,7 @@ impl Game for Credits {
 
         let dominic_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/dominic_hs.jpg")?,
+            assets.texture("headshot/dominic")?,
         );
 
         let surface = font
@@ -144,7 +143,7 @@ impl Game for Credits {
 
         let mateen_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/mateen_hs.jpg")?,
+            assets.texture("headshot/mateen")?,
         );
 
         let surface = font
@@ -157,7 +156,7 @@ impl Game for Credits {
 
         let elliot_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/elliot_hs.jpg")?,
+            assets.texture("headshot/elliot")?,
         );
 
         let surface = font
@@ -170,7 +169,7 @@ impl Game for Credits {
 
         let michael_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/michael_hs.jpg")?,
+            assets.texture("headshot/michael")?,
         );
 
         let team = [
@@ -200,75 +199,51 @@ impl Game for Credits {
         let mut index = 0;
         let mut next_status = GameStatus::Main;
 
-        // FPS tracking
-        let mut _all_frames: i32 = 0;
-        let mut last_raw_time;
-        let mut last_measurement_time = Instant::now();
+        // Ticks the current name has been held at the top of the screen
+        let mut held = 0;
+
+        core.audio.play_music(MusicTrack::Credits);
+
+        let mut timestep = FixedTimestep::new(TICK_RATE);
+        timestep.set_frame_cap(core.settings.frame_cap());
 
         'gameloop: loop {
-            last_raw_time = Instant::now(); // FPS tracking
-            for event in core.event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape | Keycode::Q),
-                        ..
-                    } => break 'gameloop,
-                    Event::KeyDown {
-                        keycode: Some(Keycode::R),
-                        ..
-                    } => {
-                        next_status = GameStatus::Game;
-                        break 'gameloop;
+            timestep.begin_frame();
+            for event in core.poll_events() {
+                if let Event::Quit { .. } = event {
+                    break 'gameloop;
+                }
+                for action in core.input.pressed_actions(&event) {
+                    match action {
+                        Action::Back => break 'gameloop,
+                        Action::Restart => {
+                            next_status = GameStatus::Game;
+                            break 'gameloop;
+                        }
+                        _ => {}
                     }
-                    _ => {}
                 }
             }
-            let mut i = 0;
-            while i < 120 {
-                i += 1;
-                if count <= MOVE_PER_FRAME + 1 {
-                    count = MOVE_PER_FRAME + 1;
-                    count = self.credit_text(core, &count, &team[index], &200, &hs[index])?;
+            // Scroll the current name up, hold it, then move on to the next
+            while timestep.tick() {
+                if count > MOVE_PER_TICK + 1 {
+                    count -= MOVE_PER_TICK;
                 } else {
-                    count = self.credit_text(core, &count, &team[index], &200, &hs[index])?;
-                    break;
-                }
-            }
-            if i == 120 {
-                count = CAM_H;
-                index += 1;
-                if index == team.len() {
-                    break;
+                    held += 1;
+                    if held == HOLD_TICKS {
+                        held = 0;
+                        count = CAM_H;
+                        index += 1;
+                        if index == team.len() {
+                            break 'gameloop;
+                        }
+                    }
                 }
-            } else {
-                continue;
             }
 
-            /* ~~~~~~ FPS Calculation ~~~~~~ */
-            // Time taken to display the last frame
-            let raw_frame_time = last_raw_time.elapsed().as_secs_f64();
-            let delay = FRAME_TIME - raw_frame_time;
-            // If the amount of time to display the last frame was less than expected, sleep
-            // until the expected amount of time has passed
-            if delay > 0.0 {
-                // Using sleep to delay will always cause slightly more delay than intended due
-                // to CPU scheduling; possibly find a better way to delay
-                sleep(Duration::from_secs_f64(delay));
-            }
-            _all_frames += 1;
-            let time_since_last_measurement = last_measurement_time.elapsed();
-            // Measures the FPS once per second
-            if time_since_last_measurement > Duration::from_secs(1) {
-                //println!("{} FPS", _all_frames);
-                // println!(
-                //     "Average FPS: {:.2}",
-                //     (_all_frames as f64) / time_since_last_measurement.as_secs_f64()
-                // );
-                _all_frames = 0;
-                last_measurement_time = Instant::now();
-            }
-            /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+            self.credit_text(core, &count, &team[index], &200, &hs[index])?;
+
+            timestep.end_frame();
         }
 
         Ok(GameState {
@@ -286,8 +261,8 @@ impl Credits {
         texture: &sdl2::render::Texture,
         padding: &u32,
         image: &Headshot,
-    ) -> Result<u32, String> {
-        let m_count = count - MOVE_PER_FRAME;
+    ) -> Result<(), String> {
+        let m_count = *count;
         //Removal of this and changing instances to just `padding` causes it to break
         // for some reason
         let m_padding = padding;
@@ -333,6 +308,6 @@ impl Credits {
         // Only one present needed per frame
         core.wincan.present();
 
-        Ok(m_count)
+        Ok(())
     }
 }
@@ -0,0 +1,253 @@
+// How hard a run gets the further the player goes
+//
+// Each preset is a curve of keyframes by distance travelled. Between two
+// keyframes every value is blended linearly, past the last one the run
+// stays as hard as the last keyframe. Tuning a preset is just editing its
+// table below; `--difficulty-curve` prints the result.
+
+use std::fmt;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+// Everything the difficulty controls at one point in a run
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DifficultyLevel {
+    pub spawn_gap: f64,            // Spawn timer reset after each object
+    pub obstacle_weight: f64,      // Scales the chance of obstacles against pickups
+    pub tall_obstacle_weight: f64, // Scales statues and chests against benches
+    pub power_weight: f64,         // Scales the chance of power-ups
+    pub water_weight: f64,         // Scales the chance of water segments
+    pub amplitude: f64,            // Scales the height of hills
+    pub frequency: f64,            // Scales how quickly hills rise and fall
+    pub formation_chance: f64,     // Chance of a coin coming as a formation
+}
+
+// (metres travelled, level reached there)
+type Keyframe = (f64, DifficultyLevel);
+
+const EASY: [Keyframe; 4] = [
+    (0.0, level(850.0, 0.8, 0.5, 1.4, 0.6, 0.5, 0.8, 0.3)),
+    (500.0, level(750.0, 1.0, 0.7, 1.2, 0.8, 0.7, 0.9, 0.35)),
+    (2000.0, level(600.0, 1.2, 0.9, 1.0, 1.0, 0.9, 1.0, 0.4)),
+    (5000.0, level(450.0, 1.4, 1.0, 0.9, 1.1, 1.0, 1.1, 0.45)),
+];
+
+// Close to the old score based spawn gaps, which went from 750 down to 300
+const NORMAL: [Keyframe; 5] = [
+    (0.0, level(750.0, 1.0, 0.7, 1.0, 0.8, 0.7, 0.9, 0.4)),
+    (250.0, level(650.0, 1.1, 0.9, 0.9, 1.0, 0.85, 1.0, 0.4)),
+    (1000.0, level(500.0, 1.3, 1.0, 0.75, 1.2, 1.0, 1.1, 0.45)),
+    (3000.0, level(350.0, 1.6, 1.2, 0.6, 1.4, 1.15, 1.2, 0.5)),
+    (5000.0, level(300.0, 1.8, 1.3, 0.5, 1.5, 1.25, 1.25, 0.55)),
+];
+
+const HARD: [Keyframe; 4] = [
+    (0.0, level(600.0, 1.3, 1.0, 0.8, 1.0, 0.9, 1.0, 0.35)),
+    (500.0, level(450.0, 1.6, 1.2, 0.6, 1.3, 1.1, 1.15, 0.45)),
+    (2000.0, level(300.0, 2.0, 1.4, 0.45, 1.6, 1.3, 1.3, 0.55)),
+    (4000.0, level(220.0, 2.4, 1.6, 0.35, 1.8, 1.4, 1.4, 0.6)),
+];
+
+// Builds a level, fields in the order they're declared in
+#[allow(clippy::too_many_arguments)]
+const fn level(
+    spawn_gap: f64,
+    obstacle_weight: f64,
+    tall_obstacle_weight: f64,
+    power_weight: f64,
+    water_weight: f64,
+    amplitude: f64,
+    frequency: f64,
+    formation_chance: f64,
+) -> DifficultyLevel {
+    DifficultyLevel {
+        spawn_gap,
+        obstacle_weight,
+        tall_obstacle_weight,
+        power_weight,
+        water_weight,
+        amplitude,
+        frequency,
+        formation_chance,
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL
+            .iter()
+            .copied()
+            .find(|difficulty| difficulty.name() == name)
+    }
+
+    // Next or previous preset, wrapping around
+    // Params: 1 for the next preset, -1 for the previous
+    pub fn cycle(&self, step: i32) -> Difficulty {
+        let count = Difficulty::ALL.len() as i32;
+        let i = Difficulty::ALL.iter().position(|d| d == self).unwrap_or(0) as i32;
+        Difficulty::ALL[(i + step).rem_euclid(count) as usize]
+    }
+
+    fn curve(&self) -> &'static [Keyframe] {
+        match self {
+            Difficulty::Easy => &EASY,
+            Difficulty::Normal => &NORMAL,
+            Difficulty::Hard => &HARD,
+        }
+    }
+
+    // Looks up how hard the run is at a point
+    // Params: metres travelled so far
+    // Returns: the level blended from the keyframes either side
+    pub fn at(&self, distance: f64) -> DifficultyLevel {
+        let curve = self.curve();
+        let next = match curve.iter().position(|(at, _)| *at > distance) {
+            Some(0) => return curve[0].1,
+            Some(next) => next,
+            None => return curve[curve.len() - 1].1,
+        };
+
+        let (from_at, from) = curve[next - 1];
+        let (to_at, to) = curve[next];
+        let t = (distance - from_at) / (to_at - from_at);
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        DifficultyLevel {
+            spawn_gap: mix(from.spawn_gap, to.spawn_gap),
+            obstacle_weight: mix(from.obstacle_weight, to.obstacle_weight),
+            tall_obstacle_weight: mix(from.tall_obstacle_weight, to.tall_obstacle_weight),
+            power_weight: mix(from.power_weight, to.power_weight),
+            water_weight: mix(from.water_weight, to.water_weight),
+            amplitude: mix(from.amplitude, to.amplitude),
+            frequency: mix(from.frequency, to.frequency),
+            formation_chance: mix(from.formation_chance, to.formation_chance),
+        }
+    }
+
+    // Table of the curve for tuning, one row per step
+    // Params: metres between rows, last distance to show
+    // Returns: the table as text
+    pub fn curve_table(&self, step: f64, until: f64) -> String {
+        let mut table = format!(
+            "{} difficulty\n{:>8} {:>9} {:>9} {:>6} {:>6} {:>6} {:>6} {:>6} {:>6}\n",
+            self,
+            "metres",
+            "spawn_gap",
+            "obstacles",
+            "tall",
+            "power",
+            "water",
+            "amp",
+            "freq",
+            "coins"
+        );
+        let mut distance = 0.0;
+        while distance <= until {
+            let l = self.at(distance);
+            table.push_str(&format!(
+                "{:>8.0} {:>9.0} {:>9.2} {:>6.2} {:>6.2} {:>6.2} {:>6.2} {:>6.2} {:>6.2}\n",
+                distance,
+                l.spawn_gap,
+                l.obstacle_weight,
+                l.tall_obstacle_weight,
+                l.power_weight,
+                l.water_weight,
+                l.amplitude,
+                l.frequency,
+                l.formation_chance
+            ));
+            distance += step;
+        }
+        table
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.name();
+        write!(f, "{}{}", name[..1].to_uppercase(), &name[1..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Levels every 25 metres up to well past the last keyframe
+    fn levels(difficulty: Difficulty) -> Vec<DifficultyLevel> {
+        (0..=400).map(|i| difficulty.at(i as f64 * 25.0)).collect()
+    }
+
+    #[test]
+    fn only_ever_gets_harder() {
+        for difficulty in Difficulty::ALL {
+            for pair in levels(difficulty).windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                assert!(b.spawn_gap <= a.spawn_gap, "{}", difficulty);
+                assert!(b.power_weight <= a.power_weight, "{}", difficulty);
+                assert!(b.obstacle_weight >= a.obstacle_weight, "{}", difficulty);
+                assert!(
+                    b.tall_obstacle_weight >= a.tall_obstacle_weight,
+                    "{}",
+                    difficulty
+                );
+                assert!(b.water_weight >= a.water_weight, "{}", difficulty);
+                assert!(b.amplitude >= a.amplitude, "{}", difficulty);
+                assert!(b.frequency >= a.frequency, "{}", difficulty);
+                assert!(b.formation_chance >= a.formation_chance, "{}", difficulty);
+            }
+        }
+    }
+
+    #[test]
+    fn clamped_to_the_first_and_last_keyframes() {
+        for difficulty in Difficulty::ALL {
+            let curve = difficulty.curve();
+            let (first, last) = (curve[0], curve[curve.len() - 1]);
+            assert_eq!(difficulty.at(-100.0), first.1);
+            assert_eq!(difficulty.at(0.0), first.1);
+            assert_eq!(difficulty.at(last.0), last.1);
+            assert_eq!(difficulty.at(last.0 * 10.0), last.1);
+        }
+    }
+
+    #[test]
+    fn blends_linearly_between_keyframes() {
+        for difficulty in Difficulty::ALL {
+            for pair in difficulty.curve().windows(2) {
+                let ((from_at, from), (to_at, to)) = (pair[0], pair[1]);
+                assert_eq!(difficulty.at(to_at), to);
+                let middle = difficulty.at((from_at + to_at) / 2.0);
+                let expected = (from.spawn_gap + to.spawn_gap) / 2.0;
+                assert!((middle.spawn_gap - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn presets_are_in_order_of_how_hard_they_are() {
+        let (easy, normal, hard) = (
+            levels(Difficulty::Easy),
+            levels(Difficulty::Normal),
+            levels(Difficulty::Hard),
+        );
+        for i in 0..easy.len() {
+            assert!(easy[i].spawn_gap >= normal[i].spawn_gap);
+            assert!(normal[i].spawn_gap >= hard[i].spawn_gap);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Coins laid out in shapes instead of one at a time
+//
+// Formations are laid out from the right edge of the screen with the same
+// ground lookups and jump physics the rest of a run uses, so a line of coins
+// hugs the terrain and every coin of an arc can be collected by jumping at
+// the first one. How often a coin comes as a formation is set by the
+// difficulty curve.
+
+use crate::reachability;
+
+use crate::simulation;
+use crate::simulation::TILE_SIZE;
+
+use crate::proceduralgen::TerrainSegment;
+
+use crate::StaticObject;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use sdl2::rect::Point;
+
+// Distance between the middles of two coins, coins are a tile wide
+const COIN_SPACING: i32 = TILE_SIZE as i32 * 6 / 5;
+
+// Number of coins in each kind of formation
+const LINE_COINS: i32 = 5;
+const ARC_COINS: usize = 7;
+const COLUMN_COINS: i32 = 3;
+const TRAIL_COINS: i32 = 4;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Formation {
+    Line,   // Along the ground, following its curve
+    Arc,    // Along the path of a jump
+    Column, // Stacked up from the ground
+    Trail,  // Along the ground, with a power-up at the end
+}
+
+impl Formation {
+    // Picks a formation, reward trails being the rarest
+    pub fn choose(rng: &mut StdRng) -> Formation {
+        match rng.gen_range(0..10) {
+            0..=2 => Formation::Line,
+            3..=5 => Formation::Arc,
+            6..=8 => Formation::Column,
+            _ => Formation::Trail,
+        }
+    }
+
+    /*  Lays out the formation, starting at a point and going right
+     *
+     *  - Takes in `terrain` which is the ground to lay it on
+     *  - Takes in `start_x` which is where the first object's middle goes
+     *  - Takes in `speed` which is the player's speed, arcs follow a jump
+     *    taken at it
+     *
+     *  - Returns each object with the point its bottom middle goes at.
+     *    Nothing is put over a pit
+     */
+    pub fn lay_out(
+        &self,
+        terrain: &[TerrainSegment],
+        start_x: i32,
+        speed: f64,
+    ) -> Vec<(StaticObject, Point)> {
+        match self {
+            Formation::Line => along_ground(terrain, start_x, LINE_COINS),
+            Formation::Arc => along_jump(terrain, start_x, speed),
+            Formation::Column => match simulation::get_ground_coord(terrain, start_x) {
+                Some(ground) => (0..COLUMN_COINS)
+                    .map(|i| {
+                        let bottom = Point::new(start_x, ground.y() - i * TILE_SIZE as i32);
+                        (StaticObject::Coin, bottom)
+                    })
+                    .collect(),
+                None => Vec::new(),
+            },
+            Formation::Trail => {
+                let mut trail = along_ground(terrain, start_x, TRAIL_COINS + 1);
+                // The last spot on the trail is the reward, if it isn't over a pit
+                let reward_x = start_x + TRAIL_COINS * COIN_SPACING;
+                if let Some(last) = trail.last_mut() {
+                    if last.1.x() == reward_x {
+                        last.0 = StaticObject::Power;
+                    }
+                }
+                trail
+            }
+        }
+    }
+}
+
+// Coins on the ground, evenly spaced
+fn along_ground(
+    terrain: &[TerrainSegment],
+    start_x: i32,
+    count: i32,
+) -> Vec<(StaticObject, Point)> {
+    (0..count)
+        .filter_map(|i| simulation::get_ground_coord(terrain, start_x + i * COIN_SPACING))
+        .map(|ground| (StaticObject::Coin, ground))
+        .collect()
+}
+
+// Coins where the middle of the player passes on a jump from start_x,
+// falling back to a line if there's nowhere to jump from
+fn along_jump(terrain: &[TerrainSegment], start_x: i32, speed: f64) -> Vec<(StaticObject, Point)> {
+    let half = TILE_SIZE as i32 / 2;
+    let path = reachability::jump_path(terrain, start_x - half, speed);
+    if path.is_empty() {
+        return along_ground(terrain, start_x, LINE_COINS);
+    }
+
+    let mut coins = Vec::new();
+    let mut next_x = start_x;
+    for (x, y) in path {
+        if x >= next_x && coins.len() < ARC_COINS {
+            coins.push((StaticObject::Coin, Point::new(x, y + half)));
+            next_x = x + COIN_SPACING;
+        }
+    }
+    coins
+}
@@ -0,0 +1,208 @@
+// Rails the player can grind along
+//
+// A rail is an obstacle that runs a set height above the ground, found with
+// the same get_ground_coord lookups as everything else on it, so it bends
+// with the terrain without keeping its own copy of its shape. Coming down
+// onto one while holding grind puts the player's board on it, and they
+// slide along with far less friction than the ground has until they jump
+// off, let go, or run out of rail.
+//
+// Balance tips further the longer it's left alone, faster on thinner rails
+// and with a jolt wherever the rail bends. Leaning back or ahead pushes it
+// the other way; tipping all the way over throws the player off the rail at
+// an angle they'll crash at unless they flip out of it.
+
+use crate::physics::Entity;
+use crate::physics::Obstacle;
+use crate::physics::MAX_UPRIGHT_ANGLE;
+
+use crate::proceduralgen::TerrainSegment;
+
+use crate::simulation;
+use crate::simulation::TILE_SIZE;
+
+use crate::ObstacleType;
+
+use sdl2::rect::Point;
+
+use std::f64::consts::PI;
+
+// How far balance tips each tick on its own, whichever way it's leaning
+const DRIFT: f64 = 0.01;
+// How far one lean moves balance
+const LEAN: f64 = 0.4;
+// How far balance is thrown by the rail bending, per radian it turns
+const JOLT: f64 = 3.0;
+// How far the player tilts on the rail at the edge of their balance
+const TILT: f64 = MAX_UPRIGHT_ANGLE / 2.0;
+// How far past upright the player is thrown off the rail by a bail, enough
+// to land on their side
+const BAIL_TILT: f64 = MAX_UPRIGHT_ANGLE * 1.5;
+// How far a rail can move up or down under the player in a tick and still
+// be the rail they're on
+pub const RAIL_REACH: i32 = (TILE_SIZE / 5) as i32;
+
+// How a type of rail is built and grinds
+pub struct Rail {
+    pub name: &'static str, // Shown in the name of a grind on it
+    pub height: i32,        // How far above the ground it runs
+    pub width: u32,         // How far along the ground it runs
+    pub friction: f64,      // Coefficient of kinetic friction grinding it
+    pub wobble: f64,        // Share of balance it tips by each tick
+    pub points: f64,        // Score for every tick spent on it
+}
+
+// Metal bar on posts, waist high
+const HANDRAIL: Rail = Rail {
+    name: "Handrail",
+    height: TILE_SIZE as i32 * 3 / 5,
+    width: TILE_SIZE * 4,
+    friction: 0.01,
+    wobble: 0.02,
+    points: 2.0,
+};
+
+// Concrete edge, low and wide enough to be steady on
+const LEDGE: Rail = Rail {
+    name: "Ledge",
+    height: TILE_SIZE as i32 * 2 / 5,
+    width: TILE_SIZE * 3,
+    friction: 0.02,
+    wobble: 0.01,
+    points: 1.0,
+};
+
+// Cable strung between poles, only reached from a jump
+const POWER_LINE: Rail = Rail {
+    name: "Power line",
+    height: TILE_SIZE as i32 * 11 / 5,
+    width: TILE_SIZE * 6,
+    friction: 0.005,
+    wobble: 0.035,
+    points: 4.0,
+};
+
+impl ObstacleType {
+    // Params: none
+    // Returns: how the obstacle grinds, None if it isn't a rail
+    pub fn rail(&self) -> Option<&'static Rail> {
+        match self {
+            ObstacleType::Handrail => Some(&HANDRAIL),
+            ObstacleType::Ledge => Some(&LEDGE),
+            ObstacleType::PowerLine => Some(&POWER_LINE),
+            _ => None,
+        }
+    }
+}
+
+/*  Finds the highest rail running between two heights at a point on screen
+ *
+ *  - Takes in `obstacles` which may include rails
+ *  - Takes in `terrain` which the rails follow
+ *  - Takes in `x` which is where to look
+ *  - Takes in `top` and `bottom` which are the screen y range to look in
+ *
+ *  - Returns the rail with the point on it at x, None if no rail passes
+ *    through the range there
+ */
+pub fn rail_between(
+    obstacles: &[Obstacle],
+    terrain: &[TerrainSegment],
+    x: i32,
+    top: i32,
+    bottom: i32,
+) -> Option<(&'static Rail, Point)> {
+    obstacles
+        .iter()
+        .filter(|o| o.x() <= x && x < o.x() + o.hitbox().width() as i32)
+        .filter_map(|o| {
+            let rail = o.obstacle_type().rail()?;
+            let ground = simulation::get_ground_coord(terrain, x)?;
+            Some((rail, Point::new(x, ground.y() - rail.height)))
+        })
+        .filter(|(_, point)| top <= point.y() && point.y() <= bottom)
+        .min_by_key(|(_, point)| point.y())
+}
+
+// Checks a rail can be put down, with known ground under all of it
+// Params: terrain, screen x of its left end, rail
+// Returns: true if there's no pit or end of the terrain along it
+pub fn fits(terrain: &[TerrainSegment], left_x: i32, rail: &Rail) -> bool {
+    let end_x = match terrain.last() {
+        Some(last) => last.x() + last.w(),
+        None => return false,
+    };
+    let right_x = left_x + rail.width as i32;
+    right_x < end_x
+        && (left_x..right_x)
+            .step_by(TILE_SIZE as usize / 10)
+            .all(|x| simulation::get_ground_coord(terrain, x).is_some())
+}
+
+// A player on a rail
+pub struct Grind {
+    rail: &'static Rail,
+    angle: f64,   // Slope of the rail where the player was last tick
+    balance: f64, // How far the player is tipping forwards, off past 1 or -1
+    ticks: u32,   // How long they've been on it
+}
+
+impl Grind {
+    // Params: rail landed on, its slope, the player's angle when they landed
+    // Returns: the grind, off balance by however crooked the landing was
+    pub fn new(rail: &'static Rail, angle: f64, theta: f64) -> Grind {
+        // Turned either way from the rail, taking the short way round
+        let crooked = (theta - angle + PI).rem_euclid(2.0 * PI) - PI;
+        Grind {
+            rail,
+            angle,
+            balance: (crooked / MAX_UPRIGHT_ANGLE).clamp(-0.5, 0.5),
+            ticks: 0,
+        }
+    }
+
+    pub fn rail(&self) -> &'static Rail {
+        self.rail
+    }
+
+    pub fn balance(&self) -> f64 {
+        self.balance
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    // Points for the grind so far
+    pub fn score(&self) -> i32 {
+        (self.ticks as f64 * self.rail.points) as i32
+    }
+
+    // Angle the player stands at on the rail, leaning with their balance
+    pub fn theta(&self) -> f64 {
+        self.angle + self.balance * TILT
+    }
+
+    // Angle a bail throws the player off at, further the way they tipped
+    pub fn bail_theta(&self) -> f64 {
+        self.angle + self.balance.signum() * BAIL_TILT
+    }
+
+    // Pushes balance back by a lean
+    // Params: true to lean ahead, false to lean back
+    pub fn lean(&mut self, ahead: bool) {
+        self.balance += if ahead { LEAN } else { -LEAN };
+    }
+
+    // Tips balance on by a tick on the rail
+    // Params: slope of the rail under the player now
+    // Returns: true once the player has lost their balance
+    pub fn tip(&mut self, angle: f64) -> bool {
+        self.balance += self.balance * self.rail.wobble
+            + DRIFT * self.balance.signum()
+            + (angle - self.angle) * JOLT;
+        self.angle = angle;
+        self.ticks += 1;
+        self.balance.abs() >= 1.0
+    }
+}
@@ -0,0 +1,282 @@
+// Persistent table of the best runs
+//
+// Scores are kept in a plain text file in the user's data folder, best
+// first, one run per line:
+//     urban-odyssey-scores 1
+//     <score> <initials> <seed> <date> <distance> <coins>
+// where date is seconds since the unix epoch and distance is in metres.
+
+use crate::utils;
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "urban-odyssey-scores 1";
+const FILE_NAME: &str = "highscores.txt";
+
+// Number of runs kept on the table
+pub const MAX_ENTRIES: usize = 10;
+// Letters of initials entered for a run
+pub const INITIALS_LEN: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: i32,
+    pub seed: u64,
+    pub date: u64,     // Seconds since the unix epoch the run finished at
+    pub distance: u64, // Metres travelled
+    pub coins: u32,    // Coins collected
+}
+
+impl HighScore {
+    // Records a run that just finished, initials are filled in later
+    pub fn new(score: i32, seed: u64, distance: u64, coins: u32) -> HighScore {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        HighScore {
+            initials: String::new(),
+            score,
+            seed,
+            date,
+            distance,
+            coins,
+        }
+    }
+
+    // Date the run finished on as YYYY-MM-DD (UTC)
+    pub fn date_string(&self) -> String {
+        // Days since 1970-01-01 to a civil date, see
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = (self.date / 86400) as i64 + 719468;
+        let era = days / 146097;
+        let doe = days - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}",
+            self.score, self.initials, self.seed, self.date, self.distance, self.coins
+        )
+    }
+
+    fn from_line(line: &str) -> Option<HighScore> {
+        let mut parts = line.split_whitespace();
+        let score = parts.next()?.parse::<i32>().ok()?;
+        let initials = parts.next()?.to_string();
+        let seed = parts.next()?.parse::<u64>().ok()?;
+        let date = parts.next()?.parse::<u64>().ok()?;
+        let distance = parts.next()?.parse::<u64>().ok()?;
+        let coins = parts.next()?.parse::<u32>().ok()?;
+        Some(HighScore {
+            initials,
+            score,
+            seed,
+            date,
+            distance,
+            coins,
+        })
+    }
+}
+
+pub struct HighScoreTable {
+    path: PathBuf,
+    entries: Vec<HighScore>, // Best first, at most MAX_ENTRIES long
+}
+
+impl HighScoreTable {
+    // Starts an empty table that will be saved to path
+    pub fn new(path: &Path) -> HighScoreTable {
+        HighScoreTable {
+            path: path.to_path_buf(),
+            entries: Vec::new(),
+        }
+    }
+
+    // Path of the table kept in the user's data folder
+    pub fn default_path() -> PathBuf {
+        utils::data_dir().join(FILE_NAME)
+    }
+
+    // Loads the table kept in the user's data folder, starting an empty one
+    // if there isn't a file yet
+    pub fn load_default() -> Result<HighScoreTable, String> {
+        HighScoreTable::load(&HighScoreTable::default_path())
+    }
+
+    // Loads the table kept at path, starting an empty one if it doesn't exist
+    pub fn load(path: &Path) -> Result<HighScoreTable, String> {
+        let mut table = HighScoreTable::new(path);
+
+        if !path.exists() {
+            return Ok(table);
+        }
+
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(format!("{}: not a high score file", path.display()));
+        }
+
+        for (i, line) in lines.enumerate() {
+            match HighScore::from_line(line) {
+                Some(entry) => table.entries.push(entry),
+                None => {
+                    return Err(format!(
+                        "{}: bad entry on line {}: {:?}",
+                        path.display(),
+                        i + 2,
+                        line
+                    ))
+                }
+            }
+        }
+
+        table
+            .entries
+            .sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        table.entries.truncate(MAX_ENTRIES);
+        Ok(table)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let mut contents = format!("{}\n", HEADER);
+        for entry in self.entries.iter() {
+            contents.push_str(&entry.to_line());
+            contents.push('\n');
+        }
+
+        fs::write(&self.path, contents).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    // Returns true if a run with this score would make it onto the table
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    // Adds a run to the table, pushing the lowest entry off if it's full
+    // Returns: the run's place on the table starting from 0, None if it
+    // didn't make it
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+
+        // Ties go below the runs that got there first
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes a file for a test to load, named so tests running at the same
+    // time don't share one
+    fn write_file(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("highscore-{}-{}.txt", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    // Loads a file written by write_file, then deletes it
+    fn load_file(name: &str, contents: &str) -> Result<HighScoreTable, String> {
+        let path = write_file(name, contents);
+        let table = HighScoreTable::load(&path);
+        fs::remove_file(&path).unwrap();
+        table
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_table() {
+        let path = std::env::temp_dir().join("highscore-never-written.txt");
+        let table = HighScoreTable::load(&path).unwrap();
+        assert!(table.entries().is_empty());
+    }
+
+    #[test]
+    fn wrong_header_is_rejected() {
+        let err = load_file("header", "urban-odyssey-replay 1\n")
+            .err()
+            .expect("loaded");
+        assert!(err.contains("not a high score file"), "{}", err);
+        assert!(load_file("empty", "").is_err());
+    }
+
+    #[test]
+    fn bad_entries_are_rejected_with_their_line() {
+        for (name, entry) in [
+            ("short", "100 ABC 1 0 20"),
+            ("score", "lots ABC 1 0 20 3"),
+            ("seed", "100 ABC -1 0 20 3"),
+            ("coins", "100 ABC 1 0 20 3.5"),
+        ] {
+            let contents = format!("{}\n300 XYZ 7 0 50 4\n{}\n", HEADER, entry);
+            let err = load_file(name, &contents).err().expect(name);
+            assert!(err.contains("line 3"), "{}: {}", name, err);
+        }
+    }
+
+    #[test]
+    fn entries_come_back_best_first_and_no_more_than_fit() {
+        let mut contents = format!("{}\n\n", HEADER);
+        for score in 1..=MAX_ENTRIES as i32 + 2 {
+            contents.push_str(&format!("{} ABC {} 0 10 1\n", score, score));
+        }
+        let table = load_file("order", &contents).unwrap();
+        let scores: Vec<i32> = table.entries().iter().map(|e| e.score).collect();
+        let best: Vec<i32> = (3..=MAX_ENTRIES as i32 + 2).rev().collect();
+        assert_eq!(scores, best);
+    }
+
+    #[test]
+    fn saved_table_loads_back() {
+        let path = write_file("saved", "");
+        let mut table = HighScoreTable::new(&path);
+        let mut run = HighScore::new(250, 42, 80, 6);
+        run.initials = "JOE".to_string();
+        table.insert(run);
+        table.save().unwrap();
+
+        let loaded = HighScoreTable::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        let entry = &loaded.entries()[0];
+        assert_eq!(
+            (entry.score, entry.initials.as_str(), entry.seed),
+            (250, "JOE", 42)
+        );
+        assert_eq!((entry.distance, entry.coins), (80, 6));
+    }
+}
@@ -0,0 +1,363 @@
+use inf_runner::rect;
+
+use inf_runner::assets::Assets;
+
+use inf_runner::highscore::HighScore;
+use inf_runner::highscore::HighScoreTable;
+use inf_runner::highscore::INITIALS_LEN;
+
+use inf_runner::Game;
+use inf_runner::GameState;
+use inf_runner::GameStatus;
+use inf_runner::SDLCore;
+
+use inf_runner::input::Action;
+
+use inf_runner::audio::MusicTrack;
+
+use inf_runner::timestep::FixedTimestep;
+use inf_runner::timestep::TICK_RATE;
+
+use sdl2::event::Event;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::TextureCreator;
+use sdl2::render::TextureQuery;
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+const ORANGE: Color = Color::RGBA(230, 150, 25, 255);
+const SILVER: Color = Color::RGBA(119, 120, 123, 255);
+const WHITE: Color = Color::RGBA(255, 255, 255, 255);
+
+const ROW_H: u32 = 32;
+const ROW_SPACING: i32 = 46;
+const FIRST_ROW_Y: i32 = 160;
+
+// Left edge of each column of the table, and its heading. Wide enough for
+// the longest value, up to a 20 digit seed
+const COLUMNS: [(i32, &str); 7] = [
+    (60, ""),
+    (120, ""),
+    (190, "Score"),
+    (350, "Distance"),
+    (500, "Coins"),
+    (640, "Date"),
+    (860, "Seed"),
+];
+
+pub struct HighScores {
+    table: HighScoreTable,
+    pending: Option<HighScore>, // Finished run waiting for its initials
+}
+
+impl Game for HighScores {
+    fn init() -> Result<Self, String> {
+        // A missing or broken score file shouldn't stop the game from starting
+        let table = match HighScoreTable::load_default() {
+            Ok(table) => table,
+            Err(e) => {
+                println!(
+                    "\n\t\tCouldn't read high scores, starting a new table: {}",
+                    e
+                );
+                HighScoreTable::new(&HighScoreTable::default_path())
+            }
+        };
+
+        Ok(HighScores {
+            table,
+            pending: None,
+        })
+    }
+
+    fn run(&mut self, core: &mut SDLCore, assets: &Assets) -> Result<GameState, String> {
+        core.wincan.set_blend_mode(sdl2::render::BlendMode::Blend);
+
+        let texture_creator = core.wincan.texture_creator();
+        let font = assets.font("ui")?;
+
+        // Initials being entered for a pending run, and which letter is selected
+        let mut initials = [b'A'; INITIALS_LEN];
+        let mut cursor = 0;
+
+        // Row of the run that was just added, drawn highlighted
+        let mut new_rank = None;
+
+        let next_status;
+
+        // Hints name whatever inputs are currently bound
+        let bindings = core.input.bindings();
+        let save_hint = format!("{} - Save", bindings.describe(Action::Confirm));
+        let leave_hint = format!(
+            "{} - Main menu   {} - Play again",
+            bindings.describe(Action::Back),
+            bindings.describe(Action::Restart)
+        );
+
+        core.audio.play_music(MusicTrack::Title);
+
+        let mut timestep = FixedTimestep::new(TICK_RATE);
+        timestep.set_frame_cap(core.settings.frame_cap());
+
+        'gameloop: loop {
+            timestep.begin_frame();
+
+            for event in core.poll_events() {
+                if let Event::Quit { .. } = event {
+                    // Quitting part way through still keeps the run, with
+                    // whatever initials are showing
+                    self.save_pending(&initials);
+                    next_status = Some(GameStatus::Credits);
+                    break 'gameloop;
+                }
+
+                if self.pending.is_some() {
+                    /* ~~~~~~ Initials Entry ~~~~~~ */
+                    // Typing a letter fills in the selected one, this comes
+                    // before actions since letters like W and Q are bound too
+                    if let Event::KeyDown {
+                        keycode: Some(k), ..
+                    } = event
+                    {
+                        let name = k.name();
+                        if name.len() == 1 && name.as_bytes()[0].is_ascii_alphabetic() {
+                            initials[cursor] = name.as_bytes()[0].to_ascii_uppercase();
+                            cursor = (cursor + 1).min(INITIALS_LEN - 1);
+                            continue;
+                        }
+                    }
+
+                    for action in core.input.pressed_actions(&event) {
+                        match action {
+                            Action::Up => {
+                                initials[cursor] = next_letter(initials[cursor], 1);
+                            }
+                            Action::Down => {
+                                initials[cursor] = next_letter(initials[cursor], -1);
+                            }
+                            Action::Left | Action::Back => {
+                                cursor = cursor.saturating_sub(1);
+                            }
+                            Action::Right => {
+                                cursor = (cursor + 1).min(INITIALS_LEN - 1);
+                            }
+                            Action::Confirm => {
+                                new_rank = self.save_pending(&initials);
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                } else {
+                    /* ~~~~~~ Table View ~~~~~~ */
+                    for action in core.input.pressed_actions(&event) {
+                        match action {
+                            Action::Back | Action::Confirm => {
+                                next_status = Some(GameStatus::Main);
+                                break 'gameloop;
+                            }
+                            Action::Restart => {
+                                next_status = Some(GameStatus::Game);
+                                break 'gameloop;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            /* ~~~~~~ Draw ~~~~~~ */
+            core.wincan.set_draw_color(SILVER);
+            core.wincan.clear();
+
+            if let Some(run) = &self.pending {
+                draw_text(
+                    core,
+                    &texture_creator,
+                    font,
+                    "New High Score!",
+                    ORANGE,
+                    (100, 60),
+                    120,
+                )?;
+                draw_text(
+                    core,
+                    &texture_creator,
+                    font,
+                    &format!("{:08}", run.score),
+                    WHITE,
+                    (100, 200),
+                    80,
+                )?;
+
+                // Selected letter is drawn in white with a bar under it
+                for (i, letter) in initials.iter().enumerate() {
+                    let x = 100 + i as i32 * 120;
+                    let color = if i == cursor { WHITE } else { ORANGE };
+                    draw_text(
+                        core,
+                        &texture_creator,
+                        font,
+                        &(*letter as char).to_string(),
+                        color,
+                        (x, 320),
+                        150,
+                    )?;
+                    if i == cursor {
+                        core.wincan.set_draw_color(WHITE);
+                        core.wincan.fill_rect(rect!(x, 480, 85, 10))?;
+                    }
+                }
+
+                draw_text(
+                    core,
+                    &texture_creator,
+                    font,
+                    "Type or Up/Down - Pick letters",
+                    ORANGE,
+                    (100, 540),
+                    50,
+                )?;
+                draw_text(
+                    core,
+                    &texture_creator,
+                    font,
+                    &save_hint,
+                    ORANGE,
+                    (100, 600),
+                    50,
+                )?;
+            } else {
+                draw_text(
+                    core,
+                    &texture_creator,
+                    font,
+                    "High Scores",
+                    ORANGE,
+                    (100, 20),
+                    100,
+                )?;
+
+                if self.table.entries().is_empty() {
+                    draw_text(
+                        core,
+                        &texture_creator,
+                        font,
+                        "No runs yet",
+                        WHITE,
+                        (100, FIRST_ROW_Y),
+                        ROW_H,
+                    )?;
+                } else {
+                    for (x, heading) in COLUMNS.iter().filter(|(_, h)| !h.is_empty()) {
+                        draw_text(
+                            core,
+                            &texture_creator,
+                            font,
+                            heading,
+                            WHITE,
+                            (*x, FIRST_ROW_Y - 36),
+                            24,
+                        )?;
+                    }
+                }
+
+                for (i, entry) in self.table.entries().iter().enumerate() {
+                    let values = [
+                        format!("{:>2}.", i + 1),
+                        entry.initials.clone(),
+                        format!("{:08}", entry.score),
+                        format!("{:>6}m", entry.distance),
+                        format!("{:>5}", entry.coins),
+                        entry.date_string(),
+                        entry.seed.to_string(),
+                    ];
+                    let color = if new_rank == Some(i) { WHITE } else { ORANGE };
+                    let y = FIRST_ROW_Y + i as i32 * ROW_SPACING;
+                    for ((x, _), value) in COLUMNS.iter().zip(values.iter()) {
+                        draw_text(core, &texture_creator, font, value, color, (*x, y), ROW_H)?;
+                    }
+                }
+
+                draw_text(
+                    core,
+                    &texture_creator,
+                    font,
+                    &leave_hint,
+                    ORANGE,
+                    (100, 660),
+                    40,
+                )?;
+            }
+
+            core.wincan.present();
+
+            // Nothing here animates, ticks are only used to pace frames
+            while timestep.tick() {}
+            timestep.end_frame();
+        }
+
+        Ok(GameState {
+            status: next_status,
+            score: 0,
+        })
+    }
+}
+
+impl HighScores {
+    // Offers a finished run a place on the table. If it qualifies, the next
+    // time this screen runs it asks for the player's initials first
+    // Returns: true if the run made it onto the table
+    pub fn submit(&mut self, run: HighScore) -> bool {
+        if self.table.qualifies(run.score) {
+            self.pending = Some(run);
+            true
+        } else {
+            false
+        }
+    }
+
+    // Puts the pending run on the table with the given initials and saves it
+    // Returns: the row it went in at, if there was a run pending
+    fn save_pending(&mut self, initials: &[u8]) -> Option<usize> {
+        let mut run = self.pending.take()?;
+        run.initials = String::from_utf8_lossy(initials).into();
+        let rank = self.table.insert(run);
+        if let Err(e) = self.table.save() {
+            println!("\n\t\tFailed to save high scores: {}", e);
+        }
+        rank
+    }
+}
+
+// Steps a letter forwards or backwards through A-Z, wrapping around
+fn next_letter(letter: u8, step: i32) -> u8 {
+    let index = (letter - b'A') as i32;
+    b'A' + (index + step).rem_euclid(26) as u8
+}
+
+// Draws a line of text with its top left corner at (x, y), scaled to the
+// given height
+fn draw_text(
+    core: &mut SDLCore,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &Font,
+    text: &str,
+    color: Color,
+    (x, y): (i32, i32),
+    h: u32,
+) -> Result<(), String> {
+    let surface = font
+        .render(text)
+        .blended(color)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+
+    let TextureQuery { width, height, .. } = texture.query();
+    let w = width * h / height;
+
+    core.wincan.copy(&texture, None, Some(rect!(x, y, w, h)))
+}
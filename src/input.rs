@@ -0,0 +1,468 @@
+// Input actions
+//
+// Screens never look at raw keys or controller buttons, they ask what
+// actions an event triggered. Which keys and buttons trigger each action is
+// loaded from a config file in the user's data folder, one action per line:
+//     jump = key:W key:Up key:Space button:a
+// Key names are SDL key names, button names are SDL game controller button
+// names. Actions missing from the file keep their default bindings.
+
+use crate::utils;
+
+use sdl2::controller::Button;
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::GameControllerSubsystem;
+use sdl2::Sdl;
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "controls.cfg";
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Action {
+    Jump,    // Leave the ground
+    Flip,    // Rotate while in the air
+    Grind,   // Hold on to rails, or ride a manual on flat ground
+    Grab,    // Hold the board in the air
+    Pause,   // Pause or resume a run
+    Confirm, // Pick the selected menu item
+    Back,    // Leave the current screen
+    Restart, // Start a new run
+    Menu,    // Return to the title screen
+    Up,      // Menu navigation
+    Down,
+    Left, // Also leans on a rail and picks which way to flip
+    Right,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::Jump,
+        Action::Flip,
+        Action::Grind,
+        Action::Grab,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::Restart,
+        Action::Menu,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+    ];
+
+    // Name used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Jump => "jump",
+            Action::Flip => "flip",
+            Action::Grind => "grind",
+            Action::Grab => "grab",
+            Action::Pause => "pause",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+            Action::Restart => "restart",
+            Action::Menu => "menu",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+        }
+    }
+
+    // Name shown on the controls screen
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Jump => "Jump",
+            Action::Flip => "Flip",
+            Action::Grind => "Grind/manual",
+            Action::Grab => "Grab",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::Restart => "Restart",
+            Action::Menu => "Main menu",
+            Action::Up => "Menu up",
+            Action::Down => "Menu down",
+            // Also menu left and right
+            Action::Left => "Lean back",
+            Action::Right => "Lean ahead",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+}
+
+// An action starting or stopping
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ActionEvent {
+    Pressed(Action),
+    Released(Action),
+}
+
+// A physical input that can be bound to an action
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Binding {
+    Key(Keycode),
+    Button(Button),
+}
+
+impl Binding {
+    // Name shown on screen, e.g. "Space" or "Pad A"
+    pub fn display_name(&self) -> String {
+        match self {
+            Binding::Key(key) => key.name(),
+            Binding::Button(button) => format!("Pad {}", button.string().to_uppercase()),
+        }
+    }
+
+    fn to_config(self) -> String {
+        match self {
+            Binding::Key(key) => format!("key:{}", key.name()),
+            Binding::Button(button) => format!("button:{}", button.string()),
+        }
+    }
+
+    fn from_config(text: &str) -> Option<Binding> {
+        if let Some(name) = text.strip_prefix("key:") {
+            Keycode::from_name(name).map(Binding::Key)
+        } else if let Some(name) = text.strip_prefix("button:") {
+            Button::from_string(name).map(Binding::Button)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Bindings {
+    // Indexed by Action
+    bindings: Vec<Vec<Binding>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        use Binding::Button as B;
+        use Binding::Key as K;
+
+        let mut bindings = Bindings {
+            bindings: vec![Vec::new(); Action::ALL.len()],
+        };
+        // Jumping and flipping share keys: jump on the ground, flip in the air
+        bindings.set(
+            Action::Jump,
+            vec![
+                K(Keycode::W),
+                K(Keycode::Up),
+                K(Keycode::Space),
+                B(Button::A),
+            ],
+        );
+        bindings.set(
+            Action::Flip,
+            vec![
+                K(Keycode::W),
+                K(Keycode::Up),
+                K(Keycode::Space),
+                B(Button::A),
+            ],
+        );
+        // Grinding shares keys with menu down, leaning with menu left and right
+        bindings.set(
+            Action::Grind,
+            vec![
+                K(Keycode::Down),
+                K(Keycode::S),
+                K(Keycode::LShift),
+                B(Button::X),
+            ],
+        );
+        bindings.set(
+            Action::Grab,
+            vec![K(Keycode::E), K(Keycode::RShift), B(Button::RightShoulder)],
+        );
+        bindings.set(Action::Pause, vec![K(Keycode::Escape), B(Button::Start)]);
+        bindings.set(
+            Action::Confirm,
+            vec![K(Keycode::Return), K(Keycode::Space), B(Button::A)],
+        );
+        bindings.set(
+            Action::Back,
+            vec![
+                K(Keycode::Escape),
+                K(Keycode::Q),
+                K(Keycode::Backspace),
+                B(Button::B),
+            ],
+        );
+        bindings.set(Action::Restart, vec![K(Keycode::R), B(Button::Y)]);
+        bindings.set(Action::Menu, vec![K(Keycode::M), B(Button::Back)]);
+        bindings.set(
+            Action::Up,
+            vec![K(Keycode::Up), K(Keycode::W), B(Button::DPadUp)],
+        );
+        bindings.set(
+            Action::Down,
+            vec![K(Keycode::Down), K(Keycode::S), B(Button::DPadDown)],
+        );
+        bindings.set(
+            Action::Left,
+            vec![K(Keycode::Left), K(Keycode::A), B(Button::DPadLeft)],
+        );
+        bindings.set(
+            Action::Right,
+            vec![K(Keycode::Right), K(Keycode::D), B(Button::DPadRight)],
+        );
+        bindings
+    }
+}
+
+impl Bindings {
+    // Path of the config file in the user's data folder
+    pub fn default_path() -> PathBuf {
+        utils::data_dir().join(FILE_NAME)
+    }
+
+    // Reads bindings from the config file, anything not in the file keeps
+    // its default
+    pub fn load(path: &Path) -> Result<Bindings, String> {
+        let mut bindings = Bindings::default();
+        if !path.exists() {
+            return Ok(bindings);
+        }
+
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let bad_line = || {
+                format!(
+                    "{}: bad binding on line {}: {:?}",
+                    path.display(),
+                    i + 1,
+                    line
+                )
+            };
+            let (name, inputs) = line.split_once('=').ok_or_else(bad_line)?;
+            let action = Action::from_name(name.trim()).ok_or_else(bad_line)?;
+            let inputs = inputs
+                .split_whitespace()
+                .map(Binding::from_config)
+                .collect::<Option<Vec<Binding>>>()
+                .ok_or_else(bad_line)?;
+            bindings.set(action, inputs);
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let mut contents = String::from("# Urban Odyssey controls\n");
+        for action in Action::ALL.iter() {
+            let inputs: Vec<String> = self
+                .get(*action)
+                .iter()
+                .map(|binding| binding.to_config())
+                .collect();
+            contents.push_str(&format!("{} = {}\n", action.name(), inputs.join(" ")));
+        }
+
+        fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        &self.bindings[action as usize]
+    }
+
+    // Short description of what triggers an action for on screen hints,
+    // e.g. "Escape" or "W/Up". Keys are preferred over buttons
+    pub fn describe(&self, action: Action) -> String {
+        let inputs = self.get(action);
+        let keys: Vec<String> = inputs
+            .iter()
+            .filter(|binding| matches!(binding, Binding::Key(_)))
+            .take(2)
+            .map(|binding| binding.display_name())
+            .collect();
+        if !keys.is_empty() {
+            keys.join("/")
+        } else if let Some(binding) = inputs.first() {
+            binding.display_name()
+        } else {
+            String::from("Unbound")
+        }
+    }
+
+    pub fn set(&mut self, action: Action, inputs: Vec<Binding>) {
+        self.bindings[action as usize] = inputs;
+    }
+
+    // Rebinds an action to a single new input of the same kind, e.g. a new
+    // key replaces its keys but leaves its controller buttons alone
+    pub fn rebind(&mut self, action: Action, input: Binding) {
+        let inputs = &mut self.bindings[action as usize];
+        inputs.retain(|binding| {
+            !matches!(
+                (binding, input),
+                (Binding::Key(_), Binding::Key(_)) | (Binding::Button(_), Binding::Button(_))
+            )
+        });
+        inputs.push(input);
+    }
+
+    // Every action the input is bound to, in Action::ALL order
+    fn actions_for(&self, input: Binding) -> impl Iterator<Item = Action> + '_ {
+        Action::ALL
+            .iter()
+            .copied()
+            .filter(move |action| self.get(*action).contains(&input))
+    }
+}
+
+// Turns SDL events into actions and keeps game controllers connected
+pub struct InputManager {
+    bindings: Bindings,
+    controller_subsys: Option<GameControllerSubsystem>,
+    controllers: Vec<GameController>,
+}
+
+impl InputManager {
+    // Loads the saved bindings and opens every connected controller. Never
+    // fails, problems are reported and the defaults / keyboard are used
+    pub fn init(sdl_cxt: &Sdl) -> InputManager {
+        let bindings = match Bindings::load(&Bindings::default_path()) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                println!("\n\t\tCouldn't read controls, using defaults: {}", e);
+                Bindings::default()
+            }
+        };
+
+        let controller_subsys = match sdl_cxt.game_controller() {
+            Ok(subsys) => Some(subsys),
+            Err(e) => {
+                println!("\n\t\tNo controller support: {}", e);
+                None
+            }
+        };
+
+        let mut input = InputManager {
+            bindings,
+            controller_subsys,
+            controllers: Vec::new(),
+        };
+        if let Some(count) = input
+            .controller_subsys
+            .as_ref()
+            .and_then(|subsys| subsys.num_joysticks().ok())
+        {
+            for index in 0..count {
+                input.open_controller(index);
+            }
+        }
+        input
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    // Replaces the bindings and writes them to the config file
+    pub fn set_bindings(&mut self, bindings: Bindings) -> Result<(), String> {
+        self.bindings = bindings;
+        self.bindings.save(&Bindings::default_path())
+    }
+
+    // Actions started or stopped by an event, in Action::ALL order. Also
+    // picks up controllers being plugged in or removed
+    pub fn actions(&mut self, event: &Event) -> Vec<ActionEvent> {
+        match event {
+            Event::KeyDown {
+                keycode: Some(key),
+                repeat: false,
+                ..
+            } => self.pressed(Binding::Key(*key)),
+            Event::KeyUp {
+                keycode: Some(key), ..
+            } => self.released(Binding::Key(*key)),
+            Event::ControllerButtonDown { button, .. } => self.pressed(Binding::Button(*button)),
+            Event::ControllerButtonUp { button, .. } => self.released(Binding::Button(*button)),
+            Event::ControllerDeviceAdded { which, .. } => {
+                self.open_controller(*which);
+                Vec::new()
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.controllers.retain(|c| c.instance_id() != *which);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // Convenience for screens that only care about presses
+    pub fn pressed_actions(&mut self, event: &Event) -> Vec<Action> {
+        self.actions(event)
+            .into_iter()
+            .filter_map(|action| match action {
+                ActionEvent::Pressed(action) => Some(action),
+                ActionEvent::Released(_) => None,
+            })
+            .collect()
+    }
+
+    fn pressed(&self, input: Binding) -> Vec<ActionEvent> {
+        self.bindings
+            .actions_for(input)
+            .map(ActionEvent::Pressed)
+            .collect()
+    }
+
+    fn released(&self, input: Binding) -> Vec<ActionEvent> {
+        self.bindings
+            .actions_for(input)
+            .map(ActionEvent::Released)
+            .collect()
+    }
+
+    fn open_controller(&mut self, index: u32) {
+        let subsys = match self.controller_subsys.as_ref() {
+            Some(subsys) => subsys,
+            None => return,
+        };
+        if !subsys.is_game_controller(index) {
+            return;
+        }
+        match subsys.open(index) {
+            Ok(controller) => {
+                // Added events also fire for controllers opened at startup
+                if self
+                    .controllers
+                    .iter()
+                    .all(|c| c.instance_id() != controller.instance_id())
+                {
+                    println!("\n\t\tController connected: {}", controller.name());
+                    self.controllers.push(controller);
+                }
+            }
+            Err(e) => println!("\n\t\tFailed to open controller {}: {}", index, e),
+        }
+    }
+}
@@ -1,25 +1,24 @@
-use crate::rect;
+use inf_runner::rect;
+
+use inf_runner::assets::Assets;
 
 use inf_runner::Game;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
 use inf_runner::SDLCore;
+use inf_runner::CAM_H;
+use inf_runner::CAM_W;
+
+use inf_runner::input::Action;
 
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use inf_runner::timestep::FixedTimestep;
+use inf_runner::timestep::TICK_RATE;
 
 use sdl2::event::Event;
-use sdl2::image::LoadTexture;
-use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 
-const FPS: f64 = 60.0;
-const FRAME_TIME: f64 = 1.0 / FPS as f64;
-
-const CAM_W: u32 = 1280;
-const CAM_H: u32 = 720;
-
 pub struct Instruction;
 
 impl Game for Instruction {
@@ -27,61 +26,63 @@ impl Game for Instruction {
         Ok(Instruction {})
     }
 
-    fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String> {
+    fn run(&mut self, core: &mut SDLCore, assets: &Assets) -> Result<GameState, String> {
         core.wincan.set_blend_mode(sdl2::render::BlendMode::Blend);
 
-        let texture_creator = core.wincan.texture_creator();
-
         core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 255));
         core.wincan.clear();
 
-        let tex_control = texture_creator.load_texture("assets/instructions/controls.png")?;
-        let tex_collect = texture_creator.load_texture("assets/instructions/collectables.png")?;
-        let tex_interact = texture_creator.load_texture("assets/instructions/interactable.png")?;
-        let tex_over = texture_creator.load_texture("assets/instructions/over.png")?;
+        let tex_control = assets.texture("instructions/controls")?;
+        let tex_collect = assets.texture("instructions/collectables")?;
+        let tex_interact = assets.texture("instructions/interactable")?;
+        let tex_over = assets.texture("instructions/over")?;
 
         core.wincan.present();
 
-        // FPS tracking
-        let mut _all_frames: i32 = 0;
-        let mut last_raw_time;
-        let mut last_measurement_time = Instant::now();
+        let mut timestep = FixedTimestep::new(TICK_RATE);
+        timestep.set_frame_cap(core.settings.frame_cap());
 
         let next_status;
 
         let mut which = 0;
 
         'gameloop: loop {
-            last_raw_time = Instant::now(); // FPS tracking
-            for event in core.event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } => {
-                        next_status = GameStatus::Credits;
-                        break 'gameloop;
+            timestep.begin_frame();
+            for event in core.poll_events() {
+                if let Event::Quit { .. } = event {
+                    next_status = GameStatus::Credits;
+                    break 'gameloop;
+                }
+                // Left click goes to the next page, right click to the previous
+                let mut actions = core.input.pressed_actions(&event);
+                if let Event::MouseButtonDown { mouse_btn, .. } = event {
+                    match mouse_btn {
+                        MouseButton::Left => actions.push(Action::Right),
+                        MouseButton::Right => actions.push(Action::Left),
+                        _ => {}
                     }
-                    Event::KeyDown {
-                        keycode: Some(k), ..
-                    } => match k {
-                        Keycode::Escape | Keycode::Q => {
+                }
+                for action in actions {
+                    match action {
+                        Action::Back => {
                             next_status = GameStatus::Main;
                             break 'gameloop;
                         }
-                        Keycode::Left => {
+                        Action::Left => {
                             which -= 1;
                         }
-                        Keycode::Right => {
+                        Action::Right | Action::Confirm => {
                             which += 1;
                         }
                         _ => {}
-                    },
-                    _ => {}
+                    }
                 }
             }
 
             match which {
                 0 => {
                     core.wincan.copy_ex(
-                        &tex_control,
+                        tex_control,
                         rect!(0, 0, CAM_W, CAM_H),
                         rect!(0, 0, CAM_W, CAM_H),
                         0.0,
@@ -92,7 +93,7 @@ impl Game for Instruction {
                 }
                 1 => {
                     core.wincan.copy_ex(
-                        &tex_collect,
+                        tex_collect,
                         rect!(0, 0, CAM_W, CAM_H),
                         rect!(0, 0, CAM_W, CAM_H),
                         0.0,
@@ -103,7 +104,7 @@ impl Game for Instruction {
                 }
                 2 => {
                     core.wincan.copy_ex(
-                        &tex_interact,
+                        tex_interact,
                         rect!(0, 0, CAM_W, CAM_H),
                         rect!(0, 0, CAM_W, CAM_H),
                         0.0,
@@ -114,7 +115,7 @@ impl Game for Instruction {
                 }
                 3 => {
                     core.wincan.copy_ex(
-                        &tex_over,
+                        tex_over,
                         rect!(0, 0, CAM_W, CAM_H),
                         rect!(0, 0, CAM_W, CAM_H),
                         0.0,
@@ -131,30 +132,7 @@ impl Game for Instruction {
 
             core.wincan.present();
 
-            /* ~~~~~~ FPS Calculation ~~~~~~ */
-            // Time taken to display the last frame
-            let raw_frame_time = last_raw_time.elapsed().as_secs_f64();
-            let delay = FRAME_TIME - raw_frame_time;
-            // If the amount of time to display the last frame was less than expected, sleep
-            // until the expected amount of time has passed
-            if delay > 0.0 {
-                // Using sleep to delay will always cause slightly more delay than intended due
-                // to CPU scheduling; possibly find a better way to delay
-                sleep(Duration::from_secs_f64(delay));
-            }
-            _all_frames += 1;
-            let time_since_last_measurement = last_measurement_time.elapsed();
-            // Measures the FPS once per second
-            if time_since_last_measurement > Duration::from_secs(1) {
-                //println!("{} FPS", _all_frames);
-                // println!(
-                //     "Average FPS: {:.2}",
-                //     (_all_frames as f64) / time_since_last_measurement.as_secs_f64()
-                // );
-                _all_frames = 0;
-                last_measurement_time = Instant::now();
-            }
-            /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+            timestep.end_frame();
         }
 
         // Out of game loop, return Ok
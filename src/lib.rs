@@ -3,7 +3,46 @@
 extern crate float_cmp;
 extern crate sdl2;
 
+pub mod assets;
+pub mod audio;
+pub mod bezier;
+pub mod biome;
+pub mod collision;
+pub mod difficulty;
+pub mod formation;
+pub mod grind;
+pub mod highscore;
+pub mod input;
+pub mod noise;
+pub mod parallax;
+pub mod physics;
+pub mod proceduralgen;
+pub mod reachability;
+pub mod replay;
+pub mod setpiece;
+pub mod settings;
+pub mod simulation;
+pub mod timestep;
+pub mod tricks;
+pub mod utils;
+
+use assets::Assets;
+use audio::SoundManager;
+use input::InputManager;
+use settings::FullscreenMode;
+use settings::ScaleMode;
+use settings::Settings;
+
+use sdl2::event::Event;
+use sdl2::event::WindowEvent;
+use sdl2::rect::Point;
 use sdl2::rect::Rect;
+use sdl2::video::FullscreenType;
+
+// Size of the area the game is drawn to. The window can be any size, the
+// canvas is scaled to fit it
+pub const CAM_W: u32 = 1280;
+pub const CAM_H: u32 = 720;
 
 pub struct SDLCore {
     #[allow(dead_code)]
@@ -11,6 +50,9 @@ pub struct SDLCore {
     pub wincan: sdl2::render::WindowCanvas,
     pub event_pump: sdl2::EventPump,
     pub cam: Rect,
+    pub audio: SoundManager,
+    pub input: InputManager,
+    pub settings: Settings,
 }
 
 pub enum GameStatus {
@@ -18,18 +60,27 @@ pub enum GameStatus {
     Game,
     Credits,
     Instruct,
+    HighScores,
+    Controls,
+    Settings,
 }
 
 // Contains all types of terrain
-#[derive(Debug)]
+// How each behaves is set in physics::TERRAIN_PHYSICS, in this order
+#[derive(Copy, Clone, Debug)]
 pub enum TerrainType {
     Grass,
     Asphalt,
     Sand,
     Water,
+    Ice,      // Slippery, can't be skated on
+    Mud,      // Slows the player down and weakens jumps
+    Ramp,     // Launches the player off its lip
+    Conveyor, // Boardwalk that carries the player along
 }
 
 // Contains all types of objects generated on terrain
+#[derive(Copy, Clone)]
 pub enum StaticObject {
     Coin,    // Collectable
     Power,   // Collectable
@@ -37,6 +88,7 @@ pub enum StaticObject {
     Balloon, // Obstacle
     Chest,   // Obstacle
     Bench,   // Obstacle
+    Rail,    // Grindable, which kind depends on the biome
 }
 
 // Contains all types of power ups
@@ -56,6 +108,9 @@ pub enum ObstacleType {
     Balloon,
     Chest,
     Bench,
+    Handrail,  // Rail, see grind.rs
+    Ledge,     // Rail
+    PowerLine, // Rail
 }
 
 #[allow(dead_code)]
@@ -65,14 +120,23 @@ pub struct GameState {
 }
 
 impl SDLCore {
-    pub fn init(title: &str, vsync: bool, width: u32, height: u32) -> Result<SDLCore, String> {
+    pub fn init(title: &str, settings: Settings) -> Result<SDLCore, String> {
         let sdl_cxt = sdl2::init()?;
         let video_subsys = sdl_cxt.video()?;
 
-        let window = video_subsys
-            .window(title, width, height)
-            .build()
-            .map_err(|e| e.to_string())?;
+        let mut window = video_subsys.window(title, settings.window_width, settings.window_height);
+        window.resizable();
+        match settings.fullscreen {
+            FullscreenMode::Windowed => {}
+            FullscreenMode::Fullscreen => {
+                window.fullscreen();
+            }
+            FullscreenMode::Desktop => {
+                window.fullscreen_desktop();
+            }
+        }
+        let window = window.build().map_err(|e| e.to_string())?;
+        let vsync = settings.vsync;
 
         let wincan = window.into_canvas().accelerated();
 
@@ -87,14 +151,140 @@ impl SDLCore {
 
         let event_pump = sdl_cxt.event_pump()?;
 
-        let cam = Rect::new(0, 0, width, height);
+        let cam = Rect::new(0, 0, CAM_W, CAM_H);
 
-        Ok(SDLCore {
+        // Falls back to silence if there's no audio device
+        let mut audio = SoundManager::init(&sdl_cxt);
+        audio.set_master_volume(settings.master_volume);
+        audio.set_music_volume(settings.music_volume);
+        audio.set_effects_volume(settings.effects_volume);
+
+        // Saved key bindings and any connected game controllers
+        let input = InputManager::init(&sdl_cxt);
+
+        let mut core = SDLCore {
             sdl_cxt,
             wincan,
             event_pump,
             cam,
-        })
+            audio,
+            input,
+            settings,
+        };
+
+        // Everything is drawn at CAM_W x CAM_H and scaled to the window
+        core.update_scaling()?;
+
+        Ok(core)
+    }
+
+    // Switches to new settings, applying everything that can change while
+    // running (vsync needs a restart) and saving them for next time
+    pub fn apply_settings(&mut self, settings: Settings) -> Result<(), String> {
+        let old = std::mem::replace(&mut self.settings, settings);
+        let settings = &self.settings;
+
+        let window = self.wincan.window_mut();
+        if (settings.window_width, settings.window_height) != (old.window_width, old.window_height)
+        {
+            window
+                .set_size(settings.window_width, settings.window_height)
+                .map_err(|e| e.to_string())?;
+        }
+        if settings.fullscreen != old.fullscreen {
+            window.set_fullscreen(fullscreen_type(settings.fullscreen))?;
+        }
+
+        self.audio.set_master_volume(settings.master_volume);
+        self.audio.set_music_volume(settings.music_volume);
+        self.audio.set_effects_volume(settings.effects_volume);
+
+        self.update_scaling()?;
+        self.save_settings()
+    }
+
+    pub fn save_settings(&self) -> Result<(), String> {
+        self.settings.save(&Settings::default_path())
+    }
+
+    // Fits the CAM_W x CAM_H canvas to the current window size
+    fn update_scaling(&mut self) -> Result<(), String> {
+        match self.settings.scaling {
+            // SDL keeps the aspect ratio and centers the canvas
+            ScaleMode::Letterbox => self
+                .wincan
+                .set_logical_size(CAM_W, CAM_H)
+                .map_err(|e| e.to_string()),
+            // Turn the logical size off and scale each axis separately
+            ScaleMode::Stretch => {
+                self.wincan
+                    .set_logical_size(0, 0)
+                    .map_err(|e| e.to_string())?;
+                let (w, h) = self.wincan.output_size()?;
+                self.wincan
+                    .set_scale(w as f32 / CAM_W as f32, h as f32 / CAM_H as f32)
+            }
+        }
+    }
+
+    // Takes every event waiting since the last call. Window resizes are
+    // handled here, and mouse positions are converted from window pixels to
+    // the CAM_W x CAM_H space everything is drawn in
+    pub fn poll_events(&mut self) -> Vec<Event> {
+        let mut events: Vec<Event> = self.event_pump.poll_iter().collect();
+
+        for event in events.iter_mut() {
+            match event {
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(w, h),
+                    ..
+                } => {
+                    // Remember the size the player dragged the window to
+                    if self.settings.fullscreen == FullscreenMode::Windowed {
+                        self.settings.window_width = *w as u32;
+                        self.settings.window_height = *h as u32;
+                    }
+                    if let Err(e) = self.update_scaling() {
+                        println!("\n\t\tFailed to rescale window: {}", e);
+                    }
+                }
+                Event::MouseMotion { x, y, .. }
+                | Event::MouseButtonDown { x, y, .. }
+                | Event::MouseButtonUp { x, y, .. } => {
+                    let logical = self.to_logical(*x, *y);
+                    *x = logical.x();
+                    *y = logical.y();
+                }
+                _ => {}
+            }
+        }
+
+        events
+    }
+
+    // Converts a point in window pixels to CAM_W x CAM_H space. Points in
+    // the letterbox bars end up outside 0..CAM_W / 0..CAM_H
+    pub fn to_logical(&self, x: i32, y: i32) -> Point {
+        match self.settings.scaling {
+            // SDL already maps mouse events when a logical size is set
+            ScaleMode::Letterbox => Point::new(x, y),
+            ScaleMode::Stretch => {
+                let (w, h) = self.wincan.window().size();
+                Point::new(
+                    (x as i64 * CAM_W as i64 / w.max(1) as i64) as i32,
+                    (y as i64 * CAM_H as i64 / h.max(1) as i64) as i32,
+                )
+            }
+        }
+    }
+}
+
+// SDL's name for a fullscreen mode
+fn fullscreen_type(mode: FullscreenMode) -> FullscreenType {
+    match mode {
+        FullscreenMode::Windowed => FullscreenType::Off,
+        FullscreenMode::Fullscreen => FullscreenType::True,
+        FullscreenMode::Desktop => FullscreenType::Desktop,
     }
 }
 
@@ -102,5 +292,5 @@ pub trait Game {
     fn init() -> Result<Self, String>
     where
         Self: Sized;
-    fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String>;
+    fn run(&mut self, core: &mut SDLCore, assets: &Assets) -> Result<GameState, String>;
 }
@@ -2,21 +2,44 @@
 // CS 1666 - Fall 2021
 // Infinite Runner
 
+mod controls;
 mod credits;
+mod highscore_screen;
 mod instruction;
-mod physics;
-mod proceduralgen;
 mod runner;
+mod settings_menu;
 mod title;
-mod utils;
 
+use inf_runner::assets::AssetManifest;
+use inf_runner::assets::Assets;
+use inf_runner::assets::MANIFEST_PATH;
+use inf_runner::difficulty::Difficulty;
+use inf_runner::replay::Replay;
+use inf_runner::settings::Settings;
 use inf_runner::Game;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
 
+use sdl2::render::TextureCreator;
+use sdl2::ttf::Sdl2TtfContext;
+use sdl2::video::WindowContext;
+
+use std::path::Path;
+
 const TITLE: &str = "Urban Odyssey";
-const CAM_W: u32 = 1280;
-const CAM_H: u32 = 720;
+
+// Rows printed by --difficulty-curve, in metres
+const CURVE_STEP: f64 = 250.0;
+const CURVE_UNTIL: f64 = 6000.0;
+
+// Options given on the command line
+#[derive(Default)]
+struct LaunchOptions {
+    seed: Option<u64>,             // --seed <number>: generate every run from this seed
+    playback: Option<Replay>,      // --replay <file>: play back a recorded run
+    frame_report: bool,            // --frame-report: print frame timings while playing
+    print_curves: Vec<Difficulty>, // --difficulty-curve [preset]: print and quit
+}
 
 // A container for all the segments of our game
 pub struct UrbanOdyssey {
@@ -25,17 +48,55 @@ pub struct UrbanOdyssey {
     runner: runner::Runner,
     credits: credits::Credits,
     instruct: instruction::Instruction,
+    high_scores: highscore_screen::HighScores,
+    controls: controls::Controls,
+    settings: settings_menu::SettingsMenu,
 }
 
 fn main() {
     println!("\nRunning {}:", TITLE);
     print!("\tInitting...");
 
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            println!("\n\t\tFailed to init: {}", e);
+            return;
+        }
+    };
+
+    // Printing difficulty curves for tuning doesn't need a window
+    if !options.print_curves.is_empty() {
+        println!("DONE");
+        for difficulty in options.print_curves.iter() {
+            println!("\n{}", difficulty.curve_table(CURVE_STEP, CURVE_UNTIL));
+        }
+        return;
+    }
+
     // Init all segments, wrap into one UrbanOdyssey
-    let game = init();
+    let game = init(options);
     match game {
         Err(e) => println!("\n\t\tFailed to init: {}", e),
         Ok(mut contents) => {
+            // Every asset is checked here so a missing file is reported
+            // before any screen starts
+            let texture_creator = contents.core.wincan.texture_creator();
+            let ttf_context = match sdl2::ttf::init() {
+                Ok(ttf_context) => ttf_context,
+                Err(e) => {
+                    println!("\n\t\tFailed to init: {}", e);
+                    return;
+                }
+            };
+            let assets = match load_assets(&texture_creator, &ttf_context) {
+                Ok(assets) => assets,
+                Err(e) => {
+                    println!("\n\t\tFailed to init: {}", e);
+                    return;
+                }
+            };
+
             println!("DONE");
 
             let mut game_manager = GameState {
@@ -50,7 +111,7 @@ fn main() {
                         print!("\tRunning...");
 
                         // TITLE SCREEN RUN
-                        match contents.title.run(&mut (contents.core)) {
+                        match contents.title.run(&mut (contents.core), &assets) {
                             Err(e) => println!("\n\t\tEncountered error while running: {}", e),
                             Ok(title_status) => {
                                 game_manager = title_status;
@@ -63,11 +124,18 @@ fn main() {
                         print!("\tRunning...");
 
                         //GAME PLAY RUN
-                        match contents.runner.run(&mut (contents.core)) {
+                        match contents.runner.run(&mut (contents.core), &assets) {
                             Err(e) => println!("\n\t\tEncountered error while running: {}", e),
                             Ok(game_status) => {
                                 game_manager = game_status;
                                 println!("DONE\nExiting cleanly");
+
+                                // Runs good enough for the table go enter initials
+                                if let Some(run) = contents.runner.take_finished_run() {
+                                    if contents.high_scores.submit(run) {
+                                        game_manager.status = Some(GameStatus::HighScores);
+                                    }
+                                }
                             }
                         };
                     }
@@ -76,7 +144,7 @@ fn main() {
                         print!("\tRunning...");
 
                         // CREDITS RUN
-                        match contents.credits.run(&mut (contents.core)) {
+                        match contents.credits.run(&mut (contents.core), &assets) {
                             Err(e) => println!("\n\t\tEncountered error while running: {}", e),
                             Ok(credits_status) => {
                                 game_manager = credits_status;
@@ -89,7 +157,7 @@ fn main() {
                         print!("\tRunning...");
 
                         // CREDITS RUN
-                        match contents.instruct.run(&mut (contents.core)) {
+                        match contents.instruct.run(&mut (contents.core), &assets) {
                             Err(e) => println!("\n\t\tEncountered error while running: {}", e),
                             Ok(instruct_status) => {
                                 game_manager = instruct_status;
@@ -97,22 +165,74 @@ fn main() {
                             }
                         };
                     }
+                    Some(GameStatus::HighScores) => {
+                        println!("\nRunning High Scores Sequence:");
+                        print!("\tRunning...");
+
+                        // HIGH SCORES RUN
+                        match contents.high_scores.run(&mut (contents.core), &assets) {
+                            Err(e) => println!("\n\t\tEncountered error while running: {}", e),
+                            Ok(high_scores_status) => {
+                                game_manager = high_scores_status;
+                                println!("DONE\nExiting cleanly");
+                            }
+                        };
+                    }
+                    Some(GameStatus::Controls) => {
+                        println!("\nRunning Controls Sequence:");
+                        print!("\tRunning...");
+
+                        // CONTROLS RUN
+                        match contents.controls.run(&mut (contents.core), &assets) {
+                            Err(e) => println!("\n\t\tEncountered error while running: {}", e),
+                            Ok(controls_status) => {
+                                game_manager = controls_status;
+                                println!("DONE\nExiting cleanly");
+                            }
+                        };
+                    }
+                    Some(GameStatus::Settings) => {
+                        println!("\nRunning Settings Sequence:");
+                        print!("\tRunning...");
+
+                        // SETTINGS RUN
+                        match contents.settings.run(&mut (contents.core), &assets) {
+                            Err(e) => println!("\n\t\tEncountered error while running: {}", e),
+                            Ok(settings_status) => {
+                                game_manager = settings_status;
+                                println!("DONE\nExiting cleanly");
+                            }
+                        };
+                    }
                     None => {
                         break;
                     }
                 };
             }
+
+            // Keeps the window size if it was resized by dragging
+            if let Err(e) = contents.core.save_settings() {
+                println!("\n\t\tFailed to save settings: {}", e);
+            }
         }
     };
 }
 
-fn init() -> Result<UrbanOdyssey, String> {
-    let core = inf_runner::SDLCore::init(TITLE, true, CAM_W, CAM_H)?;
+fn init(options: LaunchOptions) -> Result<UrbanOdyssey, String> {
+    // Settings decide how the window is created, so they come first
+    let settings = Settings::load_or_default();
+    let core = inf_runner::SDLCore::init(TITLE, settings)?;
 
     let title = title::Title::init()?;
-    let runner = runner::Runner::init()?;
+    let mut runner = runner::Runner::init()?;
+    runner.set_seed(options.seed);
+    runner.set_playback(options.playback);
+    runner.set_frame_report(options.frame_report);
     let credits = credits::Credits::init()?;
     let instruct = instruction::Instruction::init()?;
+    let high_scores = highscore_screen::HighScores::init()?;
+    let controls = controls::Controls::init()?;
+    let settings = settings_menu::SettingsMenu::init()?;
 
     Ok(UrbanOdyssey {
         core,
@@ -120,5 +240,56 @@ fn init() -> Result<UrbanOdyssey, String> {
         runner,
         credits,
         instruct,
+        high_scores,
+        controls,
+        settings,
     })
 }
+
+// Loads every texture and font listed in the asset manifest
+fn load_assets<'a>(
+    texture_creator: &'a TextureCreator<WindowContext>,
+    ttf_context: &'a Sdl2TtfContext,
+) -> Result<Assets<'a>, String> {
+    let manifest = AssetManifest::load(Path::new(MANIFEST_PATH))?;
+    Assets::load(&manifest, texture_creator, ttf_context)
+}
+
+// Reads the command line flags:
+//   --seed <number>  replay a run from the seed shown on the game over screen
+//   --replay <file>  play back a run saved to the replays folder
+//   --frame-report   print FPS and frame times against their budget each second
+//   --difficulty-curve [easy|normal|hard]
+//                    print how a preset (or all of them) ramps up, then quit
+fn parse_args() -> Result<LaunchOptions, String> {
+    let mut options = LaunchOptions::default();
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let value = args.next().ok_or("--seed requires a value")?;
+                let seed = value
+                    .parse::<u64>()
+                    .map_err(|e| format!("invalid seed {:?}: {}", value, e))?;
+                options.seed = Some(seed);
+            }
+            "--replay" => {
+                let path = args.next().ok_or("--replay requires a file")?;
+                options.playback = Some(Replay::load(Path::new(&path))?);
+            }
+            "--frame-report" => options.frame_report = true,
+            "--difficulty-curve" => {
+                match args.peek().and_then(|name| Difficulty::from_name(name)) {
+                    Some(difficulty) => {
+                        args.next();
+                        options.print_curves.push(difficulty);
+                    }
+                    None => options.print_curves.extend(Difficulty::ALL.iter()),
+                }
+            }
+            // Bundled apps can be launched with extra platform flags
+            _ => println!("\n\t\tIgnoring unknown argument {:?}", arg),
+        }
+    }
+    Ok(options)
+}
@@ -0,0 +1,377 @@
+// Seeded gradient noise for terrain and background generation
+//
+// A Noise is built from a seed and samples 1D or 2D Perlin or Simplex
+// noise. Every sample is continuous in its input and lies in [-1, 1], and
+// the same seed always gives the same values. Layering several octaves of
+// it, each `lacunarity` times the frequency and `persistence` times the
+// strength of the last, gives the rougher fractal noise hills are made of.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+// Gradients for 2D noise, as in Stefan Gustavson's "Simplex noise demystified"
+const GRAD_2D: [(f64, f64); 12] = [
+    (1.0, 1.0),
+    (-1.0, 1.0),
+    (1.0, -1.0),
+    (-1.0, -1.0),
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+];
+
+// Skew and unskew factors between the square and triangle grids of 2D
+// simplex noise, (sqrt(3) - 1) / 2 and (3 - sqrt(3)) / 6
+const F2: f64 = 0.366_025_403_784_438_6;
+const G2: f64 = 0.211_324_865_405_187_1;
+
+// Scale bringing each kind of noise out to [-1, 1]
+const PERLIN_1D_SCALE: f64 = 2.0;
+const SIMPLEX_1D_SCALE: f64 = 0.395;
+const SIMPLEX_2D_SCALE: f64 = 70.0;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum NoiseKind {
+    Perlin,  // Square grid, smooth but a little blocky in 2D
+    Simplex, // Triangle grid, fewer directional artifacts
+}
+
+// How layers of noise are stacked for fractal noise
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Octaves {
+    pub count: u32,       // Number of layers
+    pub frequency: f64,   // Frequency of the first layer, in cycles per unit
+    pub lacunarity: f64,  // Frequency multiplier from one layer to the next
+    pub persistence: f64, // Strength multiplier from one layer to the next
+}
+
+impl Octaves {
+    // Layers that each double the frequency and halve the strength
+    pub fn new(count: u32, frequency: f64) -> Octaves {
+        Octaves {
+            count,
+            frequency,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Noise {
+    // Shuffled 0..256 twice over, so two lookups can be added without wrapping
+    perm: [u8; 512],
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Noise {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut shuffled: Vec<u8> = (0..=255).collect();
+        shuffled.shuffle(&mut rng);
+
+        let mut perm = [0; 512];
+        for (i, value) in perm.iter_mut().enumerate() {
+            *value = shuffled[i % 256];
+        }
+        Noise { perm }
+    }
+
+    // Samples a single layer of noise
+    // Params: kind of noise, point to sample
+    // Returns: noise value in [-1, 1]
+    pub fn sample_1d(&self, kind: NoiseKind, x: f64) -> f64 {
+        let n = match kind {
+            NoiseKind::Perlin => self.perlin_1d(x),
+            NoiseKind::Simplex => self.simplex_1d(x),
+        };
+        n.clamp(-1.0, 1.0)
+    }
+
+    pub fn sample_2d(&self, kind: NoiseKind, p: (f64, f64)) -> f64 {
+        let n = match kind {
+            NoiseKind::Perlin => self.perlin_2d(p),
+            NoiseKind::Simplex => self.simplex_2d(p),
+        };
+        n.clamp(-1.0, 1.0)
+    }
+
+    // Samples several layers of noise added together
+    // Params: kind of noise, point to sample, how the layers are stacked
+    // Returns: noise value in [-1, 1]
+    pub fn fractal_1d(&self, kind: NoiseKind, x: f64, octaves: &Octaves) -> f64 {
+        self.fractal(octaves, |frequency| self.sample_1d(kind, x * frequency))
+    }
+
+    pub fn fractal_2d(&self, kind: NoiseKind, p: (f64, f64), octaves: &Octaves) -> f64 {
+        self.fractal(octaves, |frequency| {
+            self.sample_2d(kind, (p.0 * frequency, p.1 * frequency))
+        })
+    }
+
+    // Adds up the layers, scaled back down by their total strength so the
+    // result stays in [-1, 1]
+    fn fractal(&self, octaves: &Octaves, sample: impl Fn(f64) -> f64) -> f64 {
+        let mut total = 0.0;
+        let mut max = 0.0;
+        let mut frequency = octaves.frequency;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves.count {
+            total += sample(frequency) * amplitude;
+            max += amplitude;
+            frequency *= octaves.lacunarity;
+            amplitude *= octaves.persistence;
+        }
+
+        if max > 0.0 {
+            total / max
+        } else {
+            0.0
+        }
+    }
+
+    /* ~~~~~~ Perlin ~~~~~~ */
+
+    fn perlin_1d(&self, x: f64) -> f64 {
+        let x0 = x.floor();
+        let t = x - x0;
+
+        let g0 = self.grad_1d(x0 as i64);
+        let g1 = self.grad_1d(x0 as i64 + 1);
+
+        PERLIN_1D_SCALE * lerp(g0 * t, g1 * (t - 1.0), fade(t))
+    }
+
+    fn perlin_2d(&self, p: (f64, f64)) -> f64 {
+        let (x0, y0) = (p.0.floor(), p.1.floor());
+        let (tx, ty) = (p.0 - x0, p.1 - y0);
+        let (i, j) = (x0 as i64, y0 as i64);
+
+        // Gradient at each corner dotted with the offset from it
+        let corner = |di: i64, dj: i64| {
+            let g = GRAD_2D[self.hash_2d(i + di, j + dj) % GRAD_2D.len()];
+            g.0 * (tx - di as f64) + g.1 * (ty - dj as f64)
+        };
+
+        let bottom = lerp(corner(0, 0), corner(1, 0), fade(tx));
+        let top = lerp(corner(0, 1), corner(1, 1), fade(tx));
+        lerp(bottom, top, fade(ty))
+    }
+
+    /* ~~~~~~ Simplex ~~~~~~ */
+
+    fn simplex_1d(&self, x: f64) -> f64 {
+        let i0 = x.floor() as i64;
+        let x0 = x - i0 as f64;
+        let x1 = x0 - 1.0;
+
+        // Each end of the line contributes less the further away it is
+        let contribution = |i: i64, d: f64| {
+            let t = 1.0 - d * d;
+            let t2 = t * t;
+            t2 * t2 * self.simplex_grad_1d(i) * d
+        };
+
+        SIMPLEX_1D_SCALE * (contribution(i0, x0) + contribution(i0 + 1, x1))
+    }
+
+    fn simplex_2d(&self, p: (f64, f64)) -> f64 {
+        // Which triangle of the skewed grid the point is in
+        let s = (p.0 + p.1) * F2;
+        let i = (p.0 + s).floor() as i64;
+        let j = (p.1 + s).floor() as i64;
+        let t = (i + j) as f64 * G2;
+        let x0 = p.0 - (i as f64 - t);
+        let y0 = p.1 - (j as f64 - t);
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+        // Offsets from the triangle's three corners
+        let corners = [
+            (0, 0, x0, y0),
+            (i1, j1, x0 - i1 as f64 + G2, y0 - j1 as f64 + G2),
+            (1, 1, x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2),
+        ];
+
+        let mut n = 0.0;
+        for (di, dj, x, y) in corners.iter() {
+            let t = 0.5 - x * x - y * y;
+            if t > 0.0 {
+                let g = GRAD_2D[self.hash_2d(i + di, j + dj) % GRAD_2D.len()];
+                let t2 = t * t;
+                n += t2 * t2 * (g.0 * x + g.1 * y);
+            }
+        }
+        SIMPLEX_2D_SCALE * n
+    }
+
+    /* ~~~~~~ Lattice lookups ~~~~~~ */
+
+    fn hash(&self, i: i64) -> usize {
+        self.perm[(i & 255) as usize] as usize
+    }
+
+    fn hash_2d(&self, i: i64, j: i64) -> usize {
+        self.perm[self.hash(i) + (j & 255) as usize] as usize
+    }
+
+    // Slope of Perlin noise at a whole number, in [-1, 1]
+    fn grad_1d(&self, i: i64) -> f64 {
+        self.hash(i) as f64 / 255.0 * 2.0 - 1.0
+    }
+
+    // Slope of Simplex noise at a whole number, one of +-1 to +-8
+    fn simplex_grad_1d(&self, i: i64) -> f64 {
+        let h = self.hash(i) & 15;
+        let grad = 1.0 + (h & 7) as f64;
+        if h & 8 != 0 {
+            -grad
+        } else {
+            grad
+        }
+    }
+}
+
+/*  Smoothing the input value so the result isn't as "sharp"
+ *  Used for interpolation step of Perlin Noise Algorithm.
+ *
+ *  - Takes in value `t` to apply fade upon
+ *
+ *  - Returns smoothed value
+ */
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [NoiseKind; 2] = [NoiseKind::Perlin, NoiseKind::Simplex];
+
+    // Distance between neighbouring samples, and most they may differ by
+    const STEP: f64 = 1e-4;
+    const MAX_CHANGE: f64 = 1e-2;
+
+    // Points spread over several hundred lattice cells either side of 0, at
+    // a spacing that never lines up with the lattice
+    fn points_1d() -> impl Iterator<Item = f64> {
+        (-20_000..20_000).map(|i| i as f64 * 0.0137)
+    }
+
+    fn points_2d() -> impl Iterator<Item = (f64, f64)> {
+        (-150..150).flat_map(|i| (-150..150).map(move |j| (i as f64 * 0.731, j as f64 * 0.533)))
+    }
+
+    // The layers hills are made of
+    fn octaves() -> Octaves {
+        Octaves::new(4, 0.5)
+    }
+
+    fn in_range(n: f64) -> bool {
+        (-1.0..=1.0).contains(&n)
+    }
+
+    #[test]
+    fn samples_stay_in_range() {
+        for seed in 0..4 {
+            let noise = Noise::new(seed);
+            // Unclamped, so a wrong scale shows up
+            for x in points_1d() {
+                assert!(in_range(noise.perlin_1d(x)), "perlin_1d({})", x);
+                assert!(in_range(noise.simplex_1d(x)), "simplex_1d({})", x);
+            }
+            for p in points_2d() {
+                assert!(in_range(noise.perlin_2d(p)), "perlin_2d({:?})", p);
+                assert!(in_range(noise.simplex_2d(p)), "simplex_2d({:?})", p);
+            }
+        }
+    }
+
+    #[test]
+    fn octaves_stay_in_range() {
+        let noise = Noise::new(7);
+        for kind in KINDS.iter() {
+            for x in points_1d() {
+                assert!(in_range(noise.fractal_1d(*kind, x, &octaves())));
+            }
+            for p in points_2d() {
+                assert!(in_range(noise.fractal_2d(*kind, p, &octaves())));
+            }
+        }
+    }
+
+    #[test]
+    fn samples_are_continuous() {
+        let noise = Noise::new(3);
+        for kind in KINDS.iter() {
+            for x in points_1d() {
+                let change = noise.sample_1d(*kind, x + STEP) - noise.sample_1d(*kind, x);
+                assert!(change.abs() < MAX_CHANGE, "{:?} at {}", kind, x);
+            }
+            for p in points_2d() {
+                let n = noise.sample_2d(*kind, p);
+                for q in [(p.0 + STEP, p.1), (p.0, p.1 + STEP)].iter() {
+                    let change = noise.sample_2d(*kind, *q) - n;
+                    assert!(change.abs() < MAX_CHANGE, "{:?} at {:?}", kind, p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn samples_are_continuous_across_the_lattice() {
+        let noise = Noise::new(5);
+        for kind in KINDS.iter() {
+            for i in -300..300 {
+                let x = i as f64;
+                let change =
+                    noise.sample_1d(*kind, x + STEP / 2.0) - noise.sample_1d(*kind, x - STEP / 2.0);
+                assert!(change.abs() < MAX_CHANGE, "{:?} at {}", kind, x);
+
+                let p = (x, 0.5);
+                let change = noise.sample_2d(*kind, (p.0 + STEP / 2.0, p.1))
+                    - noise.sample_2d(*kind, (p.0 - STEP / 2.0, p.1));
+                assert!(change.abs() < MAX_CHANGE, "{:?} at {:?}", kind, p);
+            }
+        }
+    }
+
+    #[test]
+    fn octaves_are_continuous() {
+        let noise = Noise::new(9);
+        for kind in KINDS.iter() {
+            for x in points_1d() {
+                let change = noise.fractal_1d(*kind, x + STEP, &octaves())
+                    - noise.fractal_1d(*kind, x, &octaves());
+                assert!(change.abs() < MAX_CHANGE, "{:?} at {}", kind, x);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_values() {
+        let (a, b) = (Noise::new(42), Noise::new(42));
+        for kind in KINDS.iter() {
+            assert!(points_1d().all(|x| a.sample_1d(*kind, x) == b.sample_1d(*kind, x)));
+            assert!(points_2d().all(|p| a.sample_2d(*kind, p) == b.sample_2d(*kind, p)));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_values() {
+        let (a, b) = (Noise::new(1), Noise::new(2));
+        for kind in KINDS.iter() {
+            assert!(points_1d().any(|x| a.sample_1d(*kind, x) != b.sample_1d(*kind, x)));
+            assert!(points_2d().any(|p| a.sample_2d(*kind, p) != b.sample_2d(*kind, p)));
+        }
+    }
+}
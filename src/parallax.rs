@@ -0,0 +1,215 @@
+// Scrolling background layers drawn behind the terrain
+//
+// Every layer is a flat picture at a depth: one at depth 0.25 moves a
+// quarter as far as the terrain does, both along the run and up and down
+// with the camera. Layers are sampled from where the camera has got to
+// rather than shifted along each tick, so a biome can swap in a different
+// set of layers at any moment and they still line up. Drawing is left to
+// the runner, layers here only turn into rectangles on the screen.
+
+use crate::noise::Noise;
+
+use crate::proceduralgen;
+
+use crate::rect;
+
+use crate::CAM_H;
+use crate::CAM_W;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+// Width of one column of a hill silhouette, in pixels
+const HILL_COLUMN_W: i32 = 5;
+
+// How far the camera can drift up or down before the background stops
+// following it, so a long climb doesn't scroll the layers off screen
+const MAX_DRIFT_Y: f64 = CAM_H as f64 / 2.0;
+
+// How a layer's picture is made
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Generator {
+    // Texture tiled across the screen, drawn as it is
+    Image {
+        texture: &'static str, // Asset id
+        y: i32,                // Top edge with the camera at rest
+        h: u32,
+    },
+    // Perlin noise silhouette, see proceduralgen::gen_perlin_hill_point
+    Hills {
+        freq: f32, // Columns one rise and fall spans
+        amp: f32,
+        modifier: f32,
+        mul: f32, // Height of flat hills
+    },
+    // Row of flat topped buildings standing on the horizon
+    Skyline {
+        horizon: i32, // Screen y the buildings stand on
+        min_w: u32,
+        max_w: u32,
+        min_h: u32,
+        max_h: u32,
+        max_gap: u32, // Widest space between two buildings
+    },
+    // Blocky clouds scattered across a band of the sky
+    Clouds {
+        spacing: u32, // Width of the stretch each cloud can be in
+        top: i32,     // Highest a cloud can be
+        bottom: i32,  // Lowest a cloud can be
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Layer {
+    pub depth: f64,   // Share of the terrain's scrolling, 0 stays still
+    pub color: Color, // Fill of shapes, unused by images
+    pub generator: Generator,
+}
+
+// Where the background camera is, shared by every layer
+pub struct Parallax {
+    seed: u64,
+    noise: Noise,
+    x: f64, // Pixels travelled along the run
+    y: f64, // Pixels the camera has moved down, within MAX_DRIFT_Y
+}
+
+impl Parallax {
+    // Params: seed the background is generated from
+    pub fn new(seed: u64) -> Parallax {
+        Parallax {
+            seed,
+            noise: Noise::new(seed),
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    // Moves the camera along with the terrain
+    // Params: pixels the terrain moved left, pixels it moved down
+    pub fn scroll(&mut self, dx: i32, dy: i32) {
+        self.x += dx as f64;
+        self.y = (self.y + dy as f64).clamp(-MAX_DRIFT_Y, MAX_DRIFT_Y);
+    }
+
+    /*  Lays out one layer on the screen as it is now
+     *
+     *  - Takes in `layer` which is the layer to lay out
+     *  - Takes in `index` which is where the layer is in its biome's list,
+     *    so two layers of the same kind don't come out identical
+     *
+     *  - Returns the rectangles to fill with the layer's colour, or to copy
+     *    its texture to for images
+     */
+    pub fn rects(&self, layer: &Layer, index: usize) -> Vec<Rect> {
+        let scroll_x = self.x * layer.depth;
+        let drift_y = (self.y * layer.depth).round() as i32;
+        let salt = index as u64 + 1;
+
+        match layer.generator {
+            Generator::Image { y, h, .. } => {
+                let left = -(scroll_x.round() as i32).rem_euclid(CAM_W as i32);
+                vec![
+                    rect!(left, y + drift_y, CAM_W, h),
+                    rect!(left + CAM_W as i32, y + drift_y, CAM_W, h),
+                ]
+            }
+            Generator::Hills {
+                freq,
+                amp,
+                modifier,
+                mul,
+            } => {
+                let first = (scroll_x / HILL_COLUMN_W as f64).floor() as usize;
+                let left = (first as f64 * HILL_COLUMN_W as f64 - scroll_x).round() as i32;
+                (0..=CAM_W as usize / HILL_COLUMN_W as usize)
+                    .map(|i| {
+                        // Each layer reads its own stretch of the noise
+                        let column = first + i + salt as usize * 100_000;
+                        let h = proceduralgen::gen_perlin_hill_point(
+                            &self.noise,
+                            column,
+                            freq,
+                            amp,
+                            modifier,
+                            mul,
+                        );
+                        rect!(
+                            left + i as i32 * HILL_COLUMN_W,
+                            CAM_H as i32 - h as i32 + drift_y,
+                            HILL_COLUMN_W,
+                            CAM_H
+                        )
+                    })
+                    .collect()
+            }
+            Generator::Skyline {
+                horizon,
+                min_w,
+                max_w,
+                min_h,
+                max_h,
+                max_gap,
+            } => {
+                let slot_w = (max_w + max_gap) as f64;
+                let first = (scroll_x / slot_w).floor() as i64;
+                let slots = (CAM_W as f64 / slot_w).ceil() as i64 + 1;
+                (first..=first + slots)
+                    .map(|slot| {
+                        let r = scatter(self.seed, salt, slot);
+                        let w = pick(r, min_w, max_w);
+                        let h = pick(r >> 16, min_h, max_h);
+                        let shift = pick(r >> 32, 0, max_w + max_gap - w);
+                        rect!(
+                            (slot as f64 * slot_w - scroll_x).round() as i32 + shift as i32,
+                            horizon - h as i32 + drift_y,
+                            w,
+                            CAM_H
+                        )
+                    })
+                    .collect()
+            }
+            Generator::Clouds {
+                spacing,
+                top,
+                bottom,
+            } => {
+                let first = (scroll_x / spacing as f64).floor() as i64;
+                let slots = (CAM_W as f64 / spacing as f64).ceil() as i64 + 1;
+                let mut puffs = Vec::new();
+                for slot in first..=first + slots {
+                    let r = scatter(self.seed, salt, slot);
+                    // About a third of the sky is left clear
+                    if pick(r, 0, 2) == 0 {
+                        continue;
+                    }
+                    let w = pick(r >> 8, spacing / 4, spacing / 2) as i32;
+                    let x = (slot as f64 * spacing as f64 - scroll_x).round() as i32
+                        + pick(r >> 24, 0, spacing / 2) as i32;
+                    let y = top + pick(r >> 40, 0, (bottom - top).max(0) as u32) as i32 + drift_y;
+                    // A wide base with a smaller puff on top
+                    puffs.push(rect!(x, y, w, w / 5));
+                    puffs.push(rect!(x + w / 5, y - w / 8, w / 2, w / 8));
+                }
+                puffs
+            }
+        }
+    }
+}
+
+// Random looking but repeatable number for one slot of a layer
+fn scatter(seed: u64, salt: u64, slot: i64) -> u64 {
+    // splitmix64 finaliser
+    let mut z = seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (slot as u64);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Picks a number in [min, max] from random bits
+fn pick(r: u64, min: u32, max: u32) -> u32 {
+    if max <= min {
+        return min;
+    }
+    min + (r % (max - min + 1) as u64) as u32
+}
@@ -1,152 +1,487 @@
-use inf_runner::ObstacleType;
-use inf_runner::PowerType;
-use inf_runner::TerrainType;
+use crate::collision;
+use crate::collision::Contact;
+use crate::collision::Shape;
+use crate::proceduralgen::TerrainSegment;
+use crate::simulation;
+use crate::ObstacleType;
+use crate::PowerType;
+use crate::TerrainType;
 use sdl2::rect::Point;
 use sdl2::rect::Rect;
-use sdl2::render::Texture;
 
-use crate::runner::TILE_SIZE as InitTILE_SIZE;
+use crate::simulation::TILE_SIZE as InitTILE_SIZE;
 use std::f64::consts::PI;
 
 const LOWER_SPEED: f64 = -5.0;
 const UPPER_SPEED: f64 = 12.5;
+// Slowest the player runs while the run is still going
+pub const MIN_RUN_SPEED: f64 = 6.5;
 const OMEGA: f64 = PI / 18.0;
 const TILE_SIZE: f64 = InitTILE_SIZE as f64;
 
+pub const PLAYER_MASS: f64 = 3.0;
+// Furthest the player can be tilted from the ground and still land upright
+pub const MAX_UPRIGHT_ANGLE: f64 = OMEGA * 6.0;
+// Upward force applied on the tick the player jumps
+const JUMP_FORCE: f64 = 80.0;
+// How far the ground has to drop away ahead of a ramp's lip to launch from it
+const LIP_DROP: i32 = (TILE_SIZE / 4.0) as i32;
+// How far above the ground the bottom of a body can be and still be standing
+// on it. Bodies are stopped right at the ground, and the ground under their
+// middle is measured before they move along it
+const GROUND_CONTACT: i32 = (TILE_SIZE / 20.0) as i32;
+// Slowest a body has to hit something at to bounce off it, anything slower
+// just comes to rest against it
+const REST_SPEED: f64 = 2.0;
+
+// How a type of terrain behaves
+pub struct TerrainPhysics {
+    pub friction: f64, // Coefficient of kinetic friction
+    pub gravity: f64,  // Downward acceleration per tick
+    pub skate: f64,    // Share of the player's skate force they can push off it with
+    pub jump: f64,     // Share of the usual jump force
+    pub push: f64,     // Acceleration the surface itself adds along the ground
+    pub launch: f64,   // Upward speed given running off a lip, 0 for none
+    pub floats: bool,  // Bodies float in it rather than stand on it
+}
+
+// Indexed by TerrainType, so in the order its variants are declared
+const TERRAIN_PHYSICS: [TerrainPhysics; 8] = [
+    // Grass: moderate accel to max on flat
+    TerrainPhysics {
+        friction: 0.065,
+        gravity: 1.25,
+        skate: 1.0,
+        jump: 1.0,
+        push: 0.0,
+        launch: 0.0,
+        floats: false,
+    },
+    // Asphalt: quick accel to max on flat
+    TerrainPhysics {
+        friction: 0.035,
+        gravity: 1.25,
+        skate: 1.0,
+        jump: 1.0,
+        push: 0.0,
+        launch: 0.0,
+        floats: false,
+    },
+    // Sand: v slow accel to max on flat & short jumps
+    // less friction is more bc higher gravity
+    TerrainPhysics {
+        friction: 0.1,
+        gravity: 1.5,
+        skate: 1.0,
+        jump: 1.0,
+        push: 0.0,
+        launch: 0.0,
+        floats: false,
+    },
+    // Water: friction not applicable
+    TerrainPhysics {
+        friction: 0.0,
+        gravity: 1.25,
+        skate: 1.0,
+        jump: 1.0,
+        push: 0.0,
+        launch: 0.0,
+        floats: true,
+    },
+    // Ice: speed only comes from slopes
+    TerrainPhysics {
+        friction: 0.005,
+        gravity: 1.25,
+        skate: 0.0,
+        jump: 1.0,
+        push: 0.0,
+        launch: 0.0,
+        floats: false,
+    },
+    // Mud: drags the player down to their slowest
+    TerrainPhysics {
+        friction: 0.25,
+        gravity: 1.25,
+        skate: 1.0,
+        jump: 0.7,
+        push: 0.0,
+        launch: 0.0,
+        floats: false,
+    },
+    // Ramp: smooth, and launches off its lip
+    TerrainPhysics {
+        friction: 0.035,
+        gravity: 1.25,
+        skate: 1.0,
+        jump: 1.1,
+        push: 0.0,
+        launch: 20.0,
+        floats: false,
+    },
+    // Conveyor: carries the player along
+    TerrainPhysics {
+        friction: 0.035,
+        gravity: 1.25,
+        skate: 1.0,
+        jump: 1.0,
+        push: 0.1,
+        launch: 0.0,
+        floats: false,
+    },
+];
+
+impl TerrainType {
+    pub fn physics(&self) -> &'static TerrainPhysics {
+        &TERRAIN_PHYSICS[*self as usize]
+    }
+}
+
+// What a body is made of, for how it slides, bounces and falls
+pub struct Material {
+    pub friction: f64,    // Multiplies the friction of the ground under it
+    pub restitution: f64, // Share of its speed it bounces back with
+    pub gravity: f64,     // Share of gravity it feels
+    pub anchored: bool,   // Fixed in place, the world never moves it
+}
+
+// Indexed by ObstacleType, so in the order its variants are declared
+const MATERIALS: [Material; 7] = [
+    // Statue: stone, grinds to a halt and barely bounces
+    Material {
+        friction: 2.0,
+        restitution: 0.1,
+        gravity: 1.0,
+        anchored: false,
+    },
+    // Balloon: held up by its gas, so drifts once knocked loose
+    Material {
+        friction: 0.0,
+        restitution: 0.8,
+        gravity: 0.0,
+        anchored: false,
+    },
+    // Chest: wooden box, slides and tumbles
+    Material {
+        friction: 1.0,
+        restitution: 0.3,
+        gravity: 1.0,
+        anchored: false,
+    },
+    // Bench: legs catch on the ground
+    Material {
+        friction: 1.5,
+        restitution: 0.2,
+        gravity: 1.0,
+        anchored: false,
+    },
+    // Handrail, Ledge and PowerLine: rails are part of the street, how
+    // they're ground along is in grind.rs
+    RAIL_MATERIAL,
+    RAIL_MATERIAL,
+    RAIL_MATERIAL,
+];
+
+const RAIL_MATERIAL: Material = Material {
+    friction: 1.0,
+    restitution: 0.0,
+    gravity: 0.0,
+    anchored: true,
+};
+
+// Player rolls on their board and lands without bouncing
+const PLAYER_MATERIAL: Material = Material {
+    friction: 1.0,
+    restitution: 0.0,
+    gravity: 1.0,
+    anchored: false,
+};
+
+impl ObstacleType {
+    pub fn material(&self) -> &'static Material {
+        &MATERIALS[*self as usize]
+    }
+}
+
 pub struct Physics;
 
 impl Physics {
-    // Checks if entities are colliding
+    // Checks if entities are colliding, by their shapes as drawn. Also
+    // catches entityA having passed right through entityB over the last tick
     // Params: entityA, entityB
-    // Returns: true if entities are colliding, false otherwise
-    pub fn check_collision<'a>(
-        entity_a: &mut impl Entity<'a>,
-        entity_b: &mut impl Entity<'a>,
-    ) -> bool {
-        entity_a.hitbox().has_intersection(entity_b.hitbox())
+    // Returns: how entityA has to move to get out of entityB, None if they
+    //          aren't colliding
+    pub fn check_collision(
+        entity_a: &mut impl Entity,
+        entity_b: &mut impl Entity,
+    ) -> Option<Contact> {
+        let (moved_a, moved_b) = (entity_a.moved(), entity_b.moved());
+        Physics::swept_contact(
+            &entity_a.shape(),
+            (moved_a.0 - moved_b.0, moved_a.1 - moved_b.1),
+            &entity_b.shape(),
+        )
+    }
+
+    /*  Finds where a shape that has just moved collided with another
+     *
+     *  - Takes in `shape` which is where it is now
+     *  - Takes in `moved` which is how far it moved to get there, (right, down)
+     *  - Takes in `other` which is the shape it could have hit
+     *
+     *  - Returns how far it has to be pushed out of the other shape, back to
+     *    where it first touched if it went through it. None if it never
+     *    touched it
+     */
+    pub fn swept_contact(shape: &Shape, moved: (f64, f64), other: &Shape) -> Option<Contact> {
+        if let Some(contact) = shape.collide(other) {
+            return Some(contact);
+        }
+        let impact = shape.translated((-moved.0, -moved.1)).sweep(moved, other)?;
+        let into = -(moved.0 * impact.normal.0 + moved.1 * impact.normal.1);
+        let depth = (1.0 - impact.time) * into;
+        if depth > 0.0 {
+            Some(Contact {
+                normal: impact.normal,
+                depth,
+            })
+        } else {
+            None
+        }
+    }
+
+    // Checks if a body is standing on the ground, or sunk into it
+    // Params: body's hitbox, ground under its middle
+    // Returns: true if the body is on the ground
+    pub fn on_ground(hitbox: Rect, ground: Point) -> bool {
+        hitbox.contains_point(ground.offset(0, -GROUND_CONTACT))
+    }
+
+    /*  Stops a body at the ground rather than letting it move into it. It
+     *  touches the ground partway through its move, then slides along it
+     *  for the rest of the tick. A body that's already under the ground,
+     *  e.g. coming up out of water onto land or with ground that rose up
+     *  into it, is first put back on top of it
+     *
+     *  - Takes in `body` which is about to move by its velocity
+     *  - Takes in `across` which is how far it's moving right through the world
+     *  - Takes in `ground` which is the ground under it along the way
+     *
+     *  - Returns how fast it was moving into the ground when it got there,
+     *    None if it doesn't reach the ground this tick
+     */
+    pub fn stop_at_ground(body: &mut impl Body, across: f64, ground: &Shape) -> Option<f64> {
+        // Dropped onto the ground from above it, how far it ends up short
+        // of where it is now is how far it's sunk in
+        let bottom = body.hitbox().bottom() as f64;
+        let above = ground.top().map_or(0.0, |top| (bottom - top).max(0.0));
+        let shape = Shape::rect(body.hitbox(), 0.0).translated((0.0, -above));
+        let sunk = match shape.sweep((0.0, above), ground) {
+            Some(impact) => (1.0 - impact.time) * above,
+            None => 0.0,
+        };
+        if sunk > 0.0 {
+            let (x, y) = body.pos();
+            body.hard_set_pos((x, y - sunk));
+            body.align_hitbox_to_pos();
+        }
+
+        let motion = (across, -body.vel_y());
+        // Stood on by the hitbox, so turning doesn't lift a body off the ground
+        let shape = Shape::rect(body.hitbox(), 0.0);
+        let impact = shape.sweep(motion, ground)?;
+
+        let into = -(motion.0 * impact.normal.0 + motion.1 * impact.normal.1);
+        let drop = if into > 0.0 {
+            let (tangent_x, tangent_y) = (-impact.normal.1, impact.normal.0);
+            let along = (1.0 - impact.time) * (motion.0 * tangent_x + motion.1 * tangent_y);
+            impact.time * motion.1 + along * tangent_y
+        } else {
+            // Already leaving it, e.g. jumping
+            motion.1
+        };
+
+        // Only the body's height is its own to change, the world moves past
+        // it across, so it's given the speed that takes it to the ground
+        body.hard_set_vel((body.vel_x(), -drop));
+        Some(into.max(0.0))
     }
 
     // Checks if player hasn't landed on their head
     // Params: player, ground position as SDL point, angle of ground
     // Returns: true if player is upright, false otherwise
-    pub fn check_player_upright<'a>(player: &mut Player, angle: f64, ground: Point) -> bool {
-        let on_ground = player.hitbox().contains_point(ground);
+    pub fn check_player_upright(player: &mut Player, angle: f64, ground: Point) -> bool {
+        let on_ground = player
+            .shape()
+            .contains_point(ground.offset(0, -GROUND_CONTACT));
         if on_ground {
             player.was_flipping = false;
         }
-        !on_ground
-            || (player.theta() < OMEGA * 6.0 + angle
-                || player.theta() > 2.0 * PI - OMEGA * 6.0 + angle)
+        !on_ground || Physics::feet_down(player.theta(), collision::up(angle))
+    }
+
+    // Checks if something turned by theta has its feet towards a surface
+    // Params: clockwise turn in radians, unit normal pointing out of the surface
+    // Returns: true if it's within MAX_UPRIGHT_ANGLE of standing on it
+    pub fn feet_down(theta: f64, surface_normal: (f64, f64)) -> bool {
+        let up = collision::up(theta);
+        up.0 * surface_normal.0 + up.1 * surface_normal.1 >= MAX_UPRIGHT_ANGLE.cos()
     }
 
     // Applies terrain forces to a body, i.e. gravity, normal, and friction forces
     // Params: body, angle of ground, ground position as SDL Point, coeff of kinetic friction
     // Returns: none
-    pub fn apply_terrain_forces<'a>(
-        body: &mut impl Body<'a>,
+    pub fn apply_terrain_forces(
+        body: &mut impl Body,
         angle: f64,
         ground: Point,
         terrain_type: &TerrainType,
         power_up: Option<PowerType>,
     ) {
         // Set Gravity & Friction Strength From TerrainType
-        let fric_coeff: f64;
-        let mut g: f64 = 1.25;
-        match terrain_type {
-            TerrainType::Asphalt => {
-                //quick accel to max on flat
-                fric_coeff = 0.035;
-            }
-            TerrainType::Grass => {
-                //moderate accel to max on flat
-                fric_coeff = 0.065;
-            }
-            TerrainType::Sand => {
-                //v slow accel to max on flat & short jumps
-                fric_coeff = 0.1; //less friction is more bc higher gravity
-                g = 1.5;
-            }
-            TerrainType::Water => {
-                //friction not applicable
-                fric_coeff = 0.0;
-            }
-        }
-
-        // Lower gravity if power is low gravity
-        if let Some(PowerType::LowerGravity) = power_up {
-            g = g * 2.0 / 3.0;
-        }
+        let surface = terrain_type.physics();
+        let material = body.material();
+        let fric_coeff = surface.friction * material.friction;
+        let g = Physics::gravity(terrain_type, power_up) * material.gravity;
 
         // Gravity: mg
         body.apply_force((0.0, -body.mass() * g));
 
-        if let TerrainType::Water = terrain_type {
+        if surface.floats {
             return;
-        } else {
-            /*
-                Note on angles:
-                - Negative angle == uphill
-                - Positive angle == downhill
-                - sin(-x) is negative
-                - cos(-x) is positive
-            */
-
-            // If body is on ground, apply normal
-            let height = body.hitbox().height() as i32;
-            if body.hitbox().y() + height > ground.y() {
-                // Land on ground
-                if body.accel_y() < 0.0
-                    || (body.y() as f64 + 0.95 * (height as f64)) > ground.y() as f64
-                {
-                    body.hard_set_pos((
-                        body.x() as f64,
-                        ground.y() as f64 - 0.95 * (height as f64),
-                    ));
-                    body.hard_set_vel((body.vel_x(), 0.0));
-                    body.align_hitbox_to_pos();
-                }
+        }
 
-                // Normal: mg, but on an incline
-                // (-x, +y) on an uphill
-                // (+x, +y) on a downhill
-                body.apply_force((body.mass() * g * angle.sin(), body.mass() * g * angle.cos()));
-
-                // If body is on ground AND moving, apply KINETIC FRICTION
-                if body.vel_x().abs() + body.vel_y().abs() > 0.0 {
-                    // Friction: µmg, on an incline, perpendicular to normal
-                    // (-x, -y) on an uphill
-                    // (-x, +y) on an downhill
-                    // make negative if object is moving backwards
-                    let direction_adjust = body.vel_x().signum();
-                    body.apply_force((
-                        -fric_coeff * body.mass() * g * angle.cos() * direction_adjust,
-                        fric_coeff * body.mass() * g * angle.sin() * direction_adjust,
-                    ));
-                }
+        /*
+            Note on angles:
+            - Negative angle == uphill
+            - Positive angle == downhill
+            - sin(-x) is negative
+            - cos(-x) is positive
+        */
+
+        // If body is on ground, apply normal
+        // Landing on it is left to stop_at_ground
+        if Physics::on_ground(body.hitbox(), ground) {
+            // Normal: mg, but on an incline
+            // (-x, +y) on an uphill
+            // (+x, +y) on a downhill
+            body.apply_force((body.mass() * g * angle.sin(), body.mass() * g * angle.cos()));
+
+            // If body is on ground AND moving, apply KINETIC FRICTION
+            if body.vel_x().abs() + body.vel_y().abs() > 0.0 {
+                // Friction: µmg, on an incline, perpendicular to normal
+                // (-x, -y) on an uphill
+                // (-x, +y) on an downhill
+                // make negative if object is moving backwards
+                let direction_adjust = body.vel_x().signum();
+                body.apply_force((
+                    -fric_coeff * body.mass() * g * angle.cos() * direction_adjust,
+                    fric_coeff * body.mass() * g * angle.sin() * direction_adjust,
+                ));
             }
+
+            // Moving surfaces carry whatever is on them along the ground
+            body.apply_force((
+                surface.push * body.mass() * angle.cos(),
+                -surface.push * body.mass() * angle.sin(),
+            ));
+        }
+    }
+
+    // Strength of gravity over a type of terrain
+    // Params: terrain type, player's power up
+    // Returns: downward acceleration per tick
+    pub fn gravity(terrain_type: &TerrainType, power_up: Option<PowerType>) -> f64 {
+        let g = terrain_type.physics().gravity;
+
+        // Lower gravity if power is low gravity
+        if let Some(PowerType::LowerGravity) = power_up {
+            g * 2.0 / 3.0
+        } else {
+            g
         }
     }
 
     // Applies forward motion to player, as if they're propelling themselves
     // Serves to oppose and overcome backwards forces (friction and normal)
-    // Params: player, angle of ground, ground position is as SDL Point
+    // Params: player, angle of ground, ground position is as SDL Point, type
+    //         of the ground
     // Returns: None
-    pub fn apply_skate_force(player: &mut Player, angle: f64, ground: Point) {
+    pub fn apply_skate_force(
+        player: &mut Player,
+        angle: f64,
+        ground: Point,
+        terrain_type: &TerrainType,
+    ) {
         // Skate force
-        let skate_force = 1.0 / 7.0 * player.mass();
+        let skate_force = 1.0 / 7.0 * player.mass() * terrain_type.physics().skate;
 
-        if player.hitbox().contains_point(ground) {
+        if Physics::on_ground(player.hitbox(), ground) {
             // (+x, +y) on an uphill
             // (+x, -y) on a downhill
             player.apply_force((skate_force * angle.cos(), -skate_force * angle.sin()));
         }
     }
 
+    // Throws the player upwards as they run off the lip of a surface that
+    // launches, i.e. when the ground just ahead of them drops away
+    // Params: player, type of the ground under them, ground under them,
+    //         ground just ahead of them (None over a pit)
+    // Returns: true if the player was launched
+    pub fn launch_off_lip(
+        player: &mut Player,
+        terrain_type: &TerrainType,
+        ground: Point,
+        ahead: Option<Point>,
+    ) -> bool {
+        let launch = terrain_type.physics().launch;
+        if launch <= 0.0 || player.is_jumping() || !Physics::on_ground(player.hitbox(), ground) {
+            return false;
+        }
+        let drops_away = match ahead {
+            Some(ahead) => ahead.y() - ground.y() > LIP_DROP,
+            None => true,
+        };
+        if !drops_away {
+            return false;
+        }
+
+        player.hard_set_vel((player.vel_x(), launch));
+        player.jumping = true;
+        true
+    }
+
+    // Applies gravity along a rail and the rail's friction to a player
+    // grinding it. Nothing holds them up off the rail, that's left to
+    // ride_rail
+    // Params: player, slope of the rail, type of the ground under it, coeff
+    //         of kinetic friction of the rail
+    // Returns: none
+    pub fn apply_grind_forces(
+        player: &mut Player,
+        angle: f64,
+        terrain_type: &TerrainType,
+        friction: f64,
+    ) {
+        let g = Physics::gravity(terrain_type, player.power_up());
+        // Along the rail, then only its forward part, as the rail holds the
+        // player's height. Downhill is a positive angle, so speeds them up
+        let along = g * (angle.sin() - friction * angle.cos() * player.vel_x().signum());
+        player.apply_force((player.mass() * along * angle.cos(), 0.0));
+    }
+
+    // Applies the wind of the current biome, pushing the same on the ground
+    // and in the air
+    // Params: body, wind as a horizontal acceleration, positive is forwards
+    // Returns: none
+    pub fn apply_wind(body: &mut impl Body, wind: f64) {
+        body.apply_force((body.mass() * wind, 0.0));
+    }
+
     // Applies upward spring force using Hooke's law
     // Dependent on player's position: F = kx
     // Params: player, spring object
     // Returns: none
-    pub fn apply_bounce<'a>(player: &mut Player, body: &impl Body<'a>) {
+    pub fn apply_bounce(player: &mut Player, body: &impl Body) {
         // Spring force constant
         let k = 0.2;
 
@@ -185,21 +520,162 @@ impl Physics {
             player.apply_force((0.0, p * g * submerged_area));
 
             // Return player's angle back to vertical
-            // Stronger effect when deeper underwater
-            player.theta = player.theta()
-                - 0.05
-                    * player.theta()
-                    * (submerged_area
-                        / (player.hitbox().width() * player.hitbox().height()) as f64);
+            // Stronger effect when deeper underwater, up to all of them
+            let submerged =
+                submerged_area / (player.hitbox().width() * player.hitbox().height()) as f64;
+            player.theta = player.theta() - 0.05 * player.theta() * submerged.min(1.0);
         }
     }
 }
 
-/******************************* TRAITS *******************************/
+/******************************* WORLD ********************************/
+
+// Steps loose bodies over the terrain all the same way: gravity, landing on
+// and sliding along the ground, bouncing, spinning, and knocking into each
+// other. The player isn't one of them, skating and jumping are stepped by
+// the tick itself
+pub struct World<'a> {
+    terrain: &'a [TerrainSegment],
+}
+
+impl<'a> World<'a> {
+    pub fn new(terrain: &'a [TerrainSegment]) -> World<'a> {
+        World { terrain }
+    }
+
+    /*  Moves every body on by a tick, then sorts out any that have run into
+     *  each other. Anchored bodies are left where they are
+     *
+     *  - Takes in `bodies` which are the bodies to step
+     *  - Takes in `game_over` which is true once the run has ended
+     *
+     *  - Returns none
+     */
+    pub fn step<B: Body>(&self, bodies: &mut [B], game_over: bool) {
+        for body in bodies.iter_mut().filter(|body| !body.material().anchored) {
+            self.step_body(body, game_over);
+        }
+
+        for i in 0..bodies.len() {
+            let (before, after) = bodies.split_at_mut(i + 1);
+            let a = &mut before[i];
+            if a.material().anchored {
+                continue;
+            }
+            for b in after.iter_mut().filter(|b| !b.material().anchored) {
+                World::collide_bodies(a, b);
+            }
+        }
+    }
+
+    // Stands a body that's just been put on the ground the way it'll rest
+    // there, so it's turned to the slope before anything is checked against
+    // it
+    // Params: body
+    // Returns: none
+    pub fn settle(&self, body: &mut impl Body) {
+        self.step_body(body, false);
+    }
+
+    // Applies the terrain to a single body and moves it, measuring the
+    // ground under it the same way a tick does for the player
+    fn step_body(&self, body: &mut impl Body, game_over: bool) {
+        let half = TILE_SIZE as i32 / 2;
+        let left_x = body.x();
+        let left = simulation::get_ground_coord(self.terrain, left_x);
+        let middle = simulation::get_ground_coord(self.terrain, left_x + half);
+        let right = simulation::get_ground_coord(self.terrain, left_x + TILE_SIZE as i32);
+        let angle = simulation::ground_angle(left, middle, right);
+        let ground = simulation::ground_or_pit(middle, left_x + half);
+        let terrain_type = simulation::get_ground_type(self.terrain, left_x);
+        let on_water = terrain_type.physics().floats;
+
+        Physics::apply_terrain_forces(body, angle, ground, terrain_type, None);
+        body.update_vel(game_over);
+        let landed = if on_water {
+            None
+        } else {
+            let under = simulation::ground_under(self.terrain, left_x + half, body.vel_x() as i32);
+            Physics::stop_at_ground(body, body.vel_x(), &under)
+        };
+        body.update_pos(ground, angle, on_water, game_over);
+        body.reset_accel();
+
+        // Once it's over, whatever was knocked loose keeps spinning
+        if game_over {
+            return;
+        }
+        if let Some(speed) = landed {
+            let restitution = body.material().restitution;
+            if speed > REST_SPEED {
+                body.hard_set_vel((body.vel_x(), speed * restitution));
+                body.hard_set_omega(body.omega() * restitution);
+            } else {
+                body.hard_set_omega(0.0);
+            }
+        }
+    }
+
+    // Pushes two overlapping bodies apart and bounces them off each other.
+    // Bodies at rest don't push each other, so ones laid out touching stay
+    // where they were put
+    // Params: the two bodies
+    // Returns: none
+    fn collide_bodies(a: &mut impl Body, b: &mut impl Body) {
+        if (a.vel_x(), a.vel_y(), b.vel_x(), b.vel_y()) == (0.0, 0.0, 0.0, 0.0) {
+            return;
+        }
+        let contact = match a.shape().collide(&b.shape()) {
+            Some(contact) => contact,
+            None => return,
+        };
+        let (nx, ny) = contact.normal;
+        let (inv_a, inv_b) = (1.0 / a.mass(), 1.0 / b.mass());
+
+        // Out of each other, the lighter one moving further
+        let push_a = contact.depth * inv_a / (inv_a + inv_b);
+        let push_b = contact.depth - push_a;
+        a.hard_set_pos((a.x() as f64 + nx * push_a, a.y() as f64 + ny * push_a));
+        b.hard_set_pos((b.x() as f64 - nx * push_b, b.y() as f64 - ny * push_b));
+        a.align_hitbox_to_pos();
+        b.align_hitbox_to_pos();
+
+        // Velocities are kept with y going up, the normal has it going down
+        let closing = (a.vel_x() - b.vel_x()) * nx - (a.vel_y() - b.vel_y()) * ny;
+        if closing >= 0.0 {
+            // Already moving apart
+            return;
+        }
+        let restitution = a.material().restitution.max(b.material().restitution);
+        let impulse = -(1.0 + restitution) * closing / (inv_a + inv_b);
+        a.hard_set_vel((
+            a.vel_x() + impulse * inv_a * nx,
+            a.vel_y() - impulse * inv_a * ny,
+        ));
+        b.hard_set_vel((
+            b.vel_x() - impulse * inv_b * nx,
+            b.vel_y() + impulse * inv_b * ny,
+        ));
+
+        // Taken as pushing where they meet, halfway between their middles.
+        // Screen y goes down, so a positive turning force is clockwise, and
+        // omega is anticlockwise
+        let (a_mid, b_mid) = (a.center(), b.center());
+        let (rx, ry) = (
+            (b_mid.x() - a_mid.x()) as f64 / 2.0,
+            (b_mid.y() - a_mid.y()) as f64 / 2.0,
+        );
+        let torque = impulse * (rx * ny - ry * nx);
+        a.hard_set_omega(a.omega() - torque / a.rotational_inertia());
+        b.hard_set_omega(b.omega() - torque / b.rotational_inertia());
+    }
+}
+
+/**********************************************************************/
 
-pub trait Entity<'a> {
-    fn texture(&self) -> &Texture<'a>;
+/******************************* TRAITS *******************************/
 
+pub trait Entity {
     fn x(&self) -> i32 {
         self.hitbox().x()
     }
@@ -211,20 +687,45 @@ pub trait Entity<'a> {
     }
 
     fn hitbox(&self) -> Rect;
+    // How far it went through the world over the last tick, (right, down)
+    fn moved(&self) -> (f64, f64) {
+        (0.0, 0.0)
+    }
+    // What it collides as, the hitbox unless the entity is turned or
+    // irregularly shaped
+    fn shape(&self) -> Shape {
+        Shape::rect(self.hitbox(), 0.0)
+    }
     fn align_hitbox_to_pos(&mut self); // After the pos is set with f64s, this method moves hitbox
                                        // to proper SDL coordinates using i32s
 
+    // Hitbox position as of the start of the current tick
+    fn prev_pos(&self) -> (i32, i32);
+    fn save_prev_pos(&mut self); // Called at the start of every tick
+
+    // Position to draw at, blended between the last two ticks
+    // Params: how far the frame is between the last tick and the next (0.0 - 1.0)
+    // Returns: blended top left corner of the hitbox
+    fn lerp_pos(&self, alpha: f64) -> Point {
+        let (prev_x, prev_y) = self.prev_pos();
+        Point::new(
+            prev_x + ((self.x() - prev_x) as f64 * alpha).round() as i32,
+            prev_y + ((self.y() - prev_y) as f64 * alpha).round() as i32,
+        )
+    }
+
     // Adjusts terrain postion in runner.rs based on camera_adj_x & camera_adj_y
     fn camera_adj(&mut self, x_adj: i32, y_adj: i32);
 }
 
-pub trait Body<'a>: Entity<'a> {
+pub trait Body: Entity {
     fn mass(&self) -> f64;
     fn rotational_inertia(&self) -> f64 {
         let radius = (self.hitbox().width() as f64) / 2.0;
         self.mass() * radius * radius
     }
     fn update_pos(&mut self, ground: Point, angle: f64, on_water: bool, game_over: bool);
+    fn pos(&self) -> (f64, f64);
     fn hard_set_pos(&mut self, pos: (f64, f64)); // Official method to hardcode position
 
     fn vel_x(&self) -> f64;
@@ -241,9 +742,12 @@ pub trait Body<'a>: Entity<'a> {
     fn rotate(&mut self);
 
     fn omega(&self) -> f64;
+    fn hard_set_omega(&mut self, omega: f64); // Official method to hardcode angular speed
+
+    fn material(&self) -> &'static Material;
 }
 
-pub trait Collectible<'a>: Entity<'a> {
+pub trait Collectible: Entity {
     fn update_pos(&mut self, x: i32, y: i32);
     fn collect(&mut self);
     fn collected(&self) -> bool;
@@ -253,68 +757,48 @@ pub trait Collectible<'a>: Entity<'a> {
 
 /****************************** PLAYER ********************************/
 
-// Holds the player's current power and texture associated
-pub struct PlayerPower<'a> {
-    power_up: Option<PowerType>,
-    texture: &'a Texture<'a>,
-}
-
-impl<'a> PlayerPower<'a> {
-    pub fn new(power_up: Option<PowerType>, tex: &'a Texture<'a>) -> PlayerPower<'a> {
-        PlayerPower {
-            power_up: power_up,
-            texture: tex,
-        }
-    }
-
-    // Return set power-up or None if set to None
-    fn power_up(&self) -> Option<PowerType> {
-        self.power_up
-    }
-
-    // Return texture associated.
-    fn texture(&self) -> &Texture<'a> {
-        self.texture
-    }
-}
-
-pub struct Player<'a> {
+pub struct Player {
     pub pos: (f64, f64),
     velocity: (f64, f64),
     accel: (f64, f64),
     hitbox: Rect,
+    prev_pos: (i32, i32),
+    moved: (f64, f64),
 
     theta: f64, // angle of rotation, in radians
     omega: f64, // angular speed
 
     mass: f64,
-    texture: &'a Texture<'a>,
-    power_up_stuct: PlayerPower<'a>,
+    power_up: Option<PowerType>,
 
     jumping: bool,
     flipping: bool,
     was_flipping: bool,
+    flip_forward: bool, // Flips go forwards rather than backwards
+    grinding: bool,
 }
 
-impl<'a> Player<'a> {
-    pub fn new(hitbox: Rect, mass: f64, texture: &'a Texture<'a>) -> Player<'a> {
-        let empty_player = PlayerPower::new(None, texture); // Inital texture doesn't matter
+impl Player {
+    pub fn new(hitbox: Rect, mass: f64) -> Player {
         Player {
             pos: (hitbox.x() as f64, hitbox.y() as f64),
             velocity: (0.0, 0.0),
             accel: (0.0, 0.0),
             hitbox,
+            prev_pos: (hitbox.x(), hitbox.y()),
+            moved: (0.0, 0.0),
 
             theta: 0.0,
             omega: 0.0,
 
-            texture,
             mass,
-            power_up_stuct: empty_player,
+            power_up: None,
 
             jumping: true,
             flipping: false,
             was_flipping: false,
+            flip_forward: false,
+            grinding: false,
         }
     }
 
@@ -334,20 +818,19 @@ impl<'a> Player<'a> {
         self.was_flipping
     }
 
-    // Returns specific power-up player has, or None if player hasn't collected a power-up
-    pub fn power_up(&self) -> Option<PowerType> {
-        self.power_up_stuct.power_up()
+    // True while the player's board is on a rail
+    pub fn is_grinding(&self) -> bool {
+        self.grinding
     }
 
-    // Returns specific power-up texture. Should only be called after a check if player has a power-up
-    pub fn power_up_tex(&self) -> &Texture<'a> {
-        self.power_up_stuct.texture()
+    // Returns specific power-up player has, or None if player hasn't collected a power-up
+    pub fn power_up(&self) -> Option<PowerType> {
+        self.power_up
     }
 
     // Setter for power-up
-    pub fn set_power_up(&mut self, power_up: Option<PowerType>, texture: &'a Texture<'a>) {
-        let power_struct = PlayerPower::new(power_up, texture);
-        self.power_up_stuct = power_struct;
+    pub fn set_power_up(&mut self, power_up: Option<PowerType>) {
+        self.power_up = power_up;
     }
 
     // Brings player's rotational velocity to a stop
@@ -359,11 +842,23 @@ impl<'a> Player<'a> {
     pub fn resume_flipping(&mut self) {
         self.flipping = true;
         self.was_flipping = true;
-        self.omega = OMEGA;
+        // omega is anticlockwise, which is backwards
+        self.omega = if self.flip_forward { -OMEGA } else { OMEGA };
+    }
+
+    // Picks which way flips go until the player lands, turning them the
+    // other way straight away if they're already flipping
+    // Params: true for forwards, false for backwards
+    pub fn set_flip_direction(&mut self, forward: bool) {
+        self.flip_forward = forward;
+        if self.flipping {
+            self.resume_flipping();
+        }
     }
 
-    // Returns true if a jump was initiated
-    pub fn jump(&mut self, ground: Point) -> bool {
+    // Returns true if a jump was initiated, jumps are weaker or stronger
+    // depending on the ground jumped from
+    pub fn jump(&mut self, ground: Point, terrain_type: &TerrainType) -> bool {
         let height = self.hitbox.height() as f64;
         // Jump if on ground (with some give as to what "on ground" means)
         if self.hitbox().y() + (1.05 * height) as i32 > ground.y() {
@@ -371,7 +866,7 @@ impl<'a> Player<'a> {
             self.hard_set_pos((self.pos.0, ground.y() as f64 - height));
             self.align_hitbox_to_pos();
             // Apply upward force
-            self.apply_force((0.0, 80.0));
+            self.apply_force((0.0, JUMP_FORCE * terrain_type.physics().jump));
             self.jumping = true;
             true
         } else {
@@ -379,6 +874,39 @@ impl<'a> Player<'a> {
         }
     }
 
+    // Stands the player on a rail, where they stay until they leave it
+    // Params: point on the rail under their middle, angle to stand at
+    // Returns: none
+    pub fn ride_rail(&mut self, rail: Point, theta: f64) {
+        let drop = (rail.y() - self.hitbox.height() as i32) as f64 - self.pos.1;
+        self.pos.1 += drop;
+        self.moved.1 += drop;
+        self.velocity.1 = 0.0;
+        self.align_hitbox_to_pos();
+
+        self.theta = theta;
+        self.omega = 0.0;
+        // Off the ground as far as landing goes
+        self.jumping = true;
+        self.flipping = false;
+        self.was_flipping = false;
+        self.grinding = true;
+    }
+
+    // Takes the player off the rail, to fall from there
+    // Params: angle to leave at
+    // Returns: none
+    pub fn leave_rail(&mut self, theta: f64) {
+        self.theta = theta;
+        self.grinding = false;
+    }
+
+    // Jumps off the rail, as high as a jump from flat ground
+    pub fn jump_off_rail(&mut self) {
+        self.grinding = false;
+        self.apply_force((0.0, JUMP_FORCE));
+    }
+
     //flips player if directed by user, or if player has angular momentum
     //returns true if player is rotating voluntarily,
     //returns false if rotating because of angular momentum or not rotating
@@ -386,11 +914,7 @@ impl<'a> Player<'a> {
         if self.is_flipping() {
             self.rotate();
             //Player rotated halfway, so let's call it a flip
-            if self.theta() < PI {
-                true
-            } else {
-                false
-            }
+            self.theta() < PI
         } else if self.was_flipping() || game_over {
             //allows for momentum when player stops flipping
             //to adjust rate of angular velocity decrease,
@@ -408,28 +932,18 @@ impl<'a> Player<'a> {
     }
 
     // Handles collisions with player and any type of obstacle
-    // Params: obstacle to collide with
+    // Params: obstacle to collide with, contact found by check_collision
     // Returns: true if real game-ending collision occurs, false otherwise
-    pub fn collide_obstacle(&mut self, obstacle: &mut Obstacle) -> bool {
+    pub fn collide_obstacle(&mut self, obstacle: &mut Obstacle, contact: Contact) -> bool {
         let mut shielded = false;
         if let Some(PowerType::Shield) = self.power_up() {
             // Put on shield if applicable
             shielded = true;
         }
 
-        // if the collision box is taller than it is wide, the player hit the side of the object
-        if (self
-            .hitbox()
-            .intersection(obstacle.hitbox())
-            .unwrap()
-            .height()
-            > self
-                .hitbox()
-                .intersection(obstacle.hitbox())
-                .unwrap()
-                .width())
-            && self.hitbox.x() < obstacle.hitbox.x()
-        {
+        // if the player is pushed back more than up, they hit the side of the object
+        let (normal_x, normal_y) = contact.normal;
+        if normal_x.abs() > normal_y.abs() && normal_x < 0.0 {
             // Response to collision dependent on type of obstacle
             match obstacle.obstacle_type {
                 // For statue and chest, elastic collision
@@ -442,9 +956,7 @@ impl<'a> Player<'a> {
                         // https://en.wikipedia.org/wiki/Elastic_collision#One-dimensional_Newtonian
                         // Assumed object has velocity (0,0)
                         // Assumed player has velocity (vx,vy)
-                        let angle = ((self.center().y() - obstacle.center().y()) as f64
-                            / (self.center().x() - obstacle.center().x()) as f64)
-                            .atan();
+                        let angle = (normal_y / normal_x).atan();
                         let p_mass = self.mass();
                         let o_mass = obstacle.mass();
                         let p_vx = self.velocity.0;
@@ -479,10 +991,7 @@ impl<'a> Player<'a> {
                         } else {
                             // Move player
                             self.hard_set_vel((p_vx_f, p_vy_f));
-                            self.hard_set_pos((
-                                obstacle.x() as f64 - 1.05 * TILE_SIZE,
-                                self.y() as f64,
-                            ));
+                            self.push_out(contact);
                             // Apply rotational velocity due to game ending collision
                             self.omega = -alpha; // to player
                             self.rotate();
@@ -497,17 +1006,18 @@ impl<'a> Player<'a> {
                 }
                 // For Balloon, do nothing upon SIDE collision
                 ObstacleType::Balloon => false,
+                // Rails can be run through, only grinding them does anything
+                ObstacleType::Handrail | ObstacleType::Ledge | ObstacleType::PowerLine => false,
             }
         }
-        // if the collision box is wider than it is tall, the player hit the top of the object
+        // if the player is pushed up, they hit the top of the object
         // don't apply the collision to the top of an object if the player is moving upward, otherwise they will "stick" to the top on the way up
-        else if self.is_jumping() && self.vel_y() < 0.0 {
+        else if normal_y < 0.0 && self.is_jumping() && self.vel_y() < 0.0 {
             match obstacle.obstacle_type {
                 // On top collision with chest, treat the chest as if it's normal ground
                 ObstacleType::Chest | ObstacleType::Bench => {
                     if !obstacle.collided() {
-                        self.pos.1 = obstacle.y() as f64 - 0.95 * (TILE_SIZE as f64);
-                        self.align_hitbox_to_pos();
+                        self.push_out(contact);
                         self.velocity.1 = 0.0;
                         self.jumping = false;
                         self.apply_force((0.0, self.mass()));
@@ -516,7 +1026,7 @@ impl<'a> Player<'a> {
                         obstacle.collected = true;
                         obstacle.hard_set_vel((0.0, self.mass() / 2.0));
 
-                        if self.theta() < OMEGA * 6.0 || self.theta() > 360.0 - OMEGA * 6.0 {
+                        if Physics::feet_down(self.theta(), contact.normal) {
                             self.theta = 0.0;
                             false
                         } else {
@@ -541,12 +1051,21 @@ impl<'a> Player<'a> {
                     }
                     false
                 }
+                // Landing on a rail is left to grind.rs
+                ObstacleType::Handrail | ObstacleType::Ledge | ObstacleType::PowerLine => false,
             }
         } else {
             false
         }
     }
 
+    // Moves the player out of whatever they collided with
+    fn push_out(&mut self, contact: Contact) {
+        self.pos.0 += contact.normal.0 * contact.depth;
+        self.pos.1 += contact.normal.1 * contact.depth;
+        self.align_hitbox_to_pos();
+    }
+
     // Collects a coin
     // Params: coin to collect
     // Returns: true if coin has been collected, false otherwise (e.g. if it's been collected already)
@@ -562,9 +1081,9 @@ impl<'a> Player<'a> {
     // Receives new power-up
     // Params: power to use
     // Returns:
-    pub fn collide_power(&mut self, power: &mut Power, texture: &'a Texture<'a>) -> bool {
+    pub fn collide_power(&mut self, power: &mut Power) -> bool {
         if !power.collected() {
-            self.set_power_up(Some(power.power_type()), texture);
+            self.set_power_up(Some(power.power_type()));
             power.collect();
             true
         } else {
@@ -573,17 +1092,23 @@ impl<'a> Player<'a> {
     }
 }
 
-impl<'a> Entity<'a> for Player<'a> {
-    //getter for player texture
-    fn texture(&self) -> &Texture<'a> {
-        self.texture
-    }
-
+impl Entity for Player {
     //getter for player hitbox
     fn hitbox(&self) -> Rect {
         self.hitbox
     }
 
+    // Kept by update_pos, as the world moves past the player rather than
+    // the player moving through it
+    fn moved(&self) -> (f64, f64) {
+        self.moved
+    }
+
+    // Hitbox turned as the player is
+    fn shape(&self) -> Shape {
+        Shape::rect(self.hitbox, self.theta)
+    }
+
     //aligns player hitbox to their updated position
     fn align_hitbox_to_pos(&mut self) {
         self.hitbox.set_x(self.pos.0 as i32);
@@ -597,9 +1122,17 @@ impl<'a> Entity<'a> for Player<'a> {
 
         self.align_hitbox_to_pos();
     }
+
+    fn prev_pos(&self) -> (i32, i32) {
+        self.prev_pos
+    }
+
+    fn save_prev_pos(&mut self) {
+        self.prev_pos = (self.hitbox.x(), self.hitbox.y());
+    }
 }
 
-impl<'a> Body<'a> for Player<'a> {
+impl Body for Player {
     //getter for player mass
     fn mass(&self) -> f64 {
         self.mass
@@ -608,18 +1141,27 @@ impl<'a> Body<'a> for Player<'a> {
     //update player position
     fn update_pos(&mut self, ground: Point, angle: f64, on_water: bool, game_over: bool) {
         self.pos.1 -= self.vel_y();
+        self.moved = (self.vel_x(), -self.vel_y());
+        self.align_hitbox_to_pos();
 
         // Match the angle of the ground if on ground
-
-        if self.hitbox().contains_point(ground) && !on_water && !game_over {
+        if Physics::on_ground(self.hitbox, ground) && !on_water && !game_over {
+            // Landing on anything but their feet is left for
+            // check_player_upright to catch, rather than stood back up
+            if self.jumping && !Physics::feet_down(self.theta, collision::up(angle)) {
+                return;
+            }
             self.theta = angle;
             if self.jumping {
                 self.jumping = false;
                 self.was_flipping = false;
+                self.flip_forward = false;
             }
         }
+    }
 
-        self.align_hitbox_to_pos();
+    fn pos(&self) -> (f64, f64) {
+        self.pos
     }
 
     fn hard_set_pos(&mut self, pos: (f64, f64)) {
@@ -643,7 +1185,7 @@ impl<'a> Body<'a> for Player<'a> {
         if game_over {
             self.velocity.0 = (self.velocity.0 + self.accel.0).clamp(-upper_x_speed, upper_x_speed);
         } else {
-            self.velocity.0 = (self.velocity.0 + self.accel.0).clamp(6.5, upper_x_speed);
+            self.velocity.0 = (self.velocity.0 + self.accel.0).clamp(MIN_RUN_SPEED, upper_x_speed);
         }
 
         self.velocity.1 =
@@ -683,21 +1225,29 @@ impl<'a> Body<'a> for Player<'a> {
     fn omega(&self) -> f64 {
         self.omega
     }
+
+    fn hard_set_omega(&mut self, omega: f64) {
+        self.omega = omega;
+    }
+
+    fn material(&self) -> &'static Material {
+        &PLAYER_MATERIAL
+    }
 }
 
 /**********************************************************************/
 
 /*************************** OBSTACLE *********************************/
 
-pub struct Obstacle<'a> {
+pub struct Obstacle {
     pub pos: (f64, f64),
     velocity: (f64, f64),
     accel: (f64, f64),
     hitbox: Rect,
+    prev_pos: (i32, i32),
 
     mass: f64,
     pub value: i32,
-    texture: &'a Texture<'a>,
     obstacle_type: ObstacleType,
 
     theta: f64,
@@ -709,23 +1259,17 @@ pub struct Obstacle<'a> {
     pub delete_me: bool,
 }
 
-impl<'a> Obstacle<'a> {
-    pub fn new(
-        hitbox: Rect,
-        mass: f64,
-        value: i32,
-        texture: &'a Texture<'a>,
-        obstacle_type: ObstacleType,
-    ) -> Obstacle<'a> {
+impl Obstacle {
+    pub fn new(hitbox: Rect, mass: f64, value: i32, obstacle_type: ObstacleType) -> Obstacle {
         Obstacle {
             pos: (hitbox.x() as f64, hitbox.y() as f64),
             velocity: (0.0, 0.0),
             accel: (0.0, 0.0),
             hitbox,
+            prev_pos: (hitbox.x(), hitbox.y()),
 
             mass,
             value,
-            texture,
             obstacle_type,
 
             theta: 0.0,
@@ -760,15 +1304,15 @@ impl<'a> Obstacle<'a> {
     }
 }
 
-impl<'a> Entity<'a> for Obstacle<'a> {
-    fn texture(&self) -> &Texture<'a> {
-        self.texture
-    }
-
+impl Entity for Obstacle {
     fn hitbox(&self) -> Rect {
         self.hitbox
     }
 
+    fn shape(&self) -> Shape {
+        Shape::fitted(self.obstacle_type.outline(), self.hitbox, self.theta)
+    }
+
     fn align_hitbox_to_pos(&mut self) {
         self.hitbox.set_x(self.pos.0 as i32);
         self.hitbox.set_y(self.pos.1 as i32);
@@ -781,15 +1325,23 @@ impl<'a> Entity<'a> for Obstacle<'a> {
 
         self.align_hitbox_to_pos();
     }
+
+    fn prev_pos(&self) -> (i32, i32) {
+        self.prev_pos
+    }
+
+    fn save_prev_pos(&mut self) {
+        self.prev_pos = (self.hitbox.x(), self.hitbox.y());
+    }
 }
 
-impl<'a> Body<'a> for Obstacle<'a> {
+impl Body for Obstacle {
     fn mass(&self) -> f64 {
         self.mass
     }
 
     fn update_pos(&mut self, ground: Point, angle: f64, _on_water: bool, game_over: bool) {
-        if self.hitbox.contains_point(ground) && !game_over {
+        if Physics::on_ground(self.hitbox, ground) && !game_over {
             self.theta = angle;
         } else {
             self.rotate();
@@ -800,6 +1352,10 @@ impl<'a> Body<'a> for Obstacle<'a> {
         self.align_hitbox_to_pos();
     }
 
+    fn pos(&self) -> (f64, f64) {
+        self.pos
+    }
+
     fn hard_set_pos(&mut self, pos: (f64, f64)) {
         self.pos.0 = pos.0;
         self.pos.1 = pos.1;
@@ -864,25 +1420,33 @@ impl<'a> Body<'a> for Obstacle<'a> {
     fn omega(&self) -> f64 {
         self.omega
     }
+
+    fn hard_set_omega(&mut self, omega: f64) {
+        self.omega = omega;
+    }
+
+    fn material(&self) -> &'static Material {
+        self.obstacle_type.material()
+    }
 }
 
 /**********************************************************************/
 
 /**************************** COIN ************************************/
 
-pub struct Coin<'a> {
+pub struct Coin {
     pub pos: (i32, i32),
     hitbox: Rect,
-    texture: &'a Texture<'a>,
+    prev_pos: (i32, i32),
     value: i32,
     collected: bool,
 }
 
-impl<'a> Coin<'a> {
-    pub fn new(hitbox: Rect, texture: &'a Texture<'a>, value: i32) -> Coin<'a> {
+impl Coin {
+    pub fn new(hitbox: Rect, value: i32) -> Coin {
         Coin {
             pos: (hitbox.x(), hitbox.y()),
-            texture,
+            prev_pos: (hitbox.x(), hitbox.y()),
             hitbox,
             value,
             collected: false,
@@ -899,11 +1463,7 @@ impl<'a> Coin<'a> {
     }
 }
 
-impl<'a> Entity<'a> for Coin<'a> {
-    fn texture(&self) -> &Texture<'a> {
-        self.texture
-    }
-
+impl Entity for Coin {
     fn hitbox(&self) -> Rect {
         self.hitbox
     }
@@ -920,9 +1480,17 @@ impl<'a> Entity<'a> for Coin<'a> {
 
         self.align_hitbox_to_pos();
     }
+
+    fn prev_pos(&self) -> (i32, i32) {
+        self.prev_pos
+    }
+
+    fn save_prev_pos(&mut self) {
+        self.prev_pos = (self.hitbox.x(), self.hitbox.y());
+    }
 }
 
-impl<'a> Collectible<'a> for Coin<'a> {
+impl Collectible for Coin {
     fn update_pos(&mut self, x: i32, y: i32) {
         self.pos.0 = x;
         self.pos.1 = y;
@@ -941,20 +1509,20 @@ impl<'a> Collectible<'a> for Coin<'a> {
 
 /*************************** POWER ************************************/
 
-pub struct Power<'a> {
+pub struct Power {
     pub pos: (i32, i32),
     hitbox: Rect,
-    texture: &'a Texture<'a>,
+    prev_pos: (i32, i32),
     power_type: PowerType,
     collected: bool,
 }
 
-impl<'a> Power<'a> {
-    pub fn new(hitbox: Rect, texture: &'a Texture<'a>, power_type: PowerType) -> Power<'a> {
+impl Power {
+    pub fn new(hitbox: Rect, power_type: PowerType) -> Power {
         Power {
             pos: (hitbox.x(), hitbox.y()),
             hitbox,
-            texture,
+            prev_pos: (hitbox.x(), hitbox.y()),
             collected: false,
             power_type,
         }
@@ -970,18 +1538,14 @@ impl<'a> Power<'a> {
     }
 }
 
-impl<'a> Entity<'a> for Power<'a> {
-    fn texture(&self) -> &Texture<'a> {
-        self.texture
-    }
-
+impl Entity for Power {
     fn hitbox(&self) -> Rect {
         self.hitbox
     }
 
     fn align_hitbox_to_pos(&mut self) {
-        self.hitbox.set_x(self.pos.0 as i32);
-        self.hitbox.set_y(self.pos.1 as i32);
+        self.hitbox.set_x(self.pos.0);
+        self.hitbox.set_y(self.pos.1);
     }
 
     // Adjusts terrain postion in runner.rs based on camera_adj_x & camera_adj_y
@@ -991,9 +1555,17 @@ impl<'a> Entity<'a> for Power<'a> {
 
         self.align_hitbox_to_pos();
     }
+
+    fn prev_pos(&self) -> (i32, i32) {
+        self.prev_pos
+    }
+
+    fn save_prev_pos(&mut self) {
+        self.prev_pos = (self.hitbox.x(), self.hitbox.y());
+    }
 }
 
-impl<'a> Collectible<'a> for Power<'a> {
+impl Collectible for Power {
     fn update_pos(&mut self, x: i32, y: i32) {
         self.pos.0 = x;
         self.pos.1 = y;
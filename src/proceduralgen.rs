@@ -1,115 +1,359 @@
-use inf_runner::PowerType;
-use inf_runner::StaticObject;
-use inf_runner::TerrainType;
+use crate::bezier::ArcLengthTable;
+use crate::bezier::CubicBezier;
 
-use crate::rect;
+use crate::biome::Biome;
 
-use rand::Rng;
+use crate::difficulty::DifficultyLevel;
+
+use crate::noise::Noise;
+use crate::noise::NoiseKind;
+use crate::noise::Octaves;
 
-use sdl2::rect::Rect;
-use sdl2::render::Texture;
+use crate::PowerType;
+use crate::StaticObject;
+use crate::TerrainType;
+use crate::CAM_H;
 
-const CAM_W: u32 = 1280;
+use crate::reachability;
+
+use crate::setpiece::SetPiece;
+use crate::simulation::TILE_SIZE;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+// Range of how wide a terrain segment is, in pixels
+pub(crate) const SEGMENT_MIN_W: u32 = 900;
+pub(crate) const SEGMENT_MAX_W: u32 = 1600;
+
+// Pits are always wider than the player, so they can't be rolled over
+const PIT_MIN_W: usize = 120;
+const PIT_MAX_W: usize = 400;
+// Ground kept at both ends of a segment with a pit or cliff in it
+const FEATURE_MARGIN: usize = 200;
+// Tries at placing a pit before giving up on it
+const PIT_ATTEMPTS: usize = 5;
+
+// Range of how far a cliff drops, in pixels
+const CLIFF_MIN_DROP: i32 = 120;
+pub const CLIFF_MAX_DROP: i32 = 260;
+// How much shallower a cliff is tried again at when it drops too far
+const CLIFF_SHRINK: i32 = 20;
+
+// Background hill amplitudes were tuned on noise that only strayed about
+// 40% as far from 0 as this does
+const HILL_AMP_SCALE: f64 = 0.4;
+
+// Length of the curved run up to a ramp's lip, and range of its height
+const RAMP_RUN: usize = 300;
+const RAMP_MIN_RISE: i32 = 80;
+const RAMP_MAX_RISE: i32 = 160;
 
 // Where all the math is done?
 pub struct ProceduralGen;
 
-// Representation of a single bezier curve
-pub struct TerrainSegment<'a> {
-    pos: Rect,              // Bounding box
-    curve: Vec<(i32, i32)>, // Dynamic array of points defining the bezier curve
+// Stretch of ground shaped by a single cubic bezier curve. The ground is
+// kept in world space, sampled once into a table with one entry per pixel
+// column, and only `view` changes as the world scrolls past
+pub struct TerrainSegment {
+    curve: CubicBezier,    // In world space
+    start_x: i32,          // World x of the first column
+    heights: Vec<f64>,     // World y of the ground in each column
+    slopes: Vec<f64>,      // Angle of the ground in each column, see CubicBezier::slope
+    lengths: Vec<f64>,     // Distance along the ground up to each column
+    view: (i32, i32),      // Added to world coordinates to get screen coordinates
+    prev_view: (i32, i32), // View as of the start of the current tick
     terrain_type: TerrainType,
-    control_points: [(i32, i32); 4],
-    texture: &'a Texture<'a>,
+    biome: Biome,              // Biome the segment belongs to, or is leading into
+    pits: Vec<(usize, usize)>, // Column ranges with no ground, end exclusive
+
+    // Set piece the segment was made from, if any, and its objects that
+    // haven't been spawned yet as (object, column, world y of its bottom)
+    set_piece: Option<String>,
+    placed: Vec<(StaticObject, usize, f64)>,
 }
 
 // Terrain Segment Definitions
 #[allow(dead_code)]
-impl<'a> TerrainSegment<'a> {
+impl TerrainSegment {
+    // Params: curve in world space with its control points left to right,
+    //         view to draw it with, type of ground, biome it belongs to
     pub fn new(
-        pos: Rect,
-        curve: Vec<(i32, i32)>,
+        curve: CubicBezier,
+        view: (i32, i32),
         terrain_type: TerrainType,
-        control_points: [(i32, i32); 4],
-        texture: &'a Texture<'a>,
-    ) -> TerrainSegment<'a> {
-        // Set defaults, should probably be different than this
+        biome: Biome,
+    ) -> TerrainSegment {
+        let table = ArcLengthTable::new(&curve);
+        let start_x = curve.p0.0.round() as i32;
+        let width = (curve.p3.0.round() as i32 - start_x).max(1) as usize;
+
+        let mut heights = Vec::with_capacity(width);
+        let mut slopes = Vec::with_capacity(width);
+        let mut lengths = Vec::with_capacity(width);
+        for i in 0..width {
+            let t = table.t_at_x((start_x + i as i32) as f64);
+            heights.push(curve.point(t).1);
+            slopes.push(curve.slope(t));
+            lengths.push(table.length_at(t));
+        }
+
         TerrainSegment {
-            pos: pos,
-            curve: curve,
-            terrain_type: terrain_type,
-            control_points: control_points,
-            texture: texture,
+            curve,
+            start_x,
+            heights,
+            slopes,
+            lengths,
+            view,
+            prev_view: view,
+            terrain_type,
+            biome,
+            pits: Vec::new(),
+            set_piece: None,
+            placed: Vec::new(),
         }
     }
 
-    // Mutators
-    // Adjusts terrain postion in runner.rs based on camera_adj_x & camera_adj_y
-    pub fn camera_adj(&mut self, x_adj: i32, y_adj: i32) {
-        self.pos.set_x(self.pos.x() + x_adj);
-        self.pos.set_y(self.pos.y() + y_adj);
-        for tuple in self.curve.iter_mut() {
-            tuple.0 += x_adj;
-            tuple.1 += y_adj;
+    // Removes the ground between two column indices
+    // Params: first index of the pit, index just past its end
+    pub fn add_pit(&mut self, start: usize, end: usize) {
+        self.pits.push((start, end.min(self.heights.len())));
+    }
+
+    // Puts back the ground of a pit added with add_pit
+    pub fn remove_pit(&mut self, start: usize) {
+        self.pits.retain(|(pit_start, _)| *pit_start != start);
+    }
+
+    // Raises the ground before a column index into a curved kicker, leaving
+    // a sheer drop back down at the index itself
+    // Params: index of the lip, how high the lip is above the ground
+    pub fn add_ramp(&mut self, lip: usize, rise: i32) {
+        let start = lip.saturating_sub(RAMP_RUN);
+        let end = lip.min(self.heights.len());
+        for i in start..end {
+            let t = (i - start) as f64 / RAMP_RUN as f64;
+            self.heights[i] -= rise as f64 * t * t;
         }
-        for tuple in self.control_points.iter_mut() {
-            tuple.0 += x_adj;
-            tuple.1 += y_adj;
+        self.remeasure(start, end + 1);
+    }
+
+    // Moves everything from a column index onwards up or down. The end of
+    // the curve moves with it so the next segment still joins smoothly
+    // Params: first index to move, how far down to move it (negative for up)
+    pub fn shift_from(&mut self, at: usize, dy: i32) {
+        for height in self.heights.iter_mut().skip(at) {
+            *height += dy as f64;
         }
+        self.curve.p2.1 += dy as f64;
+        self.curve.p3.1 += dy as f64;
+        self.remeasure(at, at + 1);
     }
 
-    // Shifts terrain left so player can "move forward"
-    pub fn travel_update(&mut self, travel_adj: i32) {
-        self.pos.set_x(self.pos.x() - travel_adj);
-        for tuple in self.curve.iter_mut() {
-            tuple.0 -= travel_adj;
+    // Moves the ground down in steps, each step moving everything from its
+    // column onwards, in a single pass however many there are. The same as
+    // shift_from for each step in turn, so only the columns steps are at
+    // are measured again, and the end of the curve moves by all of them
+    // Params: (first index to move, how far down) of each step, in order
+    pub fn shift_steps(&mut self, steps: &[(usize, i32)]) {
+        let first = match steps.first() {
+            Some((at, _)) => *at,
+            None => return,
+        };
+        let mut offset = 0;
+        let mut grown = 0.0; // How much longer the ground so far has got
+        let mut next = steps.iter().peekable();
+        for i in first..self.heights.len() {
+            let mut stepped = false;
+            while let Some((_, dy)) = next.next_if(|(at, _)| *at <= i) {
+                offset += dy;
+                stepped = true;
+            }
+            self.heights[i] += offset as f64;
+
+            if stepped && i > 0 {
+                let old = self.lengths[i] - (self.lengths[i - 1] - grown);
+                let dy = self.heights[i] - self.heights[i - 1];
+                let new = (1.0 + dy * dy).sqrt();
+                self.slopes[i] = dy.atan2(1.0);
+                grown += new - old;
+            }
+            self.lengths[i] += grown;
+        }
+        self.curve.p2.1 += offset as f64;
+        self.curve.p3.1 += offset as f64;
+    }
+
+    // Drops everything from a column index onwards straight down, leaving a
+    // sheer wall
+    // Params: index the cliff starts at, how far down it drops
+    pub fn add_cliff(&mut self, at: usize, drop: i32) {
+        self.shift_from(at, drop);
+    }
+
+    // Measures slopes and lengths again for columns whose heights were
+    // changed by hand, so no longer follow the curve. Lengths after them
+    // move by however much the changed columns grew or shrank
+    // Params: first changed index, index just past the last
+    fn remeasure(&mut self, from: usize, to: usize) {
+        let from = from.max(1);
+        let to = to.min(self.heights.len());
+        if from >= to {
+            return;
+        }
+
+        let old_end = self.lengths[to - 1];
+        for i in from..to {
+            let dy = self.heights[i] - self.heights[i - 1];
+            self.slopes[i] = dy.atan2(1.0);
+            self.lengths[i] = self.lengths[i - 1] + (1.0 + dy * dy).sqrt();
         }
-        for tuple in self.control_points.iter_mut() {
-            tuple.0 -= travel_adj;
+        let grown = self.lengths[to - 1] - old_end;
+        for length in self.lengths.iter_mut().skip(to) {
+            *length += grown;
         }
     }
 
+    // Marks the segment as made from a set piece
+    pub fn set_set_piece(&mut self, name: &str) {
+        self.set_piece = Some(name.to_string());
+    }
+
+    // Puts an object on the segment to be spawned once it's on screen. It
+    // sits on the ground if that's higher than where it was asked for
+    // Params: object, column its middle is over, how far above the start of
+    //         the segment its bottom is
+    pub fn place_object(&mut self, object: StaticObject, index: usize, rise: i32) {
+        if index >= self.heights.len() {
+            return;
+        }
+        let y = (self.heights[0] - rise as f64).min(self.heights[index]);
+        self.placed.push((object, index, y));
+    }
+
+    // Takes out the placed objects that have come on screen
+    // Params: screen x objects at or left of are due
+    // Returns: each due object with the screen point of its bottom middle
+    pub fn take_placed_objects(&mut self, screen_x: i32) -> Vec<(StaticObject, (i32, i32))> {
+        let (x, view_y) = (self.x(), self.view.1);
+        let mut due = Vec::new();
+        self.placed.retain(|(object, index, y)| {
+            let at = x + *index as i32;
+            if at > screen_x {
+                return true;
+            }
+            due.push((*object, (at, y.round() as i32 + view_y)));
+            false
+        });
+        due
+    }
+
+    // Mutators
+    // Moves the segment on screen as the camera follows the player
+    pub fn camera_adj(&mut self, x_adj: i32, y_adj: i32) {
+        self.view.0 += x_adj;
+        self.view.1 += y_adj;
+    }
+
+    // Shifts terrain left so player can "move forward"
+    pub fn travel_update(&mut self, travel_adj: i32) {
+        self.view.0 -= travel_adj;
+    }
+
+    // Remembers where the segment was at the start of a tick
+    pub fn save_prev_pos(&mut self) {
+        self.prev_view = self.view;
+    }
+
     // Accessors
+    // Screen x of the first column
     pub fn x(&self) -> i32 {
-        self.pos.x()
+        self.start_x + self.view.0
     }
 
+    // Offset to add to every column to draw the segment blended between the
+    // last two ticks
+    // Params: how far the frame is between the last tick and the next (0.0 - 1.0)
+    pub fn lerp_offset(&self, alpha: f64) -> (i32, i32) {
+        (
+            ((self.prev_view.0 - self.view.0) as f64 * (1.0 - alpha)).round() as i32,
+            ((self.prev_view.1 - self.view.1) as f64 * (1.0 - alpha)).round() as i32,
+        )
+    }
+
+    // Screen y of the ground in the first column
     pub fn y(&self) -> i32 {
-        self.pos.y()
+        self.ground_y(0)
     }
 
+    // Width in columns, one per pixel
     pub fn w(&self) -> i32 {
-        self.pos.width() as i32
+        self.heights.len() as i32
     }
 
-    pub fn h(&self) -> i32 {
-        self.pos.height() as i32
+    pub fn get_type(&self) -> &TerrainType {
+        &self.terrain_type
     }
 
-    pub fn pos(&self) -> Rect {
-        self.pos
+    pub fn biome(&self) -> Biome {
+        self.biome
     }
 
-    pub fn get_type(&self) -> &TerrainType {
-        &self.terrain_type
+    pub fn is_set_piece(&self) -> bool {
+        self.set_piece.is_some()
     }
 
-    pub fn curve(&self) -> &Vec<(i32, i32)> {
-        &(self.curve)
+    // Name of the set piece the segment was made from
+    pub fn set_piece(&self) -> Option<&str> {
+        self.set_piece.as_deref()
     }
 
-    pub fn get_ctrl_points(&self) -> [(i32, i32); 4] {
-        self.control_points
+    // Curve the segment was sampled from, in world space
+    pub fn curve(&self) -> &CubicBezier {
+        &self.curve
     }
 
-    pub fn texture(&self) -> &Texture<'a> {
-        self.texture
+    pub fn view(&self) -> (i32, i32) {
+        self.view
+    }
+
+    // Screen y of the ground in a column, pits or not
+    pub fn ground_y(&self, index: usize) -> i32 {
+        self.heights[index].round() as i32 + self.view.1
+    }
+
+    // Angle of the ground in a column, positive going downhill
+    pub fn slope_at(&self, index: usize) -> f64 {
+        self.slopes[index]
+    }
+
+    // Distance along the ground from the start of the segment to a column
+    pub fn arc_length(&self, index: usize) -> f64 {
+        self.lengths[index]
+    }
+
+    // False if the column is over a pit
+    pub fn has_ground(&self, index: usize) -> bool {
+        !self
+            .pits
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&index))
+    }
+
+    // Point of the ground in a column, in screen space
+    // Returns: None if the column is over a pit or past the end of the segment
+    pub fn ground_at(&self, index: usize) -> Option<(i32, i32)> {
+        if index < self.heights.len() && self.has_ground(index) {
+            Some((self.x() + index as i32, self.ground_y(index)))
+        } else {
+            None
+        }
     }
 }
 
-impl<'a> PartialEq for TerrainSegment<'a> {
+impl PartialEq for TerrainSegment {
     fn eq(&self, other: &Self) -> bool {
-        self.pos == other.pos
+        self.start_x == other.start_x && self.view == other.view
     }
 }
 
@@ -125,75 +369,75 @@ impl ProceduralGen {
 
     /*  Initilization of terrain segments
      *
-     *  - Takes in `random` which is the array of random tuples of (i32, i32)
-     *    Needs to be the same values on each run for porper noise output
-     *    Represents the gradient value for points. Passed into gen_point_mod
-     *  - Takes in `prev_point` which is the x (assumes 0) and y of the last part
-     *    of generated terrain
-     *  - Takes in `cam_w` which is the width of the camera (1280)
-     *  - Takes in `cam_h` which is the height of the camera (720)
-     *  - Takes in `_is_pit` boolean which will generate a pit within this land
-     *    segment *NOT IMPLEMENTED YET*
+     *  - Takes in `noise` which is the run's seeded noise. Passed into
+     *    gen_point_mod
+     *  - Takes in `prev_seg` which is the last segment generated, the new
+     *    one carries on from the end of its curve
+     *  - Takes in `_is_pit` boolean which will cut a pit into this land
+     *    segment, if one that can be jumped fits
      *  - Takes in `_is_flat` boolean which will make the generated control point
      *    modifiers around the same y and thus, curves should be relatively flat
      *    for the next land segment
      *  - Takes in `_is_cliff` boolean which will make a cliff within the next
-     *    land segment *NOT IMPLEMENTED YET*
+     *    land segment
+     *  - Takes in `biome` which is the biome the segment belongs to, used to
+     *    choose its TerrainType
+     *  - Takes in `transition_from` which is the biome being left if this is
+     *    the transition segment into `biome`. Transitions are flat, have no
+     *    pits or cliffs, and take their TerrainType from either biome
+     *  - Takes in `level` which is how hard the run currently is, scaling the
+     *    size and frequency of hills and how often water comes up
+     *  - Takes in `view_y` which is where the camera will be vertically once
+     *    the player reaches the segment, see gen_bezier_curve
+     *  - Takes in `rng` which is the run's seeded generator, so the same seed
+     *    always produces the same terrain
      *
-     *  - Returns array of tuples associated with the output curve.
+     *  - Returns the new segment, of a random width
      */
-    pub fn gen_terrain<'a>(
-        random: &[[(i32, i32); 256]; 256],
+    #[allow(clippy::too_many_arguments)]
+    pub fn gen_terrain(
+        noise: &Noise,
         prev_seg: &TerrainSegment,
-        cam_w: i32,
-        cam_h: i32,
         _is_flat: bool,
-        tex_all: [&'a Texture<'a>; 4],
-    ) -> TerrainSegment<'a> {
-        let mut rng = rand::thread_rng();
-
+        _is_pit: bool,
+        _is_cliff: bool,
+        biome: Biome,
+        transition_from: Option<Biome>,
+        level: &DifficultyLevel,
+        view_y: i32,
+        rng: &mut StdRng,
+    ) -> TerrainSegment {
         //println!("{:?} {:?} {:?}", _is_pit, _is_flat, _is_cliff);
 
         // Generate TerrainSegment's type
-        let terrain_type = choose_terrain_type(10);
+        let terrain_type = match transition_from {
+            Some(prev_biome) if rng.gen_bool(0.5) => choose_terrain_type(prev_biome, level, rng),
+            _ => choose_terrain_type(biome, level, rng),
+        };
 
         let _is_flat = match terrain_type {
-            TerrainType::Water => true,
-            _ => _is_flat,
+            TerrainType::Water | TerrainType::Ramp => true,
+            _ => _is_flat || transition_from.is_some(),
         };
+        let _is_pit = _is_pit && transition_from.is_none();
+        let _is_cliff = _is_cliff && transition_from.is_none();
 
         let flat_mod: f64 = 0.25;
+        let length = rng.gen_range(SEGMENT_MIN_W..=SEGMENT_MAX_W);
 
-        let freq = rng.gen_range(32.0..256.0);
+        // Hills get taller and closer together as the run gets harder
+        let freq = rng.gen_range(32.0..256.0) / level.frequency;
         let amp: f64 = if _is_flat {
             // Make terrain flatter
             rng.gen::<f64>() * flat_mod
         } else {
-            rng.gen::<f64>()
+            rng.gen::<f64>() * level.amplitude
         };
 
         // Generates perlin noise for random point instead of whole map
         let map_size = 128;
-        let point_mod_1a: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
-        let point_mod_1b: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
         let point_mod_2a: f64 = gen_point_mod(
-            &random,
+            noise,
             (
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
@@ -202,7 +446,7 @@ impl ProceduralGen {
             amp,
         );
         let point_mod_2b: f64 = gen_point_mod(
-            &random,
+            noise,
             (
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
@@ -211,7 +455,7 @@ impl ProceduralGen {
             amp,
         );
         let point_mod_3a: f64 = gen_point_mod(
-            &random,
+            noise,
             (
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
@@ -220,7 +464,7 @@ impl ProceduralGen {
             amp,
         );
         let point_mod_3b: f64 = gen_point_mod(
-            &random,
+            noise,
             (
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
                 (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
@@ -229,491 +473,384 @@ impl ProceduralGen {
             amp,
         );
 
-        let prev_points = prev_seg.get_ctrl_points();
-
-        // Set p0 or previous curve's end control point
-        let q_n = prev_points[prev_points.len() - 1];
-
-        // Set q_n-1 or second to last control point of previous curve
-        let q_n1 = prev_points[prev_points.len() - 2];
-
-        //instantiation
-        let curve_points = gen_bezier_curve(
-            q_n,
-            q_n1,
-            cam_w,
-            cam_h,
-            (point_mod_1a, point_mod_1b),
+        let curve = gen_bezier_curve(
+            prev_seg.curve(),
+            view_y as f64,
+            length as f64,
             (point_mod_2a, point_mod_2b),
             (point_mod_3a, point_mod_3b),
-            100,
             _is_flat,
         );
+        let mut terrain = TerrainSegment::new(curve, prev_seg.view(), terrain_type, biome);
 
-        let rect = rect!(
-            prev_seg.curve().get(prev_seg.curve().len() - 1).unwrap().0 + 1,
-            prev_seg.curve().get(prev_seg.curve().len() - 1).unwrap().1,
-            curve_points.0.len(),
-            10
-        );
-        let tex = match terrain_type {
-            TerrainType::Asphalt => tex_all[0],
-            TerrainType::Sand => tex_all[1],
-            TerrainType::Water => tex_all[2],
-            TerrainType::Grass => tex_all[3],
-        };
-
-        let terrain = TerrainSegment::new(rect, curve_points.0, terrain_type, curve_points.1, tex);
+        // No pits or cliffs in water
+        if let TerrainType::Water = terrain.get_type() {
+            return terrain;
+        }
 
-        return terrain;
-    }
-}
+        // Hills too steep to land on are flattened before anything is cut
+        // into them
+        reachability::limit_slopes(&mut terrain);
 
-/*  Function for extending a cubic bezier curve while keeping the chained
- *  curve smooth. Works similarly to gen_cubic_bezier_curve_points()
- *      http://www.inf.ed.ac.uk/teaching/courses/cg/d3/bezierJoin.html
- */
-pub fn extend_cubic_bezier_curve(
-    prev_pn: (f64, f64),
-    prev_pn_minus_1: (f64, f64),
-    //no p0 or p1, above data structures work instead
-    p2: (f64, f64),
-    p3: (f64, f64),
-) -> (Vec<(i32, i32)>, (i32, i32)) {
-    let mut points: Vec<(i32, i32)> = Vec::new();
-
-    //Calculate p1
-    let mut p1: (f64, f64) = (0.0, 0.0);
-
-    p1.0 = prev_pn.0 + (prev_pn.0 - prev_pn_minus_1.0);
-    p1.1 = prev_pn.1 + (prev_pn.1 - prev_pn_minus_1.1);
-
-    for t in 0..CAM_W as usize {
-        let point = t as f64;
-        //points[t] = quadratic_bezier_curve_point(p0, p1, p2, point / 32.0);
-        points.push(cubic_bezier_curve_point(
-            prev_pn,
-            p1,
-            p2,
-            p3,
-            point / CAM_W as f64,
-        ));
-    }
-
-    return (points, (p1.0 as i32, p1.1 as i32));
-}
+        // Ramps are a run up to a single kicker and nothing else
+        if let TerrainType::Ramp = terrain.get_type() {
+            let len = terrain.w() as usize;
+            let lip = rng.gen_range(len / 2..len - FEATURE_MARGIN);
+            terrain.add_ramp(lip, rng.gen_range(RAMP_MIN_RISE..=RAMP_MAX_RISE));
+            return terrain;
+        }
 
-/*  Function for extending a cubic bezier curve while keeping the chained
- *  curve smooth. Works similarly to gen_cubic_bezier_curve_points()
- *      http://www.inf.ed.ac.uk/teaching/courses/cg/d3/bezierJoin.html
- */
+        // A cliff goes in the first half so a pit can still fit after it
+        let len = terrain.w() as usize;
+        let mut pit_from = FEATURE_MARGIN;
+        if _is_cliff {
+            let at = rng.gen_range(FEATURE_MARGIN..len / 2);
+            let drop = rng.gen_range(CLIFF_MIN_DROP..=CLIFF_MAX_DROP);
+            if add_survivable_cliff(&mut terrain, at, drop) {
+                pit_from = at + FEATURE_MARGIN;
+            }
+        }
+        if _is_pit {
+            add_jumpable_pit(&mut terrain, pit_from, rng);
+        }
 
-/* ~~~~~~     Bezier primary functions      ~~~~~~ */
+        terrain
+    }
+}
 
-/*  Handler for getting either quadratic or cubic bezier curve representation
- *
- *  - Takes in `p0` which is the last place the previously generated land
- *    ended
- *  - Takes in `length` which is a control parameter
- *  - Takes in `height` which is a control parameter
- *  - Takes in `point_mod_x` which are the Perlin Noise Modifiers to help
- *    generate control points
- *  - Takes in `buffer` which is a control parameter saying how close control
- *    points can be in the x direction
+/*  Cuts a pit into a segment somewhere after `from`, only if a ghost of
+ *  the player can jump it.
  *
- *  - Returns Bezier Curve representation
+ *  - Takes in `terrain` which is the segment to cut the pit into
+ *  - Takes in `from` which is the first curve index the pit may start at
+ *  - Takes in `rng` which is the run's seeded generator
  */
-#[allow(unused_assignments)]
-fn gen_bezier_curve(
-    q_n: (i32, i32),
-    q_n1: (i32, i32),
-    length: i32, // Needs to be static which is stupid so 1280
-    height: i32,
-    point_mod_1: (f64, f64),
-    point_mod_2: (f64, f64),
-    point_mod_3: (f64, f64),
-    buffer: i32,
-    _is_flat: bool,
-) -> (Vec<(i32, i32)>, [(i32, i32); 4]) {
-    //TODO - CONTROL POINT LOGIC NEEDS TO BE REFINED
-    //Bezier curve
-
-    //Cubic
-    let p1_x = q_n.0 + (q_n.0 - q_n1.0);
-    let p2: (f64, f64) = if _is_flat {
-        (
-            (point_mod_2.0 * (length - buffer) as f64 + (p1_x + buffer) as f64)
-                .clamp((p1_x + buffer) as f64, (length + q_n.0 - buffer) as f64),
-            q_n.1 as f64 + (q_n.1 as f64 - q_n1.1 as f64),
-        )
-    } else {
-        (
-            (point_mod_2.0 * (length - buffer) as f64 + (p1_x + buffer) as f64)
-                .clamp((p1_x + buffer) as f64, (length + q_n.0 - buffer) as f64),
-            ((1.0 - point_mod_2.1) * (q_n.1 as f64 * 2.1)),
-        )
-    };
-
-    let p3: (f64, f64) = if _is_flat {
-        (
-            length as f64 + q_n.0 as f64,
-            q_n.1 as f64 + (q_n.1 as f64 - q_n1.1 as f64),
-        )
-    } else {
-        (
-            length as f64 + q_n.0 as f64,
-            ((1.0 - point_mod_3.1) * (q_n.1 as f64 * 2.25)),
-        )
-    };
-
-    let mut group_of_points: Vec<(i32, i32)> = Vec::new();
-    let mut p1 = (-1, -1);
-
-    //if p1 value hasn't been given, generating the initial curve
-    if q_n1 == (-1, -1) {
-        let temp_point: (f64, f64) = (
-            (point_mod_1.0 * (length / 2 + buffer) as f64
-                + q_n.0 as f64
-                + buffer as f64
-                + (length / 2) as f64),
-            (point_mod_1.1 * q_n.1 as f64 * 2.0 - q_n.1 as f64)
-                .clamp(q_n.1 as f64 + buffer as f64, height as f64),
-        );
-        p1 = (temp_point.0 as i32, temp_point.1 as i32);
+fn add_jumpable_pit(terrain: &mut TerrainSegment, from: usize, rng: &mut StdRng) {
+    let len = terrain.w() as usize;
+    if from + PIT_MIN_W + FEATURE_MARGIN > len {
+        return;
+    }
 
-        group_of_points =
-            gen_cubic_bezier_curve_points((q_n.0 as f64, q_n.1 as f64), temp_point, p2, p3);
-    } else {
-        let tup = extend_cubic_bezier_curve(
-            (q_n.0 as f64, q_n.1 as f64),
-            (q_n1.0 as f64, q_n1.1 as f64),
-            p2,
-            p3,
-        ); //might need to swap p0 and p1
-        group_of_points = tup.0;
-        p1 = tup.1;
-    }
-
-    return (
-        group_of_points,
-        ([
-            q_n,
-            p1,
-            (p2.0 as i32, p2.1 as i32),
-            (p3.0 as i32, p3.1 as i32),
-        ]),
-    );
-}
+    for _ in 0..PIT_ATTEMPTS {
+        let start = rng.gen_range(from..=len - FEATURE_MARGIN - PIT_MIN_W);
+        let widest = (len - FEATURE_MARGIN - start).min(PIT_MAX_W);
+        let width = rng.gen_range(PIT_MIN_W..=widest);
 
-/*
- *
- *
- *
- *
- */
-// Returns an array of the points' (x,y) values
-pub fn gen_cubic_bezier_curve_points(
-    p0: (f64, f64),
-    p1: (f64, f64),
-    p2: (f64, f64),
-    p3: (f64, f64),
-) -> Vec<(i32, i32)> {
-    let mut points: Vec<(i32, i32)> = Vec::new();
-
-    for t in 0..CAM_W as usize {
-        let point = t as f64;
-        points.push(cubic_bezier_curve_point(
-            p0,
-            p1,
-            p2,
-            p3,
-            point / CAM_W as f64,
-        ));
-    }
-    return points;
+        if add_pit_if_clearable(terrain, start, width) {
+            return;
+        }
+    }
 }
 
-/*
- *
- *
+/*  Cuts a pit into a segment, only if a ghost of the player running at
+ *  reachability::RUN_SPEED can jump it from the ground just before it.
  *
+ *  - Takes in `terrain` which is the segment to cut the pit into
+ *  - Takes in `start` which is the first column of the pit
+ *  - Takes in `width` which is how many columns wide the pit is
  *
+ *  - Returns true if the pit was cut
  */
-// Returns an array of the points' (x,y) values
-#[allow(dead_code)]
-pub fn gen_quadratic_bezier_curve_points(
-    p0: (f64, f64), // Start point
-    p1: (f64, f64), // Mid point
-    p2: (f64, f64), // End point
-) -> Vec<(i32, i32)> {
-    let mut points: Vec<(i32, i32)> = vec![(-1, -1)];
-    for t in 0..CAM_W as usize {
-        let point = t as f64;
-        points.insert(
-            t,
-            quadratic_bezier_curve_point(p0, p1, p2, point / CAM_W as f64),
-        );
+fn add_pit_if_clearable(terrain: &mut TerrainSegment, start: usize, width: usize) -> bool {
+    if start < FEATURE_MARGIN || start + width + FEATURE_MARGIN > terrain.w() as usize {
+        return false;
     }
-    return points;
-}
-
-/******      Bezier helper functions      ***** */
-
-fn cubic_bezier_curve_point(
-    p0: (f64, f64), // Start point
-    p1: (f64, f64), // Mid_0 point
-    p2: (f64, f64), // Mid_1 point
-    p3: (f64, f64), // End point
-    t: f64,
-) -> (i32, i32) {
-    let x_value = p0.0 + t * (1280.0) + 1.0;
-    let y_value = (1.0 - t) * (1.0 - t) * (1.0 - t) * p0.1
-        + 3.0 * (1.0 - t) * (1.0 - t) * t * p1.1
-        + 3.0 * (1.0 - t) * t * t * p2.1
-        + t * t * t * p3.1;
-
-    return (x_value as i32, y_value as i32);
-}
-
-#[allow(dead_code)]
-fn quadratic_bezier_curve_point(
-    // Point args obtained from perlin
-    p0: (f64, f64), // Start point
-    p1: (f64, f64), // Mid point
-    p2: (f64, f64), // End point
-    t: f64,         // t = Point range 0-1 of the curve
-) -> (i32, i32) {
-    let x_value = (1.0 - t) * ((1.0 - t) * p0.0 + t * p1.0) + t * ((1.0 - t) * p1.0 + t * p2.0);
-    let y_value = (1.0 - t) * ((1.0 - t) * p0.1 + t * p1.1) + t * ((1.0 - t) * p1.1 + t * p2.1);
-    return (x_value as i32, y_value as i32);
+    terrain.add_pit(start, start + width);
+
+    // Run up from the start of the ground before the pit, land after it
+    let pit_x = terrain.x() + start as i32;
+    let clearable = reachability::can_clear(
+        std::slice::from_ref(terrain),
+        &[],
+        pit_x - FEATURE_MARGIN as i32,
+        pit_x + width as i32 + TILE_SIZE as i32 / 2,
+        reachability::RUN_SPEED,
+    );
+    if !clearable {
+        terrain.remove_pit(start);
+    }
+    clearable
 }
 
-/******      Perlin primary functions      ***** */
-
-/*  Generates a single value from the 1d perlin noise
+/*  Drops the ground into a cliff, only as far as a ghost of the player
+ *  running at reachability::RUN_SPEED can go over it and land. A drop
+ *  that's too far is tried again shallower, down to CLIFF_MIN_DROP.
  *
- *  - Takes in point `i` which is the x value we want to get the y of
- *  - Takes in `freq` which is a control value on the cord
- *  - Takes in `amp` which is a control value on the noise_1d outputs
- *  - Takes in `mul` which is a control value on the entire augmented
- *    noise_1d outputs
+ *  - Takes in `terrain` which is the segment to put the cliff in
+ *  - Takes in `at` which is the column the cliff starts at
+ *  - Takes in `drop` which is the furthest the cliff may drop
  *
- *  - Returns the y position associated witht the output of the augmented
- *    outputs
+ *  - Returns true if the cliff was added
  */
-pub fn gen_perlin_hill_point(i: usize, freq: f32, amp: f32, modifier: f32, mul: f32) -> i16 {
-    for j in 0..720 {
-        let cord = (i, j);
-
-        let n = modifier
-            * (noise_1d(cord.0 as f32 * (1.0 / freq)) * amp
-                + noise_1d(cord.0 as f32 * (1.0 / freq / 2.0)) * amp / 2.0
-                + noise_1d(cord.0 as f32 * (1.0 / freq / 4.0)) * amp / 4.0
-                + noise_1d(cord.0 as f32 * (1.0 / freq / 8.0)) * amp / 8.0);
-
-        let y = 2.0 * (cord.1 as f32 / mul) - 1.0;
-
-        if n > y {
-        } else {
-            return j as i16;
+fn add_survivable_cliff(terrain: &mut TerrainSegment, at: usize, drop: i32) -> bool {
+    // Run up to the edge from the start of the ground before it, land after it
+    let cliff_x = terrain.x() + at as i32;
+    let mut drop = drop;
+    while drop >= CLIFF_MIN_DROP {
+        terrain.add_cliff(at, drop);
+        if reachability::can_clear(
+            std::slice::from_ref(terrain),
+            &[],
+            cliff_x - FEATURE_MARGIN as i32,
+            cliff_x + FEATURE_MARGIN as i32,
+            reachability::RUN_SPEED,
+        ) {
+            return true;
         }
+        terrain.add_cliff(at, -drop);
+        drop -= CLIFF_SHRINK;
     }
-    return 720 as i16;
+    false
 }
 
-/*  Generates the advanced perlin noise value for a single point.
- *  Calls noise_2d 4 times to make output more "interesting"
+/*  Builds a segment from a set piece, starting where the last segment
+ *  ended and carrying on in the direction it was heading. Pits in the set
+ *  piece that can't be jumped are left out.
  *
- *  - Takes in `random` which is the array of random tuples of (i32, i32)
- *    Needs to be the same values on each run for porper noise output
- *    Represents the gradient value for points. Passed into noise_2d each
- *    time it is called
- *  - Takes in point values `cord` to get the noise values of
- *  - Takes in `freq` which is a control value on the cord
- *  - Takes in `amp` which is a control value on the noise_2d outputs
+ *  - Takes in `prev_seg` which is the last segment generated
+ *  - Takes in `piece` which is the set piece to build
+ *  - Takes in `biome` which is the biome the run is in
  *
- *  - Returns the advanced perlin noise value for given point augmented by
- *    control values
+ *  - Returns the new segment, with the set piece's objects placed on it
  */
-fn gen_point_mod(random: &[[(i32, i32); 256]; 256], cord: (i32, i32), freq: f64, amp: f64) -> f64 {
-    let n = noise_2d(&random, (cord.0 as f64 / (freq), cord.1 as f64 / (freq))) * (amp)
-        + noise_2d(
-            &random,
-            (cord.0 as f64 / (freq / 2.0), cord.1 as f64 / (freq / 2.0)),
-        ) * (amp / 2.0)
-        + noise_2d(
-            &random,
-            (cord.0 as f64 / (freq / 4.0), cord.1 as f64 / (freq / 4.0)),
-        ) * (amp / 4.0)
-        + noise_2d(
-            &random,
-            (cord.0 as f64 / (freq / 8.0), cord.1 as f64 / (freq / 8.0)),
-        ) * (amp / 8.0);
-    let modifier = n * 0.5 + 0.5;
-    return modifier;
+pub fn gen_set_piece(prev_seg: &TerrainSegment, piece: &SetPiece, biome: Biome) -> TerrainSegment {
+    let (p0, p1) = continue_curve(prev_seg.curve());
+    // Set pieces are written with y going up
+    let from_start = |(x, y): (f64, f64)| (p0.0 + x, p0.1 - y);
+    let curve = CubicBezier::new(p0, p1, from_start(piece.handle), from_start(piece.end));
+
+    let mut terrain = TerrainSegment::new(curve, prev_seg.view(), piece.terrain_type, biome);
+    terrain.set_set_piece(&piece.name);
+    for (start, width) in piece.pits.iter() {
+        add_pit_if_clearable(&mut terrain, *start, *width);
+    }
+    for (object, x, y) in piece.objects.iter() {
+        terrain.place_object(*object, *x, *y);
+    }
+    terrain
 }
 
-/******      Perlin helper functions      ***** */
-// Implementation adapted from https://gpfault.net/posts/perlin-noise.txt.html
+/* ~~~~~~     Bezier primary functions      ~~~~~~ */
 
-/*  Smoothing the input value so the result isn't as "sharp"
- *  Used for interpolation step of Perlin Noise Algorithm.
- *  Interchangeable between 1d and 2d implementation
+/*  Start of a curve that joins on to the end of another without a kink,
+ *  by mirroring its last handle
+ *      http://www.inf.ed.ac.uk/teaching/courses/cg/d3/bezierJoin.html
  *
- *  - Takes in value `t` to apply fade upon
+ *  - Takes in `prev` which is the curve to carry on from
  *
- *  - Returns smoothed value
+ *  - Returns the first two control points of the next curve
  */
-fn fade(t: f64) -> f64 {
-    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+pub fn continue_curve(prev: &CubicBezier) -> ((f64, f64), (f64, f64)) {
+    let p0 = prev.p3;
+    let p1 = (
+        p0.0 + (prev.p3.0 - prev.p2.0),
+        p0.1 + (prev.p3.1 - prev.p2.1),
+    );
+    (p0, p1)
 }
 
-/*  Determine gradient value for given point p
+/*  Picks the control points of the next stretch of terrain. The curve
+ *  carries on in the direction the last one ended in, see continue_curve
  *
- *  - Takes in `random` which is the array of random tuples of (i32, i32)
- *    Needs to be the same values on each run for porper noise output
- *    Represents the gradient value for points
- *  - Takes in point `p` to determine gradient of
+ *  - Takes in `prev` which is the previous segment's curve, in world space
+ *  - Takes in `view_y` which is added to world y to get screen y. How tall
+ *    hills are depends on how far down the screen they start
+ *  - Takes in `length` which is how wide the new curve is
+ *  - Takes in `point_mod_x` which are the Perlin Noise Modifiers to help
+ *    generate control points
+ *  - Takes in `_is_flat` boolean which keeps the curve heading the same way
+ *    the last one ended
  *
- *  - Returns unit vector of gradient vector
+ *  - Returns the new curve, in world space
  */
-fn grad_2d(random: &[[(i32, i32); 256]; 256], p: (f64, f64)) -> (f64, f64) {
-    let pre_v = random[p.0 as usize % 256][p.1 as usize % 256];
-
-    let v = (pre_v.0 as f64 / 256.0, pre_v.1 as f64 / 256.0);
-
-    let n = (v.0 * 2.0 - 1.0, v.1 * 2.0 - 1.0);
+fn gen_bezier_curve(
+    prev: &CubicBezier,
+    view_y: f64,
+    length: f64,
+    point_mod_2: (f64, f64),
+    point_mod_3: (f64, f64),
+    _is_flat: bool,
+) -> CubicBezier {
+    let (p0, p1) = continue_curve(prev);
 
-    let length = (n.0 * n.0 + n.1 * n.1).sqrt();
+    // The end handle is a sixth to a third of the curve long. That keeps the
+    // next curve's mirrored handle short of its far end, so x only ever
+    // increases along every curve
+    let handle = length / 6.0 * (1.0 + point_mod_2.0.abs().min(1.0));
+    let end_x = p0.0 + length;
 
-    let unit = (n.0 / length, n.1 / length);
+    let (p2_y, p3_y) = if _is_flat {
+        (p1.1, p1.1)
+    } else {
+        // Sized in screen space, like the hills always have been
+        let start_y = p0.1 + view_y;
+        (
+            (1.0 - point_mod_2.1) * (start_y * 2.1) - view_y,
+            (1.0 - point_mod_3.1) * (start_y * 2.25) - view_y,
+        )
+    };
 
-    return unit;
+    CubicBezier::new(p0, p1, (end_x - handle, p2_y), (end_x, p3_y))
 }
 
-/*  Putting everything together for making the 2d noise
- *
- *  - Takes in `random` which is the array of random tuples of (i32, i32)
- *    Needs to be the same values on each run for porper noise output
- *    Represents the gradient value for points. Passed into grad_2d
- *  - Takes in point values `p` to give noise output on
- *
- *  - Returns noise output for given values
- */
-pub fn noise_2d(random: &[[(i32, i32); 256]; 256], p: (f64, f64)) -> f64 {
-    let p0 = (p.0.floor(), p.1.floor());
-    let p1 = (p0.0 + 1.0, p0.1);
-    let p2 = (p0.0, p0.1 + 1.0);
-    let p3 = (p0.0 + 1.0, p0.1 + 1.0);
-
-    let g0 = grad_2d(&random, p0);
-    let g1 = grad_2d(&random, p1);
-    let g2 = grad_2d(&random, p2);
-    let g3 = grad_2d(&random, p3);
-
-    let t0 = p.0 - p0.0;
-    let fade_t0 = fade(t0);
-
-    let t1 = p.1 - p0.1;
-    let fade_t1 = fade(t1);
-
-    let p_minus_p0 = (p.0 - p0.0, p.1 - p0.1);
-    let p_minus_p1 = (p.0 - p1.0, p.1 - p1.1);
-    let p_minus_p2 = (p.0 - p2.0, p.1 - p2.1);
-    let p_minus_p3 = (p.0 - p3.0, p.1 - p3.1);
-
-    let g0_dot_p0 = g0.0 * p_minus_p0.0 + g0.1 * p_minus_p0.1;
-    let g1_dot_p1 = g1.0 * p_minus_p1.0 + g1.1 * p_minus_p1.1;
-    let g2_dot_p2 = g2.0 * p_minus_p2.0 + g2.1 * p_minus_p2.1;
-    let g3_dot_p3 = g3.0 * p_minus_p3.0 + g3.1 * p_minus_p3.1;
-
-    let p0p1 = (1.0 - fade_t0) * g0_dot_p0 + fade_t0 * g1_dot_p1;
-    let p2p3 = (1.0 - fade_t0) * g2_dot_p2 + fade_t0 * g3_dot_p3;
-
-    let result = (1.0 - fade_t1) * p0p1 + fade_t1 * p2p3;
-
-    return result;
-}
+/******      Perlin primary functions      ***** */
 
-/*  Determine gradient value for given value p
- *  *NOTE*: Some wierdness taking in the random values array but
- *    setting value to consistently output either -1 always or 1 always
- *    gives expected output
+/*  Generates the height of a single column of the background hills
  *
- *  - Takes in point `p` to determine gradient of
+ *  - Takes in `noise` which is the run's seeded noise
+ *  - Takes in point `i` which is the x value we want to get the y of
+ *  - Takes in `freq` which is how many columns one rise and fall of the
+ *    hills spans
+ *  - Takes in `amp` which is a control value on the noise outputs
+ *  - Takes in `modifier` which is a further control value on the amplitude
+ *  - Takes in `mul` which is the height of flat hills, in pixels
  *
- *  - Returns binary output (-1 or 1)
+ *  - Returns the height of the hills above the bottom of the screen
  */
-fn grad_1d(_p: f32) -> f32 {
-    let v: f32 = 0.0;
-
-    return if v > 0.5 { 1.0 } else { -1.0 };
+pub fn gen_perlin_hill_point(
+    noise: &Noise,
+    i: usize,
+    freq: f32,
+    amp: f32,
+    modifier: f32,
+    mul: f32,
+) -> i16 {
+    let octaves = Octaves::new(4, 1.0 / freq as f64);
+    let n = modifier as f64
+        * amp as f64
+        * HILL_AMP_SCALE
+        * noise.fractal_1d(NoiseKind::Perlin, i as f64, &octaves);
+
+    ((n + 1.0) * mul as f64 / 2.0).clamp(0.0, CAM_H as f64) as i16
 }
 
-/*  Putting everything together for making the 1d noise
+/*  Generates the fractal perlin noise value for a single point.
+ *  Four layers of noise make the output more "interesting"
  *
- *  - Takes in point value `p` to give noise output on
+ *  - Takes in `noise` which is the run's seeded noise
+ *  - Takes in point values `cord` to get the noise values of
+ *  - Takes in `freq` which is a control value on the cord
+ *  - Takes in `amp` which is a control value on the noise outputs
  *
- *  - Returns noise output for given value
+ *  - Returns the perlin noise value for given point augmented by control
+ *    values, around 0.5
  */
-fn noise_1d(p: f32) -> f32 {
-    let p0 = p.floor();
-    let p1 = p0 + 1.0;
-
-    let t = p - p0;
-    let ft = fade(t as f64) as f32;
-
-    let g0 = grad_1d(p0);
-    let g1 = grad_1d(p1);
-
-    return (1.0 - ft) * g0 * (p - p0) + ft * g1 * (p - p1);
+fn gen_point_mod(noise: &Noise, cord: (i32, i32), freq: f64, amp: f64) -> f64 {
+    let octaves = Octaves::new(4, 1.0 / freq);
+    let n = noise.fractal_2d(NoiseKind::Perlin, (cord.0 as f64, cord.1 as f64), &octaves) * amp;
+    n * 0.5 + 0.5
 }
 
 /* ~~~~~~ Random Distributions ~~~~~~ */
 
-/* Randomly choose a TerrainType. Heavily weighted to pick Grass.
- *  - Takes in `upper` which is the top of of the gen_range. Should be >= 3.
- *    Higher it is, more weighted to choose Grass
+/* Randomly choose a TerrainType, weighted by the biome and difficulty
+ *  - Takes in `biome` which is the biome the terrain is for
+ *  - Takes in `level` which is how hard the run currently is
+ *  - Takes in `rng` which is the run's seeded generator
  *
  *  - Returns a random TerrainType
  */
 // Renamed from get_random_terrain
-fn choose_terrain_type(upper: i32) -> TerrainType {
-    let mut rng = rand::thread_rng();
+fn choose_terrain_type(biome: Biome, level: &DifficultyLevel, rng: &mut StdRng) -> TerrainType {
+    let weights: Vec<(TerrainType, f64)> = biome
+        .terrain_weights()
+        .iter()
+        .map(|(terrain_type, weight)| {
+            let scale = match terrain_type {
+                TerrainType::Water => level.water_weight,
+                _ => 1.0,
+            };
+            (*terrain_type, *weight as f64 * scale)
+        })
+        .collect();
+    choose_weighted(&weights, rng)
+}
 
-    let upper = upper.clamp(3, i32::MAX);
+/*  Randomly choose a StaticObject, weighted by the biome and difficulty
+ *
+ *  - Takes in `biome` which is the biome the object is spawned in
+ *  - Takes in `level` which is how hard the run currently is
+ *  - Takes in `rng` which is the run's seeded generator
+ *
+ *  - Returns a random StaticObject
+ */
+pub fn choose_static_object(
+    biome: Biome,
+    level: &DifficultyLevel,
+    rng: &mut StdRng,
+) -> StaticObject {
+    let weights: Vec<(StaticObject, f64)> = biome
+        .object_weights()
+        .iter()
+        .map(|(object, weight)| {
+            let scale = match object {
+                StaticObject::Statue | StaticObject::Chest => {
+                    level.obstacle_weight * level.tall_obstacle_weight
+                }
+                StaticObject::Bench => level.obstacle_weight,
+                StaticObject::Power => level.power_weight,
+                StaticObject::Balloon | StaticObject::Coin | StaticObject::Rail => 1.0,
+            };
+            (*object, *weight as f64 * scale)
+        })
+        .collect();
+    choose_weighted(&weights, rng)
+}
 
-    match rng.gen_range(0..=upper) {
-        0 => TerrainType::Asphalt,
-        1 => TerrainType::Sand,
-        2 => TerrainType::Water,
-        _ => TerrainType::Grass,
+/*  Randomly choose one of a set of options
+ *
+ *  - Takes in `weights` which pairs each option with its relative chance,
+ *    at least one of which is above 0
+ *  - Takes in `rng` which is the run's seeded generator
+ *
+ *  - Returns the chosen option
+ */
+fn choose_weighted<T: Copy>(weights: &[(T, f64)], rng: &mut StdRng) -> T {
+    let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.gen_range(0.0..total);
+    for (option, weight) in weights.iter() {
+        if roll < *weight {
+            return *option;
+        }
+        roll -= weight;
     }
+    weights[weights.len() - 1].0
 }
 
-/*  Randomly choose a StaticObject
+/*  Randomly choose a set piece to build next
  *
- *  - Returns a random StaticObject
+ *  - Takes in `pieces` which are all the set pieces there are
+ *  - Takes in `biome` which is the biome the run is in
+ *  - Takes in `rng` which is the run's seeded generator
+ *
+ *  - Returns a set piece that can turn up in the biome, or None if there
+ *    aren't any
  */
-pub fn choose_static_object() -> StaticObject {
-    let mut rng = rand::thread_rng();
-    match rng.gen_range(0..=5) {
-        0 => StaticObject::Statue,
-        1 => StaticObject::Balloon,
-        2 => StaticObject::Chest,
-        3 => StaticObject::Coin,
-        4 => StaticObject::Power,
-        _ => StaticObject::Bench,
+pub fn choose_set_piece<'a>(
+    pieces: &'a [SetPiece],
+    biome: Biome,
+    rng: &mut StdRng,
+) -> Option<&'a SetPiece> {
+    let weights: Vec<(usize, f64)> = pieces
+        .iter()
+        .enumerate()
+        .filter(|(_, piece)| piece.fits(biome) && piece.weight > 0)
+        .map(|(i, piece)| (i, piece.weight as f64))
+        .collect();
+    if weights.is_empty() {
+        return None;
     }
+    Some(&pieces[choose_weighted(&weights, rng)])
 }
 
 /*  Randomly choose a PowerUp
+ *
+ *  - Takes in `rng` which is the run's seeded generator
  *
  *  - Returns a random PowerUp
  */
 // Probably shouldn't be pub when call is moved to procgen.rs
-pub fn choose_power_up() -> PowerType {
-    let mut rng = rand::thread_rng();
+pub fn choose_power_up(rng: &mut StdRng) -> PowerType {
     match rng.gen_range(0..=4) {
         // rand 0.8
         0 => PowerType::SpeedBoost,
@@ -0,0 +1,377 @@
+// Makes sure generated stretches of a run can always be survived
+//
+// Layouts are checked by running a ghost player over them with the same
+// Physics calls a real tick makes, jumping on each tick in turn. Terrain
+// that can't be landed on is flattened and objects no jump gets past are
+// left out, so a run only ends because of when the player jumped, never
+// because of what was generated.
+
+use crate::collision::Shape;
+
+use crate::physics::Body;
+use crate::physics::Entity;
+use crate::physics::Physics;
+use crate::physics::Player;
+use crate::physics::MAX_UPRIGHT_ANGLE;
+use crate::physics::MIN_RUN_SPEED;
+use crate::physics::PLAYER_MASS;
+
+use crate::proceduralgen::TerrainSegment;
+
+use crate::simulation;
+use crate::simulation::TILE_SIZE;
+
+use crate::rect;
+
+use crate::TerrainType;
+use crate::CAM_H;
+
+use sdl2::rect::Point;
+use sdl2::rect::Rect;
+
+// Share of the steepest slope the player can land upright on that hills
+// are allowed to reach
+const SLOPE_SHARE: f64 = 0.75;
+
+// Ticks in a row a jump has to work from, so clearing something never
+// takes frame perfect timing
+const TAKEOFF_WINDOW: u32 = 3;
+
+// Longest a single ghost run is simulated for
+const MAX_GHOST_TICKS: u32 = 300;
+
+// Speed generated pits are checked at, the slowest the player can be going,
+// which leaves the least time in the air to get across. Terrain can't depend
+// on how fast the player happens to be going when it's generated, or the
+// same seed would build a different run for every player
+pub const RUN_SPEED: f64 = MIN_RUN_SPEED;
+
+/*  Lowers any stretch of a segment that climbs too steeply, along with
+ *  everything after it. Speed never drops below its floor, so skating up
+ *  a hill never stalls; what makes a steep hill unfair is that landing a
+ *  jump on it tips the player over. Slopes are measured across the
+ *  player's width, the same way a tick measures them.
+ *
+ *  - Takes in `segment` which is the newly generated segment to repair
+ *
+ *  - Returns the number of points that had to be lowered
+ */
+pub fn limit_slopes(segment: &mut TerrainSegment) -> usize {
+    let span = TILE_SIZE as usize;
+    let max_rise = (MAX_UPRIGHT_ANGLE * SLOPE_SHARE).tan() * TILE_SIZE as f64;
+
+    // Ground as it will be once lowered, and where it has to be lowered
+    let mut lowered_y = Vec::with_capacity(segment.w() as usize);
+    let mut steps = Vec::new();
+    let mut offset = 0;
+    for i in 0..segment.w() as usize {
+        let y = segment.ground_y(i) + offset;
+        // Screen y grows downwards, so climbing ground has a smaller y
+        let rise = match i.checked_sub(span) {
+            Some(behind) => (lowered_y[behind] - y) as f64,
+            None => 0.0,
+        };
+        if rise > max_rise {
+            let step = (rise - max_rise).ceil() as i32;
+            steps.push((i, step));
+            offset += step;
+        }
+        lowered_y.push(segment.ground_y(i) + offset);
+    }
+    segment.shift_steps(&steps);
+    steps.len()
+}
+
+/*  Checks whether the player can get from one point to another over
+ *  whatever pits and obstacles are between them.
+ *
+ *  - Takes in `terrain` which is the ground to run over
+ *  - Takes in `obstacles` which are the shapes that end the run if touched
+ *  - Takes in `start_x` which is where the ghost starts, on the ground
+ *  - Takes in `past_x` which the middle of the ghost has to land beyond
+ *  - Takes in `speed` which is the player's speed
+ *
+ *  - Returns true if running straight there works, or jumping does from
+ *    enough ticks in a row
+ */
+pub fn can_clear(
+    terrain: &[TerrainSegment],
+    obstacles: &[Shape],
+    start_x: i32,
+    past_x: i32,
+    speed: f64,
+) -> bool {
+    if ghost_run(terrain, obstacles, start_x, past_x, speed, None) {
+        return true;
+    }
+
+    // Any later than reaching past_x is too late to jump
+    let last_takeoff =
+        (((past_x - start_x) as f64 / speed.max(1.0)).ceil() as u32).min(MAX_GHOST_TICKS);
+
+    let mut in_a_row = 0;
+    for takeoff in 1..=last_takeoff {
+        if ghost_run(terrain, obstacles, start_x, past_x, speed, Some(takeoff)) {
+            in_a_row += 1;
+            if in_a_row >= TAKEOFF_WINDOW {
+                return true;
+            }
+        } else {
+            in_a_row = 0;
+        }
+    }
+    false
+}
+
+/*  Traces where the middle of the player goes on a jump taken straight
+ *  away, so things can be laid along it.
+ *
+ *  - Takes in `terrain` which is the ground to jump from
+ *  - Takes in `start_x` which is where the ghost starts, on the ground
+ *  - Takes in `speed` which is the player's speed
+ *
+ *  - Returns the middle of the ghost on every tick from takeoff until it
+ *    lands, or nothing if there's no ground to jump from
+ */
+pub fn jump_path(terrain: &[TerrainSegment], start_x: i32, speed: f64) -> Vec<(i32, i32)> {
+    let mut ghost = match new_ghost(terrain, start_x, speed) {
+        Some(ghost) => ghost,
+        None => return Vec::new(),
+    };
+    let bottom_y = ghost.y() + CAM_H as i32;
+
+    let mut path = Vec::new();
+    let mut x = start_x as f64;
+    for tick in 0..MAX_GHOST_TICKS {
+        let footing = match Footing::under(terrain, x as i32) {
+            Some(footing) => footing,
+            None => break,
+        };
+        if tick == 0 {
+            ghost.jump(footing.ground, footing.terrain_type);
+        } else if !ghost.is_jumping()
+            || simulation::fell_into_pit(&ghost, footing.middle, footing.right, bottom_y)
+        {
+            break;
+        }
+
+        let center = ghost.hitbox().center();
+        path.push((center.x(), center.y()));
+        footing.step(&mut ghost, terrain, &mut x);
+    }
+    path
+}
+
+/*  Runs a ghost player through one attempt. In a run the world moves past
+ *  the player; here the ghost moves instead, stepping exactly like the
+ *  player does in Simulation::tick.
+ *
+ *  - Takes in the same layout as can_clear
+ *  - Takes in `takeoff` which is the tick to jump on, None to never jump
+ *
+ *  - Returns true if the ghost lands past past_x without crashing
+ */
+fn ghost_run(
+    terrain: &[TerrainSegment],
+    obstacles: &[Shape],
+    start_x: i32,
+    past_x: i32,
+    speed: f64,
+    takeoff: Option<u32>,
+) -> bool {
+    let mut ghost = match new_ghost(terrain, start_x, speed) {
+        Some(ghost) => ghost,
+        None => return false,
+    };
+    // Terrain ahead hasn't been moved into view by the camera yet, so the
+    // bottom of the screen is measured from where the ghost starts
+    let bottom_y = ghost.y() + CAM_H as i32;
+    let half = TILE_SIZE as i32 / 2;
+
+    let mut x = start_x as f64;
+    for tick in 0..MAX_GHOST_TICKS {
+        let left_x = x as i32;
+        // Nothing is known past the end of the terrain
+        let footing = match Footing::under(terrain, left_x) {
+            Some(footing) => footing,
+            None => return false,
+        };
+
+        if takeoff == Some(tick) && !ghost.is_jumping() {
+            ghost.jump(footing.ground, footing.terrain_type);
+        }
+        Physics::launch_off_lip(
+            &mut ghost,
+            footing.terrain_type,
+            footing.ground,
+            footing.right,
+        );
+
+        /* ~~~~~~ Ways to lose ~~~~~~ */
+        if !Physics::check_player_upright(&mut ghost, footing.angle, footing.ground)
+            && !footing.on_water
+        {
+            return false;
+        }
+        let shape = ghost.shape();
+        if obstacles
+            .iter()
+            .any(|o| Physics::swept_contact(&shape, ghost.moved(), o).is_some())
+        {
+            return false;
+        }
+        if simulation::fell_into_pit(&ghost, footing.middle, footing.right, bottom_y) {
+            return false;
+        }
+
+        // Made it, and is back on the ground
+        if left_x + half >= past_x
+            && !ghost.is_jumping()
+            && Physics::on_ground(ghost.hitbox(), footing.ground)
+        {
+            return true;
+        }
+
+        footing.step(&mut ghost, terrain, &mut x);
+    }
+    false
+}
+
+// Ghost standing on the ground at a point, moving at the player's speed
+fn new_ghost(terrain: &[TerrainSegment], start_x: i32, speed: f64) -> Option<Player> {
+    let start_ground = simulation::get_ground_coord(terrain, start_x + TILE_SIZE as i32 / 2)?;
+    // Standing on the ground, the same as landing leaves the player
+    let mut ghost = Player::new(
+        rect!(
+            start_x,
+            start_ground.y() - TILE_SIZE as i32,
+            TILE_SIZE,
+            TILE_SIZE
+        ),
+        PLAYER_MASS,
+    );
+    ghost.hard_set_vel((speed, 0.0));
+    Some(ghost)
+}
+
+// What's under the ghost on one tick, measured the same way a tick does
+struct Footing<'a> {
+    middle: Option<Point>,
+    right: Option<Point>,
+    angle: f64,
+    ground: Point,
+    terrain_type: &'a TerrainType,
+    on_water: bool,
+    middle_on_water: bool,
+}
+
+impl<'a> Footing<'a> {
+    // Params: terrain, left edge of the ghost
+    // Returns: None past the end of the terrain, where nothing is known
+    fn under(terrain: &'a [TerrainSegment], left_x: i32) -> Option<Footing<'a>> {
+        let end_x = terrain.last().map(|last| last.x() + last.w())?;
+        if left_x + TILE_SIZE as i32 >= end_x {
+            return None;
+        }
+
+        let half = TILE_SIZE as i32 / 2;
+        let left = simulation::get_ground_coord(terrain, left_x);
+        let middle = simulation::get_ground_coord(terrain, left_x + half);
+        let right = simulation::get_ground_coord(terrain, left_x + TILE_SIZE as i32);
+        let terrain_type = simulation::get_ground_type(terrain, left_x);
+        let middle_type = simulation::get_ground_type(terrain, left_x + half);
+        Some(Footing {
+            middle,
+            right,
+            angle: simulation::ground_angle(left, middle, right),
+            ground: simulation::ground_or_pit(middle, left_x + half),
+            terrain_type,
+            on_water: terrain_type.physics().floats,
+            middle_on_water: middle_type.physics().floats,
+        })
+    }
+
+    /* ~~~~~~ Same steps as a tick ~~~~~~ */
+    fn step(&self, ghost: &mut Player, terrain: &[TerrainSegment], x: &mut f64) {
+        Physics::apply_terrain_forces(ghost, self.angle, self.ground, self.terrain_type, None);
+        if self.on_water {
+            Physics::apply_buoyancy(ghost, self.ground);
+        }
+        Physics::apply_skate_force(ghost, self.angle, self.ground, self.terrain_type);
+        Physics::apply_wind(ghost, simulation::get_biome(terrain, *x as i32).wind());
+        ghost.update_vel(false);
+        if !self.on_water || !self.middle_on_water {
+            let middle_x = *x as i32 + TILE_SIZE as i32 / 2;
+            let ground = simulation::ground_under(terrain, middle_x, ghost.vel_x() as i32);
+            Physics::stop_at_ground(ghost, ghost.vel_x(), &ground);
+        }
+        ghost.update_pos(self.ground, self.angle, self.on_water, false);
+        ghost.reset_accel();
+
+        *x += ghost.vel_x();
+        ghost.pos.0 = *x;
+        ghost.align_hitbox_to_pos();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bezier::CubicBezier;
+    use crate::biome::Biome;
+    use crate::proceduralgen::CLIFF_MAX_DROP;
+
+    const GROUND_Y: f64 = 500.0;
+
+    // Flat ground from x 0 to 2000, drawn where it is
+    fn flat() -> TerrainSegment {
+        let curve = CubicBezier::new(
+            (0.0, GROUND_Y),
+            (700.0, GROUND_Y),
+            (1400.0, GROUND_Y),
+            (2000.0, GROUND_Y),
+        );
+        TerrainSegment::new(curve, (0, 0), TerrainType::Grass, Biome::Park)
+    }
+
+    // Box standing on the ground
+    fn standing(x: i32, w: u32, h: u32) -> Shape {
+        Shape::rect(rect!(x, GROUND_Y as i32 - h as i32, w, h), 0.0)
+    }
+
+    #[test]
+    fn narrow_pit_can_be_jumped() {
+        let mut terrain = flat();
+        terrain.add_pit(800, 920);
+        assert!(can_clear(&[terrain], &[], 600, 970, RUN_SPEED));
+    }
+
+    #[test]
+    fn wide_pit_cant_be_jumped() {
+        let mut terrain = flat();
+        terrain.add_pit(800, 1600);
+        assert!(!can_clear(&[terrain], &[], 600, 1650, RUN_SPEED));
+    }
+
+    #[test]
+    fn low_obstacle_can_be_jumped() {
+        let obstacle = standing(900, 40, 40);
+        assert!(can_clear(&[flat()], &[obstacle], 600, 990, RUN_SPEED));
+    }
+
+    #[test]
+    fn wall_cant_be_jumped() {
+        let wall = standing(900, 40, 400);
+        assert!(!can_clear(&[flat()], &[wall], 600, 990, RUN_SPEED));
+    }
+
+    #[test]
+    fn deepest_cliff_can_be_run_off() {
+        let mut terrain = flat();
+        terrain.add_cliff(900, CLIFF_MAX_DROP);
+        let terrain = [terrain];
+        assert!(ghost_run(&terrain, &[], 700, 1100, RUN_SPEED, None));
+        assert!(can_clear(&terrain, &[], 700, 1100, RUN_SPEED));
+    }
+}
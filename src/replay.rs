@@ -0,0 +1,257 @@
+// Recording and playback of a run's inputs
+//
+// A replay is the run seed and difficulty plus every input event keyed by
+// the simulation tick it happened on. Since the simulation is deterministic
+// for a seed and difficulty, feeding the same events back on the same ticks
+// reproduces the run exactly.
+//
+// File format, one entry per line:
+//     urban-odyssey-replay 1
+//     seed 1234567890
+//     difficulty normal
+//     120 jump
+//     185 flip_start
+//     201 flip_stop
+//     240 grind_start
+//     262 lean_back
+//     300 grind_stop
+//     310 grab_start
+//     330 grab_stop
+
+use crate::difficulty::Difficulty;
+
+use crate::simulation::TickInput;
+
+use std::fs;
+use std::path::Path;
+
+const HEADER: &str = "urban-odyssey-replay 1";
+
+// Input events worth recording
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReplayEvent {
+    Jump,       // Jump pressed while on the ground or a rail
+    FlipStart,  // Flip pressed while in the air
+    FlipStop,   // Flip released
+    GrindStart, // Grind pressed
+    GrindStop,  // Grind released
+    LeanBack,   // Leaned back, only does anything on a rail or in the air
+    LeanAhead,  // Leaned ahead, only does anything on a rail or in the air
+    GrabStart,  // Grab pressed
+    GrabStop,   // Grab released
+    Pause,      // Game paused, the simulation doesn't advance until resumed
+}
+
+impl ReplayEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            ReplayEvent::Jump => "jump",
+            ReplayEvent::FlipStart => "flip_start",
+            ReplayEvent::FlipStop => "flip_stop",
+            ReplayEvent::GrindStart => "grind_start",
+            ReplayEvent::GrindStop => "grind_stop",
+            ReplayEvent::LeanBack => "lean_back",
+            ReplayEvent::LeanAhead => "lean_ahead",
+            ReplayEvent::GrabStart => "grab_start",
+            ReplayEvent::GrabStop => "grab_stop",
+            ReplayEvent::Pause => "pause",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<ReplayEvent> {
+        match name {
+            "jump" => Some(ReplayEvent::Jump),
+            "flip_start" => Some(ReplayEvent::FlipStart),
+            "flip_stop" => Some(ReplayEvent::FlipStop),
+            "grind_start" => Some(ReplayEvent::GrindStart),
+            "grind_stop" => Some(ReplayEvent::GrindStop),
+            "lean_back" => Some(ReplayEvent::LeanBack),
+            "lean_ahead" => Some(ReplayEvent::LeanAhead),
+            "grab_start" => Some(ReplayEvent::GrabStart),
+            "grab_stop" => Some(ReplayEvent::GrabStop),
+            "pause" => Some(ReplayEvent::Pause),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Replay {
+    seed: u64,
+    difficulty: Difficulty,
+    events: Vec<(u64, ReplayEvent)>, // (tick, event), in tick order
+}
+
+impl Replay {
+    pub fn new(seed: u64, difficulty: Difficulty) -> Replay {
+        Replay {
+            seed,
+            difficulty,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn events(&self) -> &[(u64, ReplayEvent)] {
+        &self.events
+    }
+
+    // Adds an event that happened right before the given tick was simulated
+    pub fn record(&mut self, tick: u64, event: ReplayEvent) {
+        self.events.push((tick, event));
+    }
+
+    // Records everything in a tick's input
+    // Params: tick the input is for, input, whether the player was airborne
+    //         and off any rail, where jump does what it does on the ground
+    pub fn record_input(&mut self, tick: u64, input: &TickInput, airborne: bool) {
+        // Only the press that can do anything this tick matters
+        if airborne && input.flip {
+            self.record(tick, ReplayEvent::FlipStart);
+        } else if !airborne && input.jump {
+            self.record(tick, ReplayEvent::Jump);
+        }
+        if input.release {
+            self.record(tick, ReplayEvent::FlipStop);
+        }
+        if input.grind {
+            self.record(tick, ReplayEvent::GrindStart);
+        }
+        if input.let_go {
+            self.record(tick, ReplayEvent::GrindStop);
+        }
+        if input.lean_back {
+            self.record(tick, ReplayEvent::LeanBack);
+        }
+        if input.lean_ahead {
+            self.record(tick, ReplayEvent::LeanAhead);
+        }
+        if input.grab {
+            self.record(tick, ReplayEvent::GrabStart);
+        }
+        if input.grab_release {
+            self.record(tick, ReplayEvent::GrabStop);
+        }
+    }
+
+    // Writes the replay out in the text format described at the top of this file
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let mut contents = format!(
+            "{}\nseed {}\ndifficulty {}\n",
+            HEADER,
+            self.seed,
+            self.difficulty.name()
+        );
+        for (tick, event) in self.events.iter() {
+            contents.push_str(&format!("{} {}\n", tick, event.name()));
+        }
+
+        fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // Reads a replay written by save
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut lines = contents.lines().filter(|l| !l.trim().is_empty()).peekable();
+
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err(format!("{}: not a replay file", path.display()));
+        }
+
+        let seed = lines
+            .next()
+            .and_then(|l| l.trim().strip_prefix("seed "))
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .ok_or(format!("{}: missing seed", path.display()))?;
+
+        // Replays from before difficulties were added are all normal
+        let mut first_event_line = 3;
+        let mut difficulty = Difficulty::Normal;
+        if let Some(name) = lines
+            .peek()
+            .and_then(|l| l.trim().strip_prefix("difficulty "))
+        {
+            difficulty = Difficulty::from_name(name.trim()).ok_or(format!(
+                "{}: unknown difficulty {:?}",
+                path.display(),
+                name
+            ))?;
+            lines.next();
+            first_event_line += 1;
+        }
+
+        let mut replay = Replay::new(seed, difficulty);
+        for (i, line) in lines.enumerate() {
+            let mut parts = line.split_whitespace();
+            let tick = parts.next().and_then(|t| t.parse::<u64>().ok());
+            let event = parts.next().and_then(ReplayEvent::from_name);
+            match (tick, event) {
+                (Some(tick), Some(event)) => replay.record(tick, event),
+                _ => {
+                    return Err(format!(
+                        "{}: bad event on line {}: {:?}",
+                        path.display(),
+                        i + first_event_line,
+                        line
+                    ))
+                }
+            }
+        }
+
+        Ok(replay)
+    }
+}
+
+// Feeds a recorded replay back into the simulation one tick at a time
+pub struct ReplayPlayer<'a> {
+    replay: &'a Replay,
+    next_event: usize,
+}
+
+impl<'a> ReplayPlayer<'a> {
+    pub fn new(replay: &'a Replay) -> ReplayPlayer<'a> {
+        ReplayPlayer {
+            replay,
+            next_event: 0,
+        }
+    }
+
+    // Builds the input recorded for the given tick
+    // Params: tick about to be simulated
+    // Returns: input to pass to Simulation::tick
+    pub fn input_for(&mut self, tick: u64) -> TickInput {
+        let mut input = TickInput::default();
+        while let Some((event_tick, event)) = self.replay.events.get(self.next_event) {
+            if *event_tick > tick {
+                break;
+            }
+            match event {
+                ReplayEvent::Jump => input.jump = true,
+                ReplayEvent::FlipStart => input.flip = true,
+                ReplayEvent::FlipStop => input.release = true,
+                ReplayEvent::GrindStart => input.grind = true,
+                ReplayEvent::GrindStop => input.let_go = true,
+                ReplayEvent::LeanBack => input.lean_back = true,
+                ReplayEvent::LeanAhead => input.lean_ahead = true,
+                ReplayEvent::GrabStart => input.grab = true,
+                ReplayEvent::GrabStop => input.grab_release = true,
+                // Pausing never advances the simulation, nothing to feed back
+                ReplayEvent::Pause => {}
+            }
+            self.next_event += 1;
+        }
+        input
+    }
+}
@@ -0,0 +1,202 @@
+// Hand made stretches of a run mixed in between generated terrain
+//
+// Set pieces are written in assets/setpieces.toml, see the top of that file
+// for the format. The file is built into the game rather than read when a
+// run starts, so a replay always meets the same set pieces it was recorded
+// with.
+
+use crate::biome::Biome;
+
+use crate::proceduralgen::SEGMENT_MAX_W;
+use crate::proceduralgen::SEGMENT_MIN_W;
+
+use crate::StaticObject;
+use crate::TerrainType;
+
+const SET_PIECES: &str = include_str!("../assets/setpieces.toml");
+
+#[derive(Clone)]
+pub struct SetPiece {
+    pub name: String,
+    pub biomes: Vec<Biome>, // Biomes it can turn up in, any if empty
+    pub weight: u32,        // Chance of being picked against the others
+    pub terrain_type: TerrainType,
+
+    // Positions are from where the set piece starts, with y going up
+    pub handle: (f64, f64),                       // Second handle of the curve
+    pub end: (f64, f64),                          // End of the curve
+    pub pits: Vec<(usize, usize)>,                // (x, width)
+    pub objects: Vec<(StaticObject, usize, i32)>, // (object, x, y of its bottom)
+}
+
+impl SetPiece {
+    pub fn fits(&self, biome: Biome) -> bool {
+        self.biomes.is_empty() || self.biomes.contains(&biome)
+    }
+}
+
+// Every set piece built into the game. A broken file is reported and leaves
+// runs with no set pieces rather than stopping the game
+pub fn builtin() -> Vec<SetPiece> {
+    parse(SET_PIECES).unwrap_or_else(|e| {
+        println!("\n\t\tSet pieces not loaded: {}", e);
+        Vec::new()
+    })
+}
+
+// Params: contents of a set piece file
+// Returns: the set pieces in it, in file order
+pub fn parse(contents: &str) -> Result<Vec<SetPiece>, String> {
+    let mut pieces: Vec<SetPiece> = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        // Strip comments and blank lines
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let bad_line = |e: &str| format!("line {}: {}", i + 1, e);
+
+        if line.starts_with('[') && line.ends_with(']') {
+            if let Some(last) = pieces.last() {
+                check(last)?;
+            }
+            pieces.push(SetPiece {
+                name: line[1..line.len() - 1].trim().to_string(),
+                biomes: Vec::new(),
+                weight: 1,
+                terrain_type: TerrainType::Grass,
+                handle: (0.0, 0.0),
+                end: (0.0, 0.0),
+                pits: Vec::new(),
+                objects: Vec::new(),
+            });
+            continue;
+        }
+
+        let piece = pieces
+            .last_mut()
+            .ok_or_else(|| bad_line("entry before any [set piece]"))?;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| bad_line("expected key = value"))?;
+        let value = value.trim();
+
+        match key.trim() {
+            "biomes" => {
+                for name in value.split(',') {
+                    let biome = parse_biome(name.trim())
+                        .ok_or_else(|| bad_line(&format!("unknown biome {:?}", name.trim())))?;
+                    piece.biomes.push(biome);
+                }
+            }
+            "weight" => {
+                piece.weight = value
+                    .parse()
+                    .map_err(|_| bad_line("weight should be a whole number"))?;
+            }
+            "terrain" => {
+                piece.terrain_type = parse_terrain(value)
+                    .ok_or_else(|| bad_line(&format!("unknown terrain {:?}", value)))?;
+            }
+            "shape" => {
+                let points = value
+                    .split(',')
+                    .map(|p| parse_pair(p).map(|(x, y)| (x as f64, y as f64)))
+                    .collect::<Option<Vec<_>>>();
+                match points.as_deref() {
+                    Some([handle, end]) => {
+                        piece.handle = *handle;
+                        piece.end = *end;
+                    }
+                    _ => return Err(bad_line("shape should be \"x y, x y\"")),
+                }
+            }
+            "pit" => {
+                let (x, width) = parse_pair(value)
+                    .filter(|(x, width)| *x >= 0 && *width > 0)
+                    .ok_or_else(|| bad_line("pit should be \"x width\""))?;
+                piece.pits.push((x as usize, width as usize));
+            }
+            key => {
+                let object = parse_object(key).ok_or_else(|| bad_line("unknown key"))?;
+                let (x, y) = parse_pair(value)
+                    .filter(|(x, _)| *x >= 0)
+                    .ok_or_else(|| bad_line("object should be \"x y\""))?;
+                piece.objects.push((object, x as usize, y));
+            }
+        }
+    }
+
+    if let Some(last) = pieces.last() {
+        check(last)?;
+    }
+    Ok(pieces)
+}
+
+// Makes sure a set piece joins on to the terrain either side of it. Its
+// width is kept to that of generated segments, and its end handle to the
+// last third, so the curves before and after it still only go left to right
+fn check(piece: &SetPiece) -> Result<(), String> {
+    let width = piece.end.0;
+    if width < SEGMENT_MIN_W as f64 || width > SEGMENT_MAX_W as f64 {
+        return Err(format!(
+            "[{}] should end between x {} and {}",
+            piece.name, SEGMENT_MIN_W, SEGMENT_MAX_W
+        ));
+    }
+    if piece.handle.0 < width * 2.0 / 3.0 || piece.handle.0 > width {
+        return Err(format!(
+            "[{}] handle should be in the last third of its width",
+            piece.name
+        ));
+    }
+    if piece.objects.iter().any(|(_, x, _)| *x as f64 >= width)
+        || piece.pits.iter().any(|(x, w)| (x + w) as f64 > width)
+    {
+        return Err(format!("[{}] has something past its end", piece.name));
+    }
+    Ok(())
+}
+
+// Two whole numbers separated by spaces
+fn parse_pair(text: &str) -> Option<(i32, i32)> {
+    let mut numbers = text.split_whitespace().map(|n| n.parse::<i32>());
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(a)), Some(Ok(b)), None) => Some((a, b)),
+        _ => None,
+    }
+}
+
+fn parse_biome(name: &str) -> Option<Biome> {
+    [Biome::Park, Biome::Beach, Biome::Downtown, Biome::Harbour]
+        .iter()
+        .copied()
+        .find(|biome| biome.name().eq_ignore_ascii_case(name))
+}
+
+fn parse_terrain(name: &str) -> Option<TerrainType> {
+    match name {
+        "grass" => Some(TerrainType::Grass),
+        "asphalt" => Some(TerrainType::Asphalt),
+        "sand" => Some(TerrainType::Sand),
+        "water" => Some(TerrainType::Water),
+        "ice" => Some(TerrainType::Ice),
+        "mud" => Some(TerrainType::Mud),
+        "conveyor" => Some(TerrainType::Conveyor),
+        _ => None,
+    }
+}
+
+fn parse_object(name: &str) -> Option<StaticObject> {
+    match name {
+        "coin" => Some(StaticObject::Coin),
+        "power" => Some(StaticObject::Power),
+        "statue" => Some(StaticObject::Statue),
+        "balloon" => Some(StaticObject::Balloon),
+        "chest" => Some(StaticObject::Chest),
+        "bench" => Some(StaticObject::Bench),
+        "rail" => Some(StaticObject::Rail),
+        _ => None,
+    }
+}
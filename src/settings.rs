@@ -0,0 +1,368 @@
+// Player settings
+//
+// Saved as a small TOML file in the user's data folder:
+//     window_width = 1280
+//     window_height = 720
+//     fullscreen = "windowed"  # windowed, fullscreen or desktop
+//     scaling = "letterbox"    # letterbox or stretch
+//     vsync = true
+//     frame_cap = 240          # 0 for no cap
+//     master_volume = 1.0
+//     music_volume = 0.6
+//     effects_volume = 0.8
+//     show_score = true
+//     show_power_timer = true
+//     show_fps = false
+//     difficulty = "normal"    # easy, normal or hard
+// Missing keys keep their defaults, so older files keep working.
+
+use crate::audio;
+use crate::difficulty::Difficulty;
+use crate::timestep;
+use crate::utils;
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "settings.toml";
+
+// Window sizes offered on the settings screen
+pub const WINDOW_SIZES: [(u32, u32); 5] = [
+    (960, 540),
+    (1280, 720),
+    (1600, 900),
+    (1920, 1080),
+    (2560, 1440),
+];
+
+// Frame caps offered on the settings screen, 0 is no cap
+pub const FRAME_CAPS: [u32; 6] = [30, 60, 120, 144, 240, 0];
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FullscreenMode {
+    Windowed,
+    Fullscreen, // Changes the display's resolution to the window size
+    Desktop,    // Borderless window covering the whole display
+}
+
+impl FullscreenMode {
+    pub const ALL: [FullscreenMode; 3] = [
+        FullscreenMode::Windowed,
+        FullscreenMode::Fullscreen,
+        FullscreenMode::Desktop,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FullscreenMode::Windowed => "windowed",
+            FullscreenMode::Fullscreen => "fullscreen",
+            FullscreenMode::Desktop => "desktop",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<FullscreenMode> {
+        match name {
+            // Older settings files stored a plain on / off
+            "false" => Some(FullscreenMode::Windowed),
+            "true" => Some(FullscreenMode::Fullscreen),
+            _ => FullscreenMode::ALL
+                .iter()
+                .copied()
+                .find(|mode| mode.name() == name),
+        }
+    }
+}
+
+// How the CAM_W x CAM_H canvas is fit to a window of a different shape
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ScaleMode {
+    Letterbox, // Keep the aspect ratio, bars fill the rest
+    Stretch,   // Fill the window, distorting if needed
+}
+
+impl ScaleMode {
+    pub const ALL: [ScaleMode; 2] = [ScaleMode::Letterbox, ScaleMode::Stretch];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScaleMode::Letterbox => "letterbox",
+            ScaleMode::Stretch => "stretch",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<ScaleMode> {
+        ScaleMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == name)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Settings {
+    /* ~~~~~~ Display ~~~~~~ */
+    pub window_width: u32,
+    pub window_height: u32,
+    pub fullscreen: FullscreenMode,
+    pub scaling: ScaleMode,
+    pub vsync: bool,    // Only takes effect after a restart
+    pub frame_cap: u32, // Max frames drawn per second, 0 for no cap
+
+    /* ~~~~~~ Audio ~~~~~~ */
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+
+    /* ~~~~~~ HUD ~~~~~~ */
+    pub show_score: bool,       // Score and points for the last pickup
+    pub show_power_timer: bool, // Active power-up and its time left
+    pub show_fps: bool,         // Frames per second counter
+
+    /* ~~~~~~ Gameplay ~~~~~~ */
+    pub difficulty: Difficulty, // Chosen on the title screen
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            window_width: crate::CAM_W,
+            window_height: crate::CAM_H,
+            fullscreen: FullscreenMode::Windowed,
+            scaling: ScaleMode::Letterbox,
+            vsync: true,
+            frame_cap: timestep::DEFAULT_FRAME_CAP as u32,
+
+            master_volume: audio::DEFAULT_MASTER_VOLUME,
+            music_volume: audio::DEFAULT_MUSIC_VOLUME,
+            effects_volume: audio::DEFAULT_EFFECTS_VOLUME,
+
+            show_score: true,
+            show_power_timer: true,
+            show_fps: false,
+
+            difficulty: Difficulty::Normal,
+        }
+    }
+}
+
+impl Settings {
+    pub fn default_path() -> PathBuf {
+        utils::data_dir().join(FILE_NAME)
+    }
+
+    // Loads the settings file, falling back to the defaults if it's missing
+    // or broken so a bad file never stops the game from starting
+    pub fn load_or_default() -> Settings {
+        match Settings::load(&Settings::default_path()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                println!("\n\t\tCouldn't read settings, using defaults: {}", e);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Settings, String> {
+        let mut settings = Settings::default();
+        if !path.exists() {
+            return Ok(settings);
+        }
+
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        for (i, line) in contents.lines().enumerate() {
+            // Strip comments and blank lines
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let bad_line = || {
+                format!(
+                    "{}: bad setting on line {}: {:?}",
+                    path.display(),
+                    i + 1,
+                    line
+                )
+            };
+            let (key, value) = line.split_once('=').ok_or_else(bad_line)?;
+            settings
+                .set(key.trim(), value.trim())
+                .map_err(|e| format!("{} ({})", bad_line(), e))?;
+        }
+
+        Ok(settings)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let contents = format!(
+            "# Urban Odyssey settings\n\
+             window_width = {}\n\
+             window_height = {}\n\
+             fullscreen = \"{}\"\n\
+             scaling = \"{}\"\n\
+             vsync = {}\n\
+             frame_cap = {}\n\
+             master_volume = {:.2}\n\
+             music_volume = {:.2}\n\
+             effects_volume = {:.2}\n\
+             show_score = {}\n\
+             show_power_timer = {}\n\
+             show_fps = {}\n\
+             difficulty = \"{}\"\n",
+            self.window_width,
+            self.window_height,
+            self.fullscreen.name(),
+            self.scaling.name(),
+            self.vsync,
+            self.frame_cap,
+            self.master_volume,
+            self.music_volume,
+            self.effects_volume,
+            self.show_score,
+            self.show_power_timer,
+            self.show_fps,
+            self.difficulty.name(),
+        );
+
+        fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // Frame cap as used by FixedTimestep::set_frame_cap
+    pub fn frame_cap(&self) -> Option<f64> {
+        if self.frame_cap == 0 {
+            None
+        } else {
+            Some(self.frame_cap as f64)
+        }
+    }
+
+    // Sets a single key from the file, unknown keys are an error
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "window_width" => self.window_width = parse(value)?,
+            "window_height" => self.window_height = parse(value)?,
+            "fullscreen" => {
+                self.fullscreen = FullscreenMode::from_name(unquote(value))
+                    .ok_or(format!("invalid value {:?}", value))?
+            }
+            "scaling" => {
+                self.scaling = ScaleMode::from_name(unquote(value))
+                    .ok_or(format!("invalid value {:?}", value))?
+            }
+            "vsync" => self.vsync = parse(value)?,
+            "frame_cap" => self.frame_cap = parse(value)?,
+            "master_volume" => self.master_volume = parse::<f32>(value)?.clamp(0.0, 1.0),
+            "music_volume" => self.music_volume = parse::<f32>(value)?.clamp(0.0, 1.0),
+            "effects_volume" => self.effects_volume = parse::<f32>(value)?.clamp(0.0, 1.0),
+            "show_score" => self.show_score = parse(value)?,
+            "show_power_timer" => self.show_power_timer = parse(value)?,
+            "show_fps" => self.show_fps = parse(value)?,
+            "difficulty" => {
+                self.difficulty = Difficulty::from_name(unquote(value))
+                    .ok_or(format!("invalid value {:?}", value))?
+            }
+            _ => return Err(format!("unknown setting {:?}", key)),
+        }
+        Ok(())
+    }
+}
+
+// Strips the quotes around a TOML string
+fn unquote(value: &str) -> &str {
+    value.trim_matches('"')
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid value {:?}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Loads settings from a file with the given contents, named so tests
+    // running at the same time don't share one
+    fn load_file(name: &str, contents: &str) -> Result<Settings, String> {
+        let path =
+            std::env::temp_dir().join(format!("settings-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let settings = Settings::load(&path);
+        fs::remove_file(&path).unwrap();
+        settings
+    }
+
+    #[test]
+    fn missing_file_is_the_defaults() {
+        let path = std::env::temp_dir().join("settings-never-written.toml");
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn missing_keys_keep_their_defaults() {
+        let settings = load_file("partial", "# Just one\n\nshow_fps = true  # on\n").unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                show_fps: true,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_lines_are_rejected_with_their_line() {
+        for (name, line) in [
+            ("no_equals", "vsync true"),
+            ("unknown", "colour = \"red\""),
+            ("not_a_number", "window_width = wide"),
+            ("negative", "frame_cap = -60"),
+            ("not_a_bool", "vsync = yes"),
+            ("bad_mode", "fullscreen = \"sideways\""),
+            ("bad_difficulty", "difficulty = \"nightmare\""),
+        ] {
+            let contents = format!("show_fps = true\n\n{}\n", line);
+            let err = load_file(name, &contents).expect_err(name);
+            assert!(err.contains("line 3"), "{}: {}", name, err);
+        }
+    }
+
+    #[test]
+    fn volumes_are_clamped() {
+        let settings = load_file("volumes", "master_volume = 3.5\nmusic_volume = -1\n").unwrap();
+        assert_eq!(settings.master_volume, 1.0);
+        assert_eq!(settings.music_volume, 0.0);
+    }
+
+    #[test]
+    fn old_on_off_fullscreen_still_loads() {
+        let settings = load_file("old_fullscreen", "fullscreen = true\n").unwrap();
+        assert_eq!(settings.fullscreen, FullscreenMode::Fullscreen);
+    }
+
+    #[test]
+    fn saved_settings_load_back() {
+        let path = std::env::temp_dir().join(format!("settings-saved-{}.toml", std::process::id()));
+        let settings = Settings {
+            window_width: 1920,
+            window_height: 1080,
+            fullscreen: FullscreenMode::Desktop,
+            scaling: ScaleMode::Stretch,
+            frame_cap: 0,
+            music_volume: 0.25,
+            difficulty: Difficulty::Hard,
+            ..Settings::default()
+        };
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), settings);
+    }
+}
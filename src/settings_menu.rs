@@ -0,0 +1,262 @@
+use inf_runner::rect;
+
+use inf_runner::assets::Assets;
+
+use inf_runner::audio::MusicTrack;
+
+use inf_runner::input::Action;
+
+use inf_runner::utils::menu_row_at;
+
+use inf_runner::settings::FullscreenMode;
+use inf_runner::settings::ScaleMode;
+use inf_runner::settings::Settings;
+use inf_runner::settings::FRAME_CAPS;
+use inf_runner::settings::WINDOW_SIZES;
+
+use inf_runner::Game;
+use inf_runner::GameState;
+use inf_runner::GameStatus;
+use inf_runner::SDLCore;
+
+use inf_runner::timestep::FixedTimestep;
+use inf_runner::timestep::TICK_RATE;
+
+use sdl2::event::Event;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::TextureQuery;
+
+const ORANGE: Color = Color::RGBA(230, 150, 25, 255);
+const SILVER: Color = Color::RGBA(119, 120, 123, 255);
+const WHITE: Color = Color::RGBA(255, 255, 255, 255);
+
+const ROW_H: u32 = 40;
+const ROW_SPACING: i32 = 48;
+const FIRST_ROW_Y: i32 = 110;
+
+// Number of settings listed on the screen
+const ROWS: usize = 11;
+
+// Volume change per press
+const VOLUME_STEP: f32 = 0.1;
+
+pub struct SettingsMenu;
+
+impl Game for SettingsMenu {
+    fn init() -> Result<Self, String> {
+        Ok(SettingsMenu {})
+    }
+
+    fn run(&mut self, core: &mut SDLCore, assets: &Assets) -> Result<GameState, String> {
+        core.wincan.set_blend_mode(sdl2::render::BlendMode::Blend);
+
+        let texture_creator = core.wincan.texture_creator();
+        let font = assets.font("ui")?;
+
+        core.audio.play_music(MusicTrack::Title);
+
+        let mut selected = 0;
+
+        let next_status;
+
+        let mut timestep = FixedTimestep::new(TICK_RATE);
+        timestep.set_frame_cap(core.settings.frame_cap());
+
+        'gameloop: loop {
+            timestep.begin_frame();
+
+            for event in core.poll_events() {
+                if let Event::Quit { .. } = event {
+                    next_status = Some(GameStatus::Credits);
+                    break 'gameloop;
+                }
+
+                // Hovering the mouse over a row selects it, clicking confirms it
+                // A right click steps the hovered setting backwards
+                let mut clicked = None;
+                match event {
+                    Event::MouseMotion { y, .. } => {
+                        if let Some(row) = menu_row_at(y, FIRST_ROW_Y, ROW_SPACING, ROW_H, ROWS) {
+                            selected = row;
+                        }
+                    }
+                    Event::MouseButtonDown { mouse_btn, y, .. } => {
+                        if let Some(row) = menu_row_at(y, FIRST_ROW_Y, ROW_SPACING, ROW_H, ROWS) {
+                            selected = row;
+                            clicked = match mouse_btn {
+                                MouseButton::Left => Some(Action::Confirm),
+                                MouseButton::Right => Some(Action::Left),
+                                _ => None,
+                            };
+                        }
+                    }
+                    _ => {}
+                }
+                let mut actions = core.input.pressed_actions(&event);
+                actions.extend(clicked);
+
+                for action in actions {
+                    // Left steps a setting back, Right and Confirm step it forward
+                    let step = match action {
+                        Action::Up => {
+                            selected = (selected + ROWS - 1) % ROWS;
+                            break;
+                        }
+                        Action::Down => {
+                            selected = (selected + 1) % ROWS;
+                            break;
+                        }
+                        Action::Back => {
+                            next_status = Some(GameStatus::Main);
+                            break 'gameloop;
+                        }
+                        Action::Left => -1,
+                        Action::Right | Action::Confirm => 1,
+                        _ => continue,
+                    };
+
+                    let mut settings = core.settings.clone();
+                    change_setting(&mut settings, selected, step);
+                    if let Err(e) = core.apply_settings(settings) {
+                        println!("\n\t\tFailed to apply settings: {}", e);
+                    }
+                    // Frame cap is the only setting the loop itself uses
+                    timestep.set_frame_cap(core.settings.frame_cap());
+                    break;
+                }
+            }
+
+            /* ~~~~~~ Draw ~~~~~~ */
+            core.wincan.set_draw_color(SILVER);
+            core.wincan.clear();
+
+            let mut lines = vec![(String::from("Settings"), ORANGE, 10, 80)];
+            for (i, (name, value)) in describe_settings(&core.settings).iter().enumerate() {
+                let color = if i == selected { WHITE } else { ORANGE };
+                let y = FIRST_ROW_Y + i as i32 * ROW_SPACING;
+                lines.push((format!("{:<20} < {} >", name, value), color, y, ROW_H));
+            }
+            let bindings = core.input.bindings();
+            lines.push((
+                format!(
+                    "{}/{} - Change   {} - Main menu",
+                    bindings.describe(Action::Left),
+                    bindings.describe(Action::Right),
+                    bindings.describe(Action::Back)
+                ),
+                ORANGE,
+                650,
+                ROW_H,
+            ));
+
+            for (text, color, y, h) in lines.iter() {
+                let surface = font
+                    .render(text)
+                    .blended(*color)
+                    .map_err(|e| e.to_string())?;
+                let texture = texture_creator
+                    .create_texture_from_surface(&surface)
+                    .map_err(|e| e.to_string())?;
+                let TextureQuery { width, height, .. } = texture.query();
+                core.wincan.copy(
+                    &texture,
+                    None,
+                    Some(rect!(100, *y, width * *h / height, *h)),
+                )?;
+            }
+
+            core.wincan.present();
+
+            // Nothing here animates, ticks are only used to pace frames
+            while timestep.tick() {}
+            timestep.end_frame();
+        }
+
+        Ok(GameState {
+            status: next_status,
+            score: 0,
+        })
+    }
+}
+
+// Each setting's name and current value, in the order they're listed
+fn describe_settings(settings: &Settings) -> [(&'static str, String); ROWS] {
+    let on_off = |on: bool| String::from(if on { "On" } else { "Off" });
+    let percent = |volume: f32| format!("{:.0}%", volume * 100.0);
+    [
+        (
+            "Window size",
+            format!("{} x {}", settings.window_width, settings.window_height),
+        ),
+        (
+            "Fullscreen",
+            String::from(match settings.fullscreen {
+                FullscreenMode::Windowed => "Off",
+                FullscreenMode::Fullscreen => "On",
+                FullscreenMode::Desktop => "Desktop",
+            }),
+        ),
+        (
+            "Scaling",
+            String::from(match settings.scaling {
+                ScaleMode::Letterbox => "Letterbox",
+                ScaleMode::Stretch => "Stretch",
+            }),
+        ),
+        ("VSync (restart)", on_off(settings.vsync)),
+        (
+            "Frame cap",
+            match settings.frame_cap {
+                0 => String::from("None"),
+                cap => format!("{} FPS", cap),
+            },
+        ),
+        ("Master volume", percent(settings.master_volume)),
+        ("Music volume", percent(settings.music_volume)),
+        ("Effects volume", percent(settings.effects_volume)),
+        ("Show score", on_off(settings.show_score)),
+        ("Show power-up timer", on_off(settings.show_power_timer)),
+        ("Show FPS", on_off(settings.show_fps)),
+    ]
+}
+
+// Steps the setting on the given row forwards (1) or backwards (-1)
+fn change_setting(settings: &mut Settings, row: usize, step: i32) {
+    match row {
+        0 => {
+            let current = (settings.window_width, settings.window_height);
+            let (w, h) = cycle(&WINDOW_SIZES, &current, step);
+            settings.window_width = w;
+            settings.window_height = h;
+        }
+        1 => settings.fullscreen = cycle(&FullscreenMode::ALL, &settings.fullscreen, step),
+        2 => settings.scaling = cycle(&ScaleMode::ALL, &settings.scaling, step),
+        3 => settings.vsync = !settings.vsync,
+        4 => settings.frame_cap = cycle(&FRAME_CAPS, &settings.frame_cap, step),
+        5 => settings.master_volume = step_volume(settings.master_volume, step),
+        6 => settings.music_volume = step_volume(settings.music_volume, step),
+        7 => settings.effects_volume = step_volume(settings.effects_volume, step),
+        8 => settings.show_score = !settings.show_score,
+        9 => settings.show_power_timer = !settings.show_power_timer,
+        _ => settings.show_fps = !settings.show_fps,
+    }
+}
+
+// Next option after current in the list, wrapping around. A value that
+// isn't in the list (e.g. edited into the file by hand) starts from the top
+fn cycle<T: Copy + PartialEq>(options: &[T], current: &T, step: i32) -> T {
+    let len = options.len() as i32;
+    let index = match options.iter().position(|option| option == current) {
+        Some(index) => (index as i32 + step).rem_euclid(len),
+        None => 0,
+    };
+    options[index as usize]
+}
+
+fn step_volume(volume: f32, step: i32) -> f32 {
+    // Round to whole steps so repeated presses don't drift
+    let steps = (volume / VOLUME_STEP).round() + step as f32;
+    (steps * VOLUME_STEP).clamp(0.0, 1.0)
+}
@@ -0,0 +1,1384 @@
+// Headless core of a single run of the game
+//
+// Everything that affects gameplay (player, terrain, objects, score) lives
+// here and is advanced one tick at a time from an input snapshot. Nothing in
+// this file touches a canvas, so runs can be simulated without a window;
+// runner.rs only draws whatever state the simulation is in.
+
+use crate::biome::Biome;
+
+use crate::difficulty::Difficulty;
+use crate::difficulty::DifficultyLevel;
+
+use crate::formation::Formation;
+
+use crate::noise::Noise;
+
+use crate::bezier::CubicBezier;
+
+use crate::collision;
+use crate::collision::Shape;
+
+use crate::grind;
+use crate::grind::Grind;
+use crate::grind::RAIL_REACH;
+
+use crate::physics::Body;
+use crate::physics::Coin;
+use crate::physics::Entity;
+use crate::physics::Obstacle;
+use crate::physics::Physics;
+use crate::physics::Player;
+use crate::physics::Power;
+use crate::physics::World;
+use crate::physics::PLAYER_MASS;
+
+use crate::proceduralgen;
+use crate::proceduralgen::TerrainSegment;
+
+use crate::reachability;
+
+use crate::setpiece;
+use crate::setpiece::SetPiece;
+
+use crate::tricks::Combo;
+use crate::tricks::Trick;
+use crate::tricks::Tricks;
+use crate::tricks::MANUAL_SLOPE;
+
+use crate::rect;
+
+use crate::ObstacleType;
+use crate::PowerType;
+use crate::StaticObject;
+use crate::TerrainType;
+use crate::CAM_H;
+use crate::CAM_W;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use sdl2::rect::Point;
+use sdl2::rect::Rect;
+
+pub const TILE_SIZE: u32 = 100;
+
+// Bounds to keep the player within
+// Used for camera postioning
+pub const TERRAIN_UPPER_BOUND: i32 = 2 * TILE_SIZE as i32;
+pub const TERRAIN_LOWER_BOUND: i32 = CAM_H as i32 - TERRAIN_UPPER_BOUND;
+pub const PLAYER_X: i32 = 2 * TILE_SIZE as i32;
+
+// Max total number of coins, obstacles, and powers that can exist at
+// once. Could be split up later for more complicated procgen
+const MAX_NUM_OBJECTS: i32 = 10;
+
+// Number of ticks a collected power-up lasts
+pub const POWER_DURATION: i32 = 360;
+
+// Number of ticks the last collected point value stays on screen
+const POINT_DURATION: i32 = 60;
+
+// Number of ticks to keep simulating after the player has lost, so the crash
+// can play out before the run ends
+const GAME_OVER_DURATION: i32 = 120;
+
+// Percent chance of each new terrain segment having a pit or a cliff
+const PIT_CHANCE: i32 = 15;
+const CLIFF_CHANCE: i32 = 10;
+// Percent chance of a new terrain segment being a set piece instead, if
+// one fits the biome
+const SET_PIECE_CHANCE: i32 = 8;
+
+// How far below the far edge of a pit the player can be and still scramble
+// up onto it
+const PIT_LIP: i32 = TILE_SIZE as i32 / 5;
+
+// How much more one half of the ground under the player can drop than the
+// other before it's taken as the edge of a cliff rather than a slope
+const CLIFF_EDGE: i32 = TILE_SIZE as i32 / 5;
+
+// Each kind of random choice is drawn from its own stream of the run seed.
+// How often objects are rolled for depends on how the player plays, so
+// sharing one stream would make the terrain after it depend on that too
+const TERRAIN_STREAM: u64 = 0x7465_7272_6169_6e00;
+const OBJECT_STREAM: u64 = 0x6f62_6a65_6374_7300;
+const POWER_STREAM: u64 = 0x706f_7765_7273_0000;
+
+// How far before a new obstacle the check that it can be jumped starts
+const OBSTACLE_RUN_UP: i32 = 3 * TILE_SIZE as i32;
+
+// Stand-in ground height over pits, far enough down that nothing lands on it
+const PIT_FLOOR_Y: i32 = 4 * CAM_H as i32;
+
+// Most the camera moves vertically in a tick, so dropping off a cliff doesn't
+// jerk the whole screen at once
+const MAX_CAMERA_STEP: i32 = 30;
+
+// Player input for a single tick of the simulation
+#[derive(Default, Clone, Copy)]
+pub struct TickInput {
+    pub jump: bool,         // Jump pressed: leave the ground if standing on it
+    pub flip: bool,         // Flip pressed: start rotating if in the air
+    pub release: bool,      // Flip released: stop rotating
+    pub grind: bool,        // Grind pressed: hold on to rails, or manual, until released
+    pub let_go: bool,       // Grind released: come off any rail
+    pub lean_back: bool,    // Lean back pressed: push balance back on a rail, flip backwards
+    pub lean_ahead: bool,   // Lean ahead pressed: push balance forwards on a rail, flip forwards
+    pub grab: bool,         // Grab pressed: hold the board in the air until released
+    pub grab_release: bool, // Grab released
+}
+
+// Things that happened during a tick that the screen may want to react to,
+// e.g. by playing a sound
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameEvent {
+    Jump,     // Player left the ground
+    Coin,     // Coin collected
+    PowerUp,  // Power-up picked up
+    Flip,     // Flip completed, or went round again
+    Trick,    // Any other trick completed
+    Combo,    // Combo banked on landing
+    Crash,    // Player ran into an obstacle
+    Fall,     // Player fell into a pit
+    Splash,   // Player landed in water
+    Grind,    // Player's board landed on a rail
+    Bail,     // Player lost their balance on a rail
+    GameOver, // Run just ended
+}
+
+pub struct Simulation {
+    pub player: Player,
+
+    // Ground / object vectors
+    pub all_terrain: Vec<TerrainSegment>,
+    pub all_obstacles: Vec<Obstacle>,
+    pub all_coins: Vec<Coin>,
+    // Refers to powers currently spawned on the ground, not active powers
+    pub all_powers: Vec<Power>,
+
+    // Score of an entire run
+    pub total_score: i32,
+
+    // Run stats kept for the high score table
+    distance: f64,        // Pixels travelled
+    coins_collected: u32, // Coins picked up
+
+    power_timer: i32,    // Current powerup expires when it reaches 0
+    point_timer: i32,    // Timer to show +point_value
+    last_point_val: i32, // Last collected obstacle/coin's value
+
+    game_over: bool,
+    game_over_timer: i32,
+
+    // Events from the last tick
+    events: Vec<GameEvent>,
+    in_water: bool, // Player was in the water last tick
+
+    grind: Option<Grind>, // Rail the player is on, if any
+    grind_held: bool,     // Grind is being held down
+    grab_held: bool,      // Grab is being held down
+
+    tricks: Tricks,
+    last_combo: Option<Combo>, // Combo banked most recently, shown for a while
+
+    // Number of ticks simulated so far
+    ticks: u64,
+
+    // How far the world moved left and down on screen last tick
+    scroll: (i32, i32),
+
+    // Object spawning vars
+    spawn_timer: f64, // Can spawn a new object when it reaches 0
+
+    // Biome new terrain is generated in, and how many more segments of it
+    // there are before the next one
+    biome: Biome,
+    biome_segments_left: u32,
+
+    // Preset deciding how quickly the run gets harder
+    difficulty: Difficulty,
+
+    // Seed the run was started from, every random choice is drawn from a
+    // stream of it
+    seed: u64,
+    terrain_rng: StdRng, // Shape and type of terrain, biomes, set pieces
+    object_rng: StdRng,  // When objects spawn and which ones
+    power_rng: StdRng,   // Which power-up each one is
+
+    // World x where the terrain generated so far ends, and the view y the
+    // camera will have settled at by then. Both are kept apart from the real
+    // camera so new terrain never depends on how the player has played
+    terrain_end: f64,
+    terrain_view_y: i32,
+
+    // Noise the shape of the terrain is drawn from
+    noise: Noise,
+
+    // Hand made stretches that can be mixed into the terrain
+    set_pieces: Vec<SetPiece>,
+}
+
+impl Simulation {
+    // Creates a new run. The same seed and difficulty always generate the
+    // same terrain, objects and power-ups
+    pub fn new(seed: u64, difficulty: Difficulty) -> Simulation {
+        let mut terrain_rng = StdRng::seed_from_u64(seed ^ TERRAIN_STREAM);
+
+        // Perlin Noise init
+        let noise = Noise::new(terrain_rng.gen());
+
+        // Starting terrain is a flat line across the screen
+        let start_y = (TERRAIN_LOWER_BOUND + TILE_SIZE as i32) as f64;
+        let start_curve = CubicBezier::new(
+            (0.0, start_y),
+            (CAM_W as f64 / 3.0, start_y),
+            (CAM_W as f64 * 2.0 / 3.0, start_y),
+            (CAM_W as f64, start_y),
+        );
+        let biome_segments_left =
+            terrain_rng.gen_range(Biome::Park.length().0..=Biome::Park.length().1);
+
+        let init_terrain_1 =
+            TerrainSegment::new(start_curve, (0, 0), TerrainType::Grass, Biome::Park);
+        let terrain_view_y = camera_after(&init_terrain_1, 0);
+
+        Simulation {
+            // Create player at default position
+            player: Player::new(
+                rect!(
+                    PLAYER_X,
+                    TERRAIN_UPPER_BOUND, // + TILE_SIZE as i32,
+                    TILE_SIZE,
+                    TILE_SIZE
+                ),
+                PLAYER_MASS,
+            ),
+
+            all_terrain: vec![init_terrain_1],
+            all_obstacles: Vec::new(),
+            all_coins: Vec::new(),
+            all_powers: Vec::new(),
+
+            total_score: 0,
+            distance: 0.0,
+            coins_collected: 0,
+
+            power_timer: 0,
+            point_timer: 0,
+            last_point_val: 0,
+
+            game_over: false,
+            game_over_timer: GAME_OVER_DURATION,
+            events: Vec::new(),
+            in_water: false,
+
+            grind: None,
+            grind_held: false,
+            grab_held: false,
+
+            tricks: Tricks::new(),
+            last_combo: None,
+
+            ticks: 0,
+            scroll: (0, 0),
+
+            spawn_timer: 500.0,
+
+            biome: Biome::Park,
+            biome_segments_left,
+
+            difficulty,
+
+            seed,
+            terrain_rng,
+            object_rng: StdRng::seed_from_u64(seed ^ OBJECT_STREAM),
+            power_rng: StdRng::seed_from_u64(seed ^ POWER_STREAM),
+
+            terrain_end: CAM_W as f64,
+            terrain_view_y,
+
+            noise,
+
+            set_pieces: setpiece::builtin(),
+        }
+    }
+
+    // Accessors
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    // How hard the run is at the distance travelled so far
+    pub fn level(&self) -> DifficultyLevel {
+        self.difficulty.at(self.distance / TILE_SIZE as f64)
+    }
+
+    pub fn game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.ticks
+    }
+
+    // True once the game over animation has played out and the run is done
+    pub fn is_finished(&self) -> bool {
+        self.game_over && self.game_over_timer <= 0
+    }
+
+    // Distance travelled this run in metres, one tile is a metre
+    pub fn distance(&self) -> u64 {
+        (self.distance / TILE_SIZE as f64) as u64
+    }
+
+    pub fn coins_collected(&self) -> u32 {
+        self.coins_collected
+    }
+
+    // Pixels the world moved left and down last tick, for the background
+    // to follow
+    pub fn scroll(&self) -> (i32, i32) {
+        self.scroll
+    }
+
+    // Everything that happened during the last tick, in order
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    pub fn power_timer(&self) -> i32 {
+        self.power_timer
+    }
+
+    pub fn point_timer(&self) -> i32 {
+        self.point_timer
+    }
+
+    pub fn grind(&self) -> Option<&Grind> {
+        self.grind.as_ref()
+    }
+
+    // Combo in progress, if any
+    pub fn combo(&self) -> Option<&Combo> {
+        self.tricks.combo()
+    }
+
+    // Combo banked most recently, if its points are still on screen
+    pub fn last_combo(&self) -> Option<&Combo> {
+        if self.point_timer > 0 {
+            self.last_combo.as_ref()
+        } else {
+            None
+        }
+    }
+
+    // True while the player is holding a grab in the air
+    pub fn is_grabbing(&self) -> bool {
+        self.grab_held && self.player.is_jumping() && !self.player.is_grinding()
+    }
+
+    // True while the player is in a manual
+    pub fn is_manual(&self) -> bool {
+        self.tricks.is_manual()
+    }
+
+    pub fn last_point_val(&self) -> i32 {
+        self.last_point_val
+    }
+
+    // Biome the player is currently in
+    pub fn biome(&self) -> Biome {
+        get_biome(&self.all_terrain, PLAYER_X)
+    }
+
+    // Advances the game by a single tick
+    // Params: player input received since the last tick
+    // Returns: none
+    pub fn tick(&mut self, input: &TickInput) {
+        self.ticks += 1;
+        self.events.clear();
+
+        // Remember where everything was so drawing can blend between ticks
+        self.player.save_prev_pos();
+        for ground in self.all_terrain.iter_mut() {
+            ground.save_prev_pos();
+        }
+        for obs in self.all_obstacles.iter_mut() {
+            obs.save_prev_pos();
+        }
+        for coin in self.all_coins.iter_mut() {
+            coin.save_prev_pos();
+        }
+        for power_up in self.all_powers.iter_mut() {
+            power_up.save_prev_pos();
+        }
+
+        // End game loop, 'player has lost' state
+        if self.game_over {
+            self.game_over_timer -= 1; // Animation buffer
+            if self.game_over_timer <= 0 {
+                return;
+            }
+        }
+
+        // Score collected in a single tick
+        let mut curr_step_score: f64 = 0.0;
+
+        //  Get ground point at player and TILE_SIZE ahead of player, None over a pit
+        let left_ground = get_ground_coord(&self.all_terrain, PLAYER_X); // left of player
+        let curr_ground = get_ground_coord(&self.all_terrain, PLAYER_X + (TILE_SIZE as i32) / 2); // middle of player
+        let right_ground = get_ground_coord(&self.all_terrain, PLAYER_X + TILE_SIZE as i32); // right of player
+        let angle = ground_angle(left_ground, curr_ground, right_ground); // slope under player
+        let curr_ground_point = ground_or_pit(curr_ground, PLAYER_X + (TILE_SIZE as i32) / 2);
+        // Copied out of the terrain, which grinding needs self free to change
+        let terrain_type = *get_ground_type(&self.all_terrain, PLAYER_X);
+        let curr_terrain_type = &terrain_type; //for physics
+
+        /* ~~~~~~ Handle Input ~~~~~~ */
+        if input.grind {
+            self.grind_held = true;
+        }
+        if input.let_go {
+            self.grind_held = false;
+        }
+        if input.grab {
+            self.grab_held = true;
+        }
+        if input.grab_release {
+            self.grab_held = false;
+        }
+        if !self.game_over {
+            if self.player.is_grinding() {
+                if input.jump {
+                    self.player.jump_off_rail();
+                    self.events.push(GameEvent::Jump);
+                    self.finish_grind();
+                } else if !self.grind_held {
+                    self.player.leave_rail(self.player.theta());
+                    self.finish_grind();
+                } else if let Some(grind) = self.grind.as_mut() {
+                    if input.lean_back {
+                        grind.lean(false);
+                    }
+                    if input.lean_ahead {
+                        grind.lean(true);
+                    }
+                }
+            } else if self.player.is_jumping() {
+                if input.lean_back {
+                    self.player.set_flip_direction(false);
+                }
+                if input.lean_ahead {
+                    self.player.set_flip_direction(true);
+                }
+                if input.flip {
+                    self.player.resume_flipping();
+                }
+            } else if input.jump && self.player.jump(curr_ground_point, curr_terrain_type) {
+                self.events.push(GameEvent::Jump);
+            }
+            if input.release {
+                self.player.stop_flipping();
+            }
+        }
+
+        //Power handling
+        if self.power_timer == 0 {
+            self.power_timer -= 1;
+            self.player.set_power_up(None);
+        } else if self.power_timer > 0 {
+            self.power_timer -= 1;
+        }
+
+        // Apply bouncy shoes, if applicable
+        // Effectively just repeated jumps, independent of player input
+        if let Some(PowerType::BouncyShoes) = self.player.power_up() {
+            if !self.player.is_jumping() && self.player.jump(curr_ground_point, curr_terrain_type) {
+                self.events.push(GameEvent::Jump);
+            }
+        }
+
+        // Running off the lip of a ramp throws the player into the air
+        if !self.game_over
+            && Physics::launch_off_lip(
+                &mut self.player,
+                curr_terrain_type,
+                curr_ground_point,
+                right_ground,
+            )
+        {
+            self.events.push(GameEvent::Jump);
+        }
+
+        /* ~~~~~~ Handle Player Collisions ~~~~~~ */
+
+        // If the player doesn't land on ther feet, end game
+        // except on water
+        let on_water = curr_terrain_type.physics().floats;
+
+        let was_game_over = self.game_over;
+
+        if !Physics::check_player_upright(&mut self.player, angle, curr_ground_point) {
+            // A bad landing loses the combo, even on water
+            self.tricks.bail();
+            if !on_water {
+                self.game_over = true;
+            }
+        }
+
+        // Over a pit, the run is over once the player drops below the far
+        // edge and hits the wall, or falls off the bottom of the screen
+        if !self.game_over && fell_into_pit(&self.player, curr_ground, right_ground, CAM_H as i32) {
+            // Stop dead against the wall and drop
+            self.player.hard_set_vel((0.0, self.player.vel_y()));
+            self.events.push(GameEvent::Fall);
+            self.game_over = true;
+        }
+
+        // Check through all collisions with obstacles
+        // End game if crash occurs
+        // collect points if ideal collision occurs
+        for o in self.all_obstacles.iter_mut() {
+            if let Some(contact) = Physics::check_collision(&mut self.player, o) {
+                if self.player.collide_obstacle(o, contact) {
+                    if !self.game_over {
+                        self.events.push(GameEvent::Crash);
+                    }
+                    self.game_over = true;
+                }
+
+                if o.collected() && self.point_timer == 0 {
+                    //check if points need to be collected for obstacle interaction
+                    curr_step_score += o.value() as f64;
+                    self.last_point_val = o.value();
+                    self.point_timer = POINT_DURATION;
+                }
+            }
+        }
+
+        // Check for coin collection
+        // Add to score if collected
+        // Remove coins if player collects them
+        let mut to_remove_ind: i32 = -1;
+        let mut counter = 0;
+        for c in self.all_coins.iter_mut() {
+            if Physics::check_collision(&mut self.player, c).is_some() {
+                if self.player.collide_coin(c) {
+                    to_remove_ind = counter;
+                    self.coins_collected += 1;
+                    self.events.push(GameEvent::Coin);
+                    curr_step_score += c.value() as f64; //increments the
+                                                         // score based on the
+                                                         // coins value
+
+                    self.last_point_val = c.value();
+                    self.point_timer = POINT_DURATION; // Time to show last_coin_val on
+                                                       // screen
+                }
+                continue;
+            }
+            counter += 1;
+        }
+        if to_remove_ind != -1 {
+            self.all_coins.remove(to_remove_ind as usize);
+        }
+
+        // Check for powerup pickups
+        // Apply to player and begin countdown if picked up
+        let mut to_remove_ind: i32 = -1;
+        let mut counter = 0;
+        for p in self.all_powers.iter_mut() {
+            if Physics::check_collision(&mut self.player, p).is_some() {
+                if self.player.collide_power(p) {
+                    to_remove_ind = counter;
+                    self.power_timer = POWER_DURATION;
+                    self.events.push(GameEvent::PowerUp);
+                }
+                continue;
+            }
+            counter += 1;
+        }
+        if to_remove_ind != -1 {
+            self.all_powers.remove(to_remove_ind as usize);
+        }
+
+        if self.game_over && !was_game_over {
+            self.events.push(GameEvent::GameOver);
+        }
+
+        /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+
+        /* ~~~~~~ Handle Forces from Physics and move sprites ~~~~~~ */
+
+        // Apply forces on player
+        let current_power = self.player.power_up();
+
+        if self.player.is_grinding() {
+            self.grind_along(angle, curr_ground_point, terrain_type);
+        } else {
+            Physics::apply_terrain_forces(
+                // Gravity, normal, and friction
+                &mut self.player,
+                angle,
+                curr_ground_point,
+                curr_terrain_type,
+                current_power,
+            );
+            if on_water {
+                Physics::apply_buoyancy(&mut self.player, curr_ground_point);
+            }
+            if !self.game_over {
+                // Propel forward
+                Physics::apply_skate_force(
+                    &mut self.player,
+                    angle,
+                    curr_ground_point,
+                    curr_terrain_type,
+                );
+            }
+            let wind = self.biome().wind();
+            Physics::apply_wind(&mut self.player, wind);
+            //update player attributes
+            self.player.update_vel(self.game_over);
+
+            // Land on the ground rather than in it, coming up out of water
+            // onto it as soon as there's solid ground under the middle
+            let middle_x = PLAYER_X + (TILE_SIZE as i32) / 2;
+            let middle_on_water = get_ground_type(&self.all_terrain, middle_x)
+                .physics()
+                .floats;
+            if !on_water || !middle_on_water {
+                let travel = self.player.vel_x();
+                let ground = ground_under(&self.all_terrain, middle_x, travel as i32);
+                Physics::stop_at_ground(&mut self.player, travel.trunc(), &ground);
+            }
+
+            self.player
+                .update_pos(curr_ground_point, angle, on_water, self.game_over);
+
+            // Anything that still leaves the player under solid ground, or
+            // off the bottom of the screen, is a fall they can't come back from
+            if !self.game_over && buried(&self.player, curr_ground, middle_on_water, CAM_H as i32) {
+                self.events.push(GameEvent::Fall);
+                self.events.push(GameEvent::GameOver);
+                self.game_over = true;
+            }
+
+            // Coming down onto a rail while holding grind puts the board on it
+            if self.grind_held && !self.game_over {
+                self.catch_rail(angle);
+            }
+        }
+
+        // Splash when first touching down in water
+        let in_water = on_water && !self.player.is_jumping();
+        if in_water && !self.in_water {
+            self.events.push(GameEvent::Splash);
+        }
+        self.in_water = in_water;
+
+        self.player.flip(self.game_over);
+        if !self.game_over {
+            curr_step_score += self.track_tricks(angle);
+        }
+
+        self.player.reset_accel();
+
+        // Obstacles, knocked or not, move under the same physics
+        World::new(&self.all_terrain).step(&mut self.all_obstacles, self.game_over);
+
+        /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+
+        // Generate new objects if player hasn't died
+        if !self.game_over {
+            self.spawn_set_piece_objects();
+            self.spawn_objects();
+        }
+
+        // Update total_score
+        if !self.game_over {
+            curr_step_score += self.player.vel_x() / 5.0; // Increase score by factor of ammount moved that frame
+            if let Some(PowerType::ScoreMultiplier) = self.player.power_up() {
+                if self.point_timer == POINT_DURATION {
+                    curr_step_score *= 2.0; // Hardcoded power bonus
+                    self.last_point_val *= 2;
+                }
+            }
+            self.total_score += curr_step_score as i32;
+        }
+
+        /* Update ground / object positions to move player forward
+         * by the distance they should move this single tick
+         */
+        let travel_update = self.player.vel_x();
+        if !self.game_over {
+            self.distance += travel_update;
+        }
+        for ground in self.all_terrain.iter_mut() {
+            ground.travel_update(travel_update as i32);
+        }
+        for obs in self.all_obstacles.iter_mut() {
+            obs.travel_update(travel_update as i32);
+        }
+        for coin in self.all_coins.iter_mut() {
+            coin.travel_update(travel_update as i32);
+        }
+        for power_up in self.all_powers.iter_mut() {
+            power_up.travel_update(travel_update as i32);
+        }
+
+        // Generate new ground when the last segment becomes visible. It's
+        // as hard as the run will be when it comes into view, so the same
+        // seed always builds the same terrain however the player plays
+        let last_seg = self.all_terrain.last().unwrap();
+        if last_seg.x() < CAM_W as i32 {
+            let level = self
+                .difficulty
+                .at((self.terrain_end - CAM_W as f64) / TILE_SIZE as f64);
+            // Move on to the next biome once this one has run out, starting
+            // with a transition segment
+            let transition_from = if self.biome_segments_left == 0 {
+                let prev_biome = self.biome;
+                self.biome = prev_biome.next(&mut self.terrain_rng);
+                let (min_len, max_len) = self.biome.length();
+                self.biome_segments_left = self.terrain_rng.gen_range(min_len..=max_len);
+                Some(prev_biome)
+            } else {
+                None
+            };
+            self.biome_segments_left -= 1;
+
+            let is_flat = self.terrain_rng.gen_range(0..100) < 5;
+            let feature_roll = self.terrain_rng.gen_range(0..100);
+
+            // Never two set pieces in a row, or one as a transition
+            let set_piece = if self.terrain_rng.gen_range(0..100) < SET_PIECE_CHANCE
+                && transition_from.is_none()
+                && !last_seg.is_set_piece()
+            {
+                proceduralgen::choose_set_piece(&self.set_pieces, self.biome, &mut self.terrain_rng)
+            } else {
+                None
+            };
+
+            let new_terrain = match set_piece {
+                Some(piece) => proceduralgen::gen_set_piece(last_seg, piece, self.biome),
+                None => proceduralgen::ProceduralGen::gen_terrain(
+                    &self.noise,
+                    last_seg,
+                    is_flat,
+                    feature_roll < PIT_CHANCE,
+                    (PIT_CHANCE..PIT_CHANCE + CLIFF_CHANCE).contains(&feature_roll),
+                    self.biome,
+                    transition_from,
+                    &level,
+                    self.terrain_view_y,
+                    &mut self.terrain_rng,
+                ),
+            };
+            self.terrain_end += new_terrain.w() as f64;
+            self.terrain_view_y = camera_after(&new_terrain, self.terrain_view_y);
+            self.all_terrain.push(new_terrain);
+        }
+
+        /* ~~~~~~ Begin Camera Section ~~~~~~ */
+        /* This should be the very last section of calcultions,
+         * as the camera position relies upon updated math for
+         * EVERYTHING ELSE. Below the camera section we only have
+         * removal of offscreen objects from their vectors.
+         */
+
+        // Adjust camera vertically based on y/height of the ground
+        // Over a pit it holds still so the player can be seen falling
+        let camera_adj_y = match curr_ground {
+            Some(ground) if ground.y() < TERRAIN_UPPER_BOUND => TERRAIN_UPPER_BOUND - ground.y(),
+            Some(ground) if ground.y() + TILE_SIZE as i32 > TERRAIN_LOWER_BOUND => {
+                TERRAIN_LOWER_BOUND - ground.y()
+            }
+            _ => 0,
+        }
+        .clamp(-MAX_CAMERA_STEP, MAX_CAMERA_STEP);
+
+        for ground in self.all_terrain.iter_mut() {
+            ground.camera_adj(0, camera_adj_y);
+        }
+        for obs in self.all_obstacles.iter_mut() {
+            obs.camera_adj(0, camera_adj_y);
+        }
+        for coin in self.all_coins.iter_mut() {
+            coin.camera_adj(0, camera_adj_y);
+        }
+        for power_up in self.all_powers.iter_mut() {
+            power_up.camera_adj(0, camera_adj_y);
+        }
+        self.player.camera_adj(0, camera_adj_y);
+        self.scroll = (travel_update as i32, camera_adj_y);
+        /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+
+        /* ~~~~~~ Remove stuff which is now offscreen ~~~~~~ */
+        self.all_terrain
+            .retain(|ground| ground.x() + ground.w() > -(CAM_W as i32));
+        self.all_obstacles.retain(|obs| {
+            obs.x() + obs.hitbox().width() as i32 > -(TILE_SIZE as i32)
+                && obs.x() < (CAM_W as f64 * 1.5) as i32
+                && obs.y() < CAM_H as i32
+        });
+        self.all_coins.retain(|coin| {
+            coin.x() + TILE_SIZE as i32 > -(TILE_SIZE as i32) && coin.y() < CAM_H as i32
+        });
+        self.all_powers.retain(|power| {
+            power.x() + TILE_SIZE as i32 > -(TILE_SIZE as i32) && power.y() < CAM_H as i32
+        });
+        /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+
+        if self.point_timer > 0 {
+            self.point_timer -= 1;
+        }
+    }
+
+    // Picks and spawns a new object at the right edge of the screen once
+    // enough distance has been covered since the last one
+    fn spawn_objects(&mut self) {
+        // Value spawn_timer is reset to upon spawning an object.
+        // Decreases to increase spawn rates the further the run goes
+        let level = self.level();
+        let min_spawn_gap = level.spawn_gap;
+
+        // Choose new object to generate
+        let mut new_object: Option<StaticObject> = None;
+        let curr_num_objects =
+            self.all_obstacles.len() + self.all_coins.len() + self.all_powers.len();
+        let spawn_trigger = self.object_rng.gen_range(0..MAX_NUM_OBJECTS);
+
+        if self.spawn_timer > 0.0 {
+            self.spawn_timer -= self.player.vel_x() / 2.5;
+        } else if is_set_piece(&self.all_terrain, CAM_W as i32 - 1) {
+            // Set pieces bring their own objects, random ones start again
+            // a gap after them
+            self.spawn_timer = min_spawn_gap;
+        } else if spawn_trigger >= curr_num_objects as i32 {
+            let biome = get_biome(&self.all_terrain, (CAM_W as i32) - 1);
+            new_object = Some(proceduralgen::choose_static_object(
+                biome,
+                &level,
+                &mut self.object_rng,
+            ));
+            self.spawn_timer = min_spawn_gap;
+        } else {
+            // Min spawn gap can be replaced with basically any value for this random
+            // range. Smaller values will spawn objects more often
+            self.spawn_timer = self.object_rng.gen_range(0.0..min_spawn_gap);
+        }
+
+        // Nothing spawns over a pit
+        let spawn_coord: Point = match get_ground_coord(&self.all_terrain, (CAM_W as i32) - 1) {
+            Some(point) => point,
+            None => return,
+        };
+
+        match new_object {
+            // Coins sometimes come in a formation, more often later on
+            Some(StaticObject::Coin)
+                if self
+                    .object_rng
+                    .gen_bool(level.formation_chance.clamp(0.0, 1.0)) =>
+            {
+                let formation = Formation::choose(&mut self.object_rng);
+                let objects =
+                    formation.lay_out(&self.all_terrain, spawn_coord.x(), self.player.vel_x());
+                for (object, bottom) in objects {
+                    self.spawn_object(object, bottom);
+                }
+            }
+            Some(object) => self.spawn_object(object, spawn_coord),
+            None => {}
+        }
+    }
+
+    // Spawns the objects of set pieces as they reach the right edge of the
+    // screen, where they were placed by hand
+    fn spawn_set_piece_objects(&mut self) {
+        let mut due = Vec::new();
+        for ground in self.all_terrain.iter_mut() {
+            due.extend(ground.take_placed_objects(CAM_W as i32 - 1));
+        }
+        for (object, (x, y)) in due {
+            self.spawn_object(object, Point::new(x, y));
+        }
+    }
+
+    // Params: object to spawn, point its bottom middle goes at
+    fn spawn_object(&mut self, object: StaticObject, spawn_coord: Point) {
+        // Don't spawn certain objects on water
+        let mut on_water = false;
+        if let TerrainType::Water = get_ground_type(&self.all_terrain, spawn_coord.x()) {
+            on_water = true;
+        }
+
+        // Spawn new object
+        match object {
+            StaticObject::Statue if !on_water => {
+                let mut obstacle = Obstacle::new(
+                    rect!(
+                        // Adjust x coordinate so that center of object is on ground
+                        spawn_coord.x - TILE_SIZE as i32 / 2,
+                        // Adjust y coordinate so that bottom of object is on ground
+                        spawn_coord.y - TILE_SIZE as i32,
+                        TILE_SIZE,
+                        TILE_SIZE
+                    ),
+                    35.0, // mass
+                    0,    // value
+                    ObstacleType::Statue,
+                );
+                World::new(&self.all_terrain).settle(&mut obstacle);
+                // Left out if it can't be jumped along with what's before it
+                if self.can_clear_obstacle(&obstacle) {
+                    self.all_obstacles.push(obstacle);
+                }
+            }
+            StaticObject::Balloon => {
+                let mut obstacle = Obstacle::new(
+                    rect!(
+                        spawn_coord.x - TILE_SIZE as i32 / 2,
+                        spawn_coord.y - TILE_SIZE as i32,
+                        TILE_SIZE,
+                        TILE_SIZE
+                    ),
+                    1.0,
+                    100, //value
+                    ObstacleType::Balloon,
+                );
+                World::new(&self.all_terrain).settle(&mut obstacle);
+                self.all_obstacles.push(obstacle);
+            }
+            StaticObject::Chest if !on_water => {
+                let mut obstacle = Obstacle::new(
+                    rect!(
+                        spawn_coord.x - TILE_SIZE as i32 / 2,
+                        spawn_coord.y - TILE_SIZE as i32,
+                        TILE_SIZE,
+                        TILE_SIZE
+                    ),
+                    35.0,
+                    200, // value
+                    ObstacleType::Chest,
+                );
+                World::new(&self.all_terrain).settle(&mut obstacle);
+                if self.can_clear_obstacle(&obstacle) {
+                    self.all_obstacles.push(obstacle);
+                }
+            }
+            StaticObject::Bench if !on_water => {
+                let mut obstacle = Obstacle::new(
+                    rect!(
+                        spawn_coord.x - TILE_SIZE as i32 / 2,
+                        spawn_coord.y - TILE_SIZE as i32 * 2 / 3,
+                        TILE_SIZE,
+                        TILE_SIZE * 2 / 3
+                    ),
+                    35.0,
+                    200, // value
+                    ObstacleType::Bench,
+                );
+                World::new(&self.all_terrain).settle(&mut obstacle);
+                if self.can_clear_obstacle(&obstacle) {
+                    self.all_obstacles.push(obstacle);
+                }
+            }
+            StaticObject::Coin => {
+                let coin = Coin::new(
+                    rect!(
+                        spawn_coord.x - TILE_SIZE as i32 / 2,
+                        spawn_coord.y - TILE_SIZE as i32,
+                        TILE_SIZE,
+                        TILE_SIZE
+                    ),
+                    1000, // value
+                );
+                self.all_coins.push(coin);
+            }
+            // Rails follow the ground, so they need ground under all of them
+            StaticObject::Rail if !on_water => {
+                let kinds = get_biome(&self.all_terrain, spawn_coord.x()).rails();
+                let kind = kinds[self.object_rng.gen_range(0..kinds.len())];
+                let rail = match kind.rail() {
+                    Some(rail) => rail,
+                    None => return,
+                };
+                let left_x = spawn_coord.x - TILE_SIZE as i32 / 2;
+                if grind::fits(&self.all_terrain, left_x, rail) {
+                    // Anchored, so never settled or knocked about
+                    self.all_obstacles.push(Obstacle::new(
+                        rect!(left_x, spawn_coord.y - rail.height, rail.width, rail.height),
+                        1.0,
+                        0,
+                        kind,
+                    ));
+                }
+            }
+            StaticObject::Power => {
+                let pow = Power::new(
+                    rect!(
+                        spawn_coord.x - TILE_SIZE as i32 / 2,
+                        spawn_coord.y - TILE_SIZE as i32,
+                        TILE_SIZE,
+                        TILE_SIZE
+                    ),
+                    proceduralgen::choose_power_up(&mut self.power_rng),
+                );
+                self.all_powers.push(pow);
+            }
+            // ... Add any new types of objects here ...
+            _ => {}
+        }
+    }
+
+    /*  Carries the player along the rail they're grinding for a tick, and
+     *  takes them off it if they lose their balance or run out of rail
+     *
+     *  - Takes in `angle` which is the slope of the ground under the player,
+     *    which the rail follows
+     *  - Takes in `ground` which is the point on the ground under them
+     *  - Takes in `terrain_type` which is the type of that ground
+     */
+    fn grind_along(&mut self, angle: f64, ground: Point, terrain_type: TerrainType) {
+        if self.game_over {
+            self.player.leave_rail(self.player.theta());
+            self.grind = None;
+            return;
+        }
+        let wind = self.biome().wind();
+        let grind = match self.grind.as_mut() {
+            Some(grind) => grind,
+            None => return,
+        };
+
+        Physics::apply_grind_forces(
+            &mut self.player,
+            angle,
+            &terrain_type,
+            grind.rail().friction,
+        );
+        Physics::apply_wind(&mut self.player, wind);
+        self.player.update_vel(false);
+        // The rail holds them at its height, the world moves it past them
+        self.player.hard_set_vel((self.player.vel_x(), 0.0));
+        self.player.update_pos(ground, angle, false, false);
+
+        if grind.tip(angle) {
+            // Thrown off whichever way they tipped, which isn't a flip
+            self.player.leave_rail(grind.bail_theta());
+            self.tricks.ride(self.player.theta());
+            self.events.push(GameEvent::Bail);
+            self.grind = None;
+            return;
+        }
+
+        // Stay on the rail where it'll be once the world has moved
+        let feet = self.player.hitbox().bottom();
+        let ahead_x = PLAYER_X + TILE_SIZE as i32 / 2 + self.player.vel_x() as i32;
+        match grind::rail_between(
+            &self.all_obstacles,
+            &self.all_terrain,
+            ahead_x,
+            feet - RAIL_REACH,
+            feet + RAIL_REACH,
+        ) {
+            Some((_, point)) => self.player.ride_rail(point, grind.theta()),
+            None => {
+                // Ran off the end
+                self.player.leave_rail(self.player.theta());
+                self.finish_grind();
+            }
+        }
+    }
+
+    // Puts the player's board on a rail if they've just come down onto one
+    // with their feet towards it
+    // Params: slope of the ground under the player, which rails follow
+    // Returns: none
+    fn catch_rail(&mut self, angle: f64) {
+        if !self.player.is_jumping() || self.player.vel_y() > 0.0 {
+            return;
+        }
+        if !Physics::feet_down(self.player.theta(), collision::up(angle)) {
+            return;
+        }
+
+        // Anywhere their feet passed through this tick
+        let feet = self.player.hitbox().bottom();
+        let fell = self.player.moved().1.max(0.0) as i32;
+        if let Some((rail, point)) = grind::rail_between(
+            &self.all_obstacles,
+            &self.all_terrain,
+            PLAYER_X + TILE_SIZE as i32 / 2,
+            feet - fell,
+            feet,
+        ) {
+            let grind = Grind::new(rail, angle, self.player.theta());
+            self.player.ride_rail(point, grind.theta());
+            self.grind = Some(grind);
+            self.events.push(GameEvent::Grind);
+        }
+    }
+
+    // Ends the player's grind once they're off the rail on their feet,
+    // adding it to their combo
+    // Params: none
+    // Returns: none
+    fn finish_grind(&mut self) {
+        if let Some(grind) = self.grind.take() {
+            if self
+                .tricks
+                .grind(grind.rail().name, grind.score())
+                .is_some()
+            {
+                self.events.push(GameEvent::Trick);
+            }
+        }
+    }
+
+    /*  Picks out tricks from what the player did this tick, and banks their
+     *  combo once they're back on the ground
+     *
+     *  - Takes in `angle` which is the slope of the ground under the player
+     *
+     *  - Returns points banked this tick
+     */
+    fn track_tricks(&mut self, angle: f64) -> f64 {
+        let theta = self.player.theta();
+        let trick = if self.player.is_grinding() {
+            self.tricks.ride(theta);
+            None
+        } else if self.player.is_jumping() {
+            self.tricks.air(theta, self.grab_held)
+        } else if self.grind_held && angle.abs() < MANUAL_SLOPE {
+            // Grind held on flat ground rides a manual instead
+            self.tricks.manual(theta)
+        } else {
+            return self.bank_combo(theta);
+        };
+
+        match trick {
+            Some(Trick::Flip { .. }) => self.events.push(GameEvent::Flip),
+            Some(_) => self.events.push(GameEvent::Trick),
+            None => {}
+        }
+        0.0
+    }
+
+    // Ends the player's combo now they've landed, showing its points
+    // Params: player's angle
+    // Returns: points for the combo
+    fn bank_combo(&mut self, theta: f64) -> f64 {
+        let combo = match self.tricks.land(theta) {
+            Some(combo) => combo,
+            None => return 0.0,
+        };
+        let points = combo.total();
+        self.last_point_val = points;
+        self.point_timer = POINT_DURATION;
+        self.last_combo = Some(combo);
+        self.events.push(GameEvent::Combo);
+        points as f64
+    }
+
+    // Checks a new obstacle can be jumped, along with any others already on
+    // the ground just before it
+    // Params: the new obstacle, settled on the ground
+    // Returns: true if it's safe to spawn
+    fn can_clear_obstacle(&self, new: &Obstacle) -> bool {
+        // Start somewhere with ground under it, far enough back to jump from
+        let mut start_x = (new.x() - OBSTACLE_RUN_UP).max(PLAYER_X);
+        while get_ground_coord(&self.all_terrain, start_x + TILE_SIZE as i32 / 2).is_none() {
+            start_x += TILE_SIZE as i32 / 10;
+            if start_x + TILE_SIZE as i32 > new.x() {
+                return false;
+            }
+        }
+
+        // Balloons and rails can be run through and knocked obstacles are
+        // moving, so only standing solid ones are in the way
+        let mut in_way: Vec<Shape> = self
+            .all_obstacles
+            .iter()
+            .filter(|o| !o.collided() && !matches!(o.obstacle_type(), ObstacleType::Balloon))
+            .filter(|o| o.obstacle_type().rail().is_none())
+            .filter(|o| o.x() >= start_x + TILE_SIZE as i32)
+            .map(|o| o.shape())
+            .collect();
+        in_way.push(new.shape());
+
+        reachability::can_clear(
+            &self.all_terrain,
+            &in_way,
+            start_x,
+            new.x() + new.hitbox().width() as i32 + TILE_SIZE as i32 / 2,
+            self.player.vel_x(),
+        )
+    }
+}
+
+/* ~~~~~~ Helper Functions ~~~~~ */
+
+// Given the current terrain and an x coordinate of the screen,
+// returns the (x, y) of the ground at that x, or None over a pit or
+// anywhere there's no terrain
+pub fn get_ground_coord(all_terrain: &[TerrainSegment], screen_x: i32) -> Option<Point> {
+    // Loop backwards
+    for ground in all_terrain.iter().rev() {
+        // The first segment starting at or behind
+        // the given x, which it must be above
+        if ground.x() <= screen_x {
+            let point_ind: usize = (screen_x - ground.x()) as usize;
+            return ground.ground_at(point_ind).map(|(x, y)| Point::new(x, y));
+        }
+    }
+    None
+}
+
+// Where the camera settles vertically once the player has run the length of
+// a segment, following the ground the same way a tick does but all at once
+// Params: segment, view y before it
+// Returns: view y after it
+fn camera_after(segment: &TerrainSegment, view_y: i32) -> i32 {
+    (0..segment.w() as usize).fold(view_y, |view_y, i| {
+        let ground_y = segment.ground_y(i) - segment.view().1 + view_y;
+        if ground_y < TERRAIN_UPPER_BOUND {
+            view_y + TERRAIN_UPPER_BOUND - ground_y
+        } else if ground_y + TILE_SIZE as i32 > TERRAIN_LOWER_BOUND {
+            view_y + TERRAIN_LOWER_BOUND - ground_y
+        } else {
+            view_y
+        }
+    })
+}
+
+// Ground point for physics to use, over a pit one so far down nothing
+// touches it and bodies just fall
+pub(crate) fn ground_or_pit(ground: Option<Point>, screen_x: i32) -> Point {
+    ground.unwrap_or_else(|| Point::new(screen_x, PIT_FLOOR_Y))
+}
+
+// Ground the middle of something passes over as it moves, as a line it can
+// be stopped at. Broken over pits, where there's nothing to stop it
+// Params: terrain, screen x of the middle, how far it's moving right
+// Returns: the ground as a shape
+pub(crate) fn ground_under(all_terrain: &[TerrainSegment], middle_x: i32, across: i32) -> Shape {
+    let from = middle_x.min(middle_x + across) - 1;
+    let to = middle_x.max(middle_x + across) + 1;
+    let points = (from..=to)
+        .map(|x| get_ground_coord(all_terrain, x).map(|g| (g.x() as f64, g.y() as f64)))
+        .collect::<Vec<_>>();
+    Shape::polyline(&points)
+}
+
+// Slope of the ground under something TILE_SIZE wide, from the ground under
+// its left, middle and right. If an end is over a pit, the middle is used
+// in its place. The same goes for an end past the edge of a cliff, where
+// the ground it's still on is the flatter half
+pub(crate) fn ground_angle(
+    left: Option<Point>,
+    middle: Option<Point>,
+    right: Option<Point>,
+) -> f64 {
+    let (from, to) = match (left, middle, right) {
+        (Some(l), Some(m), Some(r)) => {
+            let (behind, ahead) = (m.y() - l.y(), r.y() - m.y());
+            if (ahead - behind).abs() <= CLIFF_EDGE {
+                (l, r)
+            } else if behind.abs() < ahead.abs() {
+                (l, m)
+            } else {
+                (m, r)
+            }
+        }
+        (Some(l), None, Some(r)) => (l, r),
+        (Some(l), Some(m), None) => (l, m),
+        (None, Some(m), Some(r)) => (m, r),
+        _ => return 0.0,
+    };
+    ((to.y() - from.y()) as f64 / (to.x() - from.x()) as f64).atan()
+}
+
+// Given the current terrain and an x coordinate of the screen,
+// returns the type of the ground at that x
+pub fn get_ground_type(all_terrain: &[TerrainSegment], screen_x: i32) -> &TerrainType {
+    // Loop backwards
+    for ground in all_terrain.iter().rev() {
+        // The first segment starting at or behind
+        // the given x, which it must be above
+        if ground.x() <= screen_x {
+            return ground.get_type();
+        }
+    }
+    &TerrainType::Grass //default to grass
+}
+
+// Given the current terrain and an x coordinate of the screen,
+// returns the biome of the ground at that x
+pub fn get_biome(all_terrain: &[TerrainSegment], screen_x: i32) -> Biome {
+    for ground in all_terrain.iter().rev() {
+        if ground.x() <= screen_x {
+            return ground.biome();
+        }
+    }
+    Biome::Park
+}
+
+// Given the current terrain and an x coordinate of the screen, returns
+// whether the ground at that x is a set piece
+pub fn is_set_piece(all_terrain: &[TerrainSegment], screen_x: i32) -> bool {
+    for ground in all_terrain.iter().rev() {
+        if ground.x() <= screen_x {
+            return ground.is_set_piece();
+        }
+    }
+    false
+}
+
+// Checks whether a player has ended up somewhere they can't get out of
+// Params: player, ground under their middle, whether it's water, y of the
+//         bottom of the screen
+// Returns: true if the whole hitbox is under solid ground, or it's fallen
+//          off the bottom of the screen
+pub(crate) fn buried(
+    player: &Player,
+    ground: Option<Point>,
+    on_water: bool,
+    bottom_y: i32,
+) -> bool {
+    let under_ground = match ground {
+        Some(ground) => !on_water && player.y() > ground.y(),
+        None => false,
+    };
+    under_ground || player.y() > bottom_y
+}
+
+// Checks whether a player over a pit can still make it out
+// Params: player, ground under their middle and right, y of the bottom of
+//         the screen
+// Returns: true once they've dropped below the far edge and hit the wall, or
+//          fallen off the bottom of the screen
+pub(crate) fn fell_into_pit(
+    player: &Player,
+    middle: Option<Point>,
+    right: Option<Point>,
+    bottom_y: i32,
+) -> bool {
+    if middle.is_some() {
+        return false;
+    }
+    let hit_wall = match right {
+        Some(rim) => player.y() + TILE_SIZE as i32 > rim.y() + PIT_LIP,
+        None => false,
+    };
+    hit_wall || player.y() > bottom_y
+}
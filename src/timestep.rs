@@ -0,0 +1,196 @@
+// Fixed-timestep scheduling shared by every screen
+//
+// Game logic always advances in ticks of exactly 1 / TICK_RATE seconds, no
+// matter how fast frames are rendered. Each frame, the real time that has
+// passed is added to an accumulator and as many whole ticks as fit are run;
+// whatever is left over becomes the interpolation factor used to draw objects
+// between their last two tick positions.
+
+use std::fmt;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+// Logic ticks per second, all per-tick physics constants assume this rate
+pub const TICK_RATE: f64 = 60.0;
+
+// Default limit on rendered frames per second, keeps the loop from spinning
+// when vsync isn't available
+pub const DEFAULT_FRAME_CAP: f64 = 240.0;
+
+// Most real time that can be waiting to be simulated. Anything past this (a
+// breakpoint, dragging the window, a screen that never ticks) is dropped
+// instead of being simulated all at once
+const MAX_FRAME_TIME: f64 = 0.25;
+
+pub struct FixedTimestep {
+    tick_time: f64,
+    frame_cap: Option<f64>,
+
+    accumulator: f64,
+    last_frame: Instant,
+    frame_start: Instant,
+
+    stats: FrameStats,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f64) -> FixedTimestep {
+        let now = Instant::now();
+        FixedTimestep {
+            tick_time: 1.0 / tick_rate,
+            frame_cap: Some(DEFAULT_FRAME_CAP),
+
+            accumulator: 0.0,
+            last_frame: now,
+            frame_start: now,
+
+            stats: FrameStats::new(now),
+        }
+    }
+
+    // Limits rendered frames per second, None to render as fast as possible
+    pub fn set_frame_cap(&mut self, frame_cap: Option<f64>) {
+        self.frame_cap = frame_cap;
+    }
+
+    // Length of a single tick in seconds
+    pub fn tick_time(&self) -> f64 {
+        self.tick_time
+    }
+
+    // Starts a new frame, adding the real time since the last one to the
+    // time waiting to be simulated
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_frame).as_secs_f64();
+        self.accumulator = (self.accumulator + elapsed).min(MAX_FRAME_TIME);
+        self.last_frame = now;
+        self.frame_start = now;
+    }
+
+    // Returns true if another tick should be run this frame, consuming it.
+    // Meant to be used as `while timestep.tick() { ... }`
+    pub fn tick(&mut self) -> bool {
+        if self.accumulator >= self.tick_time {
+            self.accumulator -= self.tick_time;
+            self.stats.ticks += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // How far the current frame is between the last tick and the next one,
+    // from 0.0 to 1.0. Used to blend positions when drawing
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.tick_time).clamp(0.0, 1.0)
+    }
+
+    // Throws away time waiting to be simulated, e.g. after being paused, so
+    // the game doesn't race to catch up
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.last_frame = Instant::now();
+    }
+
+    // Ends the frame, sleeping off whatever is left of the frame cap
+    pub fn end_frame(&mut self) {
+        let work_time = self.frame_start.elapsed().as_secs_f64();
+        self.stats.record_frame(work_time);
+
+        if let Some(cap) = self.frame_cap {
+            let delay = 1.0 / cap - work_time;
+            // If the frame took less time than allowed, sleep until the
+            // expected amount of time has passed
+            if delay > 0.0 {
+                // Using sleep to delay will always cause slightly more delay than intended due
+                // to CPU scheduling; possibly find a better way to delay
+                sleep(Duration::from_secs_f64(delay));
+            }
+        }
+    }
+
+    // Returns the frame budget report once per second, None otherwise
+    pub fn report(&mut self) -> Option<FrameReport> {
+        let budget = match self.frame_cap {
+            Some(cap) => 1.0 / cap,
+            None => self.tick_time,
+        };
+        self.stats.report(budget)
+    }
+}
+
+// Running totals used to build a FrameReport
+struct FrameStats {
+    since: Instant,
+    frames: u32,
+    ticks: u32,
+    total_work: f64,
+    worst_work: f64,
+}
+
+impl FrameStats {
+    fn new(since: Instant) -> FrameStats {
+        FrameStats {
+            since,
+            frames: 0,
+            ticks: 0,
+            total_work: 0.0,
+            worst_work: 0.0,
+        }
+    }
+
+    fn record_frame(&mut self, work_time: f64) {
+        self.frames += 1;
+        self.total_work += work_time;
+        self.worst_work = self.worst_work.max(work_time);
+    }
+
+    fn report(&mut self, budget: f64) -> Option<FrameReport> {
+        let elapsed = self.since.elapsed().as_secs_f64();
+        if elapsed < 1.0 || self.frames == 0 {
+            return None;
+        }
+
+        let report = FrameReport {
+            fps: self.frames as f64 / elapsed,
+            tps: self.ticks as f64 / elapsed,
+            avg_frame_ms: self.total_work / self.frames as f64 * 1000.0,
+            worst_frame_ms: self.worst_work * 1000.0,
+            budget_ms: budget * 1000.0,
+        };
+        *self = FrameStats::new(Instant::now());
+        Some(report)
+    }
+}
+
+// How the last second of frames fit into the time allowed for each
+pub struct FrameReport {
+    pub fps: f64,            // Frames rendered per second
+    pub tps: f64,            // Logic ticks run per second, should be TICK_RATE
+    pub avg_frame_ms: f64,   // Average time spent working on a frame
+    pub worst_frame_ms: f64, // Longest time spent working on a frame
+    pub budget_ms: f64,      // Time allowed for a single frame
+}
+
+impl FrameReport {
+    // Fraction of the frame budget used by the average frame
+    pub fn budget_used(&self) -> f64 {
+        self.avg_frame_ms / self.budget_ms
+    }
+}
+
+impl fmt::Display for FrameReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} FPS, {:.1} TPS, frame avg {:.2}ms / worst {:.2}ms of {:.2}ms budget ({:.0}%)",
+            self.fps,
+            self.tps,
+            self.avg_frame_ms,
+            self.worst_frame_ms,
+            self.budget_ms,
+            self.budget_used() * 100.0
+        )
+    }
+}
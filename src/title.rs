@@ -1,21 +1,50 @@
-use crate::rect;
+use inf_runner::rect;
+
+use inf_runner::assets::Assets;
+
+use inf_runner::difficulty::Difficulty;
 
 use inf_runner::Game;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
 use inf_runner::SDLCore;
 
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use inf_runner::audio::MusicTrack;
+
+use inf_runner::input::Action;
+
+use inf_runner::utils::menu_row_at;
+
+use inf_runner::timestep::FixedTimestep;
+use inf_runner::timestep::TICK_RATE;
 
 use sdl2::event::Event;
-use sdl2::image::LoadTexture;
-use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 
-const FPS: f64 = 60.0;
-const FRAME_TIME: f64 = 1.0 / FPS as f64;
+const ORANGE: Color = Color::RGBA(230, 150, 25, 255);
+const WHITE: Color = Color::RGBA(255, 255, 255, 255);
+
+// Menu items, top to bottom
+const MENU: [&str; 8] = [
+    "Play",
+    "Difficulty",
+    "Instructions",
+    "High Scores",
+    "Settings",
+    "Controls",
+    "Credits",
+    "Quit game",
+];
+
+// Row that picks the difficulty instead of opening a screen
+const DIFFICULTY_ROW: usize = 1;
+
+// Position of the menu rows
+const MENU_TOP: i32 = 230;
+const MENU_SPACING: i32 = 58;
+const MENU_ROW_H: u32 = 50;
 
 pub struct Title;
 
@@ -24,15 +53,11 @@ impl Game for Title {
         Ok(Title {})
     }
 
-    fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String> {
+    fn run(&mut self, core: &mut SDLCore, assets: &Assets) -> Result<GameState, String> {
         core.wincan.set_blend_mode(sdl2::render::BlendMode::Blend);
 
         let texture_creator = core.wincan.texture_creator();
-
-        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
-
-        let mut font = ttf_context.load_font("./assets/DroidSansMono.ttf", 128)?;
-        font.set_style(sdl2::ttf::FontStyle::BOLD);
+        let font = assets.font("ui")?;
 
         let mut player_angle = 0.0;
 
@@ -44,65 +69,103 @@ impl Game for Title {
             .create_texture_from_surface(&surface)
             .map_err(|e| e.to_string())?;
 
-        let surface = font
-            .render("P/Space - Play")
-            .blended(Color::RGBA(230, 150, 25, 255))
-            .map_err(|e| e.to_string())?;
-        let play_texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
-
-        let surface = font
-            .render("I - Instructions")
-            .blended(Color::RGBA(230, 150, 25, 255))
-            .map_err(|e| e.to_string())?;
-        let instruction_texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
-
-        let surface = font
-            .render("C - Credits")
-            .blended(Color::RGBA(230, 150, 25, 255))
-            .map_err(|e| e.to_string())?;
-        let credits_texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
+        // Menu items in orange, plus a white copy for the selected one
+        let mut menu_textures = Vec::new();
+        for label in MENU.iter() {
+            let mut pair = Vec::new();
+            for color in [ORANGE, WHITE].iter() {
+                let surface = font
+                    .render(label)
+                    .blended(*color)
+                    .map_err(|e| e.to_string())?;
+                pair.push(
+                    texture_creator
+                        .create_texture_from_surface(&surface)
+                        .map_err(|e| e.to_string())?,
+                );
+            }
+            menu_textures.push(pair);
+        }
+        let mut selected = 0;
 
-        let surface = font
-            .render("Escape/Q - Quit game")
-            .blended(Color::RGBA(230, 150, 25, 255))
-            .map_err(|e| e.to_string())?;
-        let quit_texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
+        // Each difficulty in orange and white, shown after its menu item
+        let mut difficulty_textures = Vec::new();
+        for difficulty in Difficulty::ALL.iter() {
+            let label = format!("< {} >", difficulty);
+            let mut pair = Vec::new();
+            for color in [ORANGE, WHITE].iter() {
+                let surface = font
+                    .render(&label)
+                    .blended(*color)
+                    .map_err(|e| e.to_string())?;
+                pair.push(
+                    texture_creator
+                        .create_texture_from_surface(&surface)
+                        .map_err(|e| e.to_string())?,
+                );
+            }
+            difficulty_textures.push((pair, label.len()));
+        }
+        let start_difficulty = core.settings.difficulty;
 
-        let tex_player = texture_creator.load_texture("assets/player/player.png")?;
+        let tex_player = assets.texture("player/default")?;
 
         let next_status;
 
-        // FPS tracking
-        let mut _all_frames: i32 = 0;
-        let mut last_raw_time;
-        let mut last_measurement_time = Instant::now();
+        core.audio.play_music(MusicTrack::Title);
+
+        let mut timestep = FixedTimestep::new(TICK_RATE);
+        timestep.set_frame_cap(core.settings.frame_cap());
 
         'gameloop: loop {
-            last_raw_time = Instant::now(); // FPS tracking
+            timestep.begin_frame();
             core.wincan.set_draw_color(Color::RGBA(119, 120, 123, 255));
             core.wincan.clear();
 
             // Draw text
             core.wincan
                 .copy(&title_texture, None, Some(rect!(30, 40, 850, 200)))?;
-            core.wincan
-                .copy(&play_texture, None, Some(rect!(100, 230, 350, 100)))?;
-            core.wincan
-                .copy(&instruction_texture, None, Some(rect!(100, 340, 420, 100)))?;
-            core.wincan
-                .copy(&credits_texture, None, Some(rect!(100, 450, 300, 100)))?;
-            core.wincan
-                .copy(&quit_texture, None, Some(rect!(100, 560, 550, 100)))?;
+            for (i, label) in MENU.iter().enumerate() {
+                let tex_item = &menu_textures[i][(i == selected) as usize];
+                core.wincan.copy(
+                    tex_item,
+                    None,
+                    Some(rect!(
+                        100,
+                        MENU_TOP + i as i32 * MENU_SPACING,
+                        label.len() * 24,
+                        MENU_ROW_H
+                    )),
+                )?;
+            }
+            let difficulty = core.settings.difficulty;
+            let difficulty_ind = Difficulty::ALL
+                .iter()
+                .position(|d| *d == difficulty)
+                .unwrap_or(0);
+            let (tex_difficulty, label_len) = &difficulty_textures[difficulty_ind];
+            core.wincan.copy(
+                &tex_difficulty[(selected == DIFFICULTY_ROW) as usize],
+                None,
+                Some(rect!(
+                    130 + MENU[DIFFICULTY_ROW].len() * 24,
+                    MENU_TOP + DIFFICULTY_ROW as i32 * MENU_SPACING,
+                    label_len * 24,
+                    MENU_ROW_H
+                )),
+            )?;
+
+            // Marker next to the selected item
+            core.wincan.set_draw_color(WHITE);
+            core.wincan.fill_rect(rect!(
+                60,
+                MENU_TOP + 15 + selected as i32 * MENU_SPACING,
+                20,
+                20
+            ))?;
+
             core.wincan.copy_ex(
-                &tex_player,
+                tex_player,
                 rect!(0, 0, 250, 250),
                 rect!(900, 300, 250, 250),
                 -player_angle,
@@ -112,63 +175,84 @@ impl Game for Title {
             )?;
 
             core.wincan.present();
-            player_angle = (player_angle + 5.0) % 360.0;
 
-            for event in core.event_pump.poll_iter() {
+            // Spin the player at a steady rate however fast frames are drawn
+            while timestep.tick() {
+                player_angle = (player_angle + 5.0) % 360.0;
+            }
+
+            for event in core.poll_events() {
+                if let Event::Quit { .. } = event {
+                    next_status = None;
+                    break 'gameloop;
+                }
+                // Hovering the mouse over a row selects it, clicking confirms it
+                let mut clicked = false;
                 match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape | Keycode::Q),
+                    Event::MouseMotion { y, .. } => {
+                        if let Some(row) =
+                            menu_row_at(y, MENU_TOP, MENU_SPACING, MENU_ROW_H, MENU.len())
+                        {
+                            selected = row;
+                        }
+                    }
+                    Event::MouseButtonDown {
+                        mouse_btn: MouseButton::Left,
+                        y,
                         ..
                     } => {
-                        next_status = None;
-                        break 'gameloop;
+                        if let Some(row) =
+                            menu_row_at(y, MENU_TOP, MENU_SPACING, MENU_ROW_H, MENU.len())
+                        {
+                            selected = row;
+                            clicked = true;
+                        }
                     }
-                    Event::KeyDown {
-                        keycode: Some(k), ..
-                    } => match k {
-                        Keycode::P | Keycode::Space => {
-                            next_status = Some(GameStatus::Game);
-                            break 'gameloop;
+                    _ => {}
+                }
+                let mut actions = core.input.pressed_actions(&event);
+                if clicked {
+                    actions.push(Action::Confirm);
+                }
+
+                for action in actions {
+                    match action {
+                        Action::Up => {
+                            selected = (selected + MENU.len() - 1) % MENU.len();
+                        }
+                        Action::Down => {
+                            selected = (selected + 1) % MENU.len();
+                        }
+                        Action::Left if selected == DIFFICULTY_ROW => {
+                            core.settings.difficulty = core.settings.difficulty.cycle(-1);
+                        }
+                        Action::Right if selected == DIFFICULTY_ROW => {
+                            core.settings.difficulty = core.settings.difficulty.cycle(1);
+                        }
+                        Action::Confirm if selected == DIFFICULTY_ROW => {
+                            core.settings.difficulty = core.settings.difficulty.cycle(1);
                         }
-                        Keycode::C => {
-                            next_status = Some(GameStatus::Credits);
+                        Action::Confirm => {
+                            next_status = menu_status(selected);
                             break 'gameloop;
                         }
-                        Keycode::I => {
-                            next_status = Some(GameStatus::Instruct);
+                        Action::Back => {
+                            next_status = None;
                             break 'gameloop;
                         }
                         _ => {}
-                    },
-                    _ => {}
+                    }
                 }
             }
 
-            /* ~~~~~~ FPS Calculation ~~~~~~ */
-            // Time taken to display the last frame
-            let raw_frame_time = last_raw_time.elapsed().as_secs_f64();
-            let delay = FRAME_TIME - raw_frame_time;
-            // If the amount of time to display the last frame was less than expected, sleep
-            // until the expected amount of time has passed
-            if delay > 0.0 {
-                // Using sleep to delay will always cause slightly more delay than intended due
-                // to CPU scheduling; possibly find a better way to delay
-                sleep(Duration::from_secs_f64(delay));
-            }
-            _all_frames += 1;
-            let time_since_last_measurement = last_measurement_time.elapsed();
-            // Measures the FPS once per second
-            if time_since_last_measurement > Duration::from_secs(1) {
-                //println!("{} FPS", _all_frames);
-                // println!(
-                //     "Average FPS: {:.2}",
-                //     (_all_frames as f64) / time_since_last_measurement.as_secs_f64()
-                // );
-                _all_frames = 0;
-                last_measurement_time = Instant::now();
+            timestep.end_frame();
+        }
+
+        // Keep the chosen difficulty for next time
+        if core.settings.difficulty != start_difficulty {
+            if let Err(e) = core.save_settings() {
+                println!("\n\t\tFailed to save settings: {}", e);
             }
-            /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
         }
 
         // Out of game loop, return Ok
@@ -178,3 +262,16 @@ impl Game for Title {
         })
     }
 }
+
+// Screen each menu item leads to, None quits the game
+fn menu_status(item: usize) -> Option<GameStatus> {
+    match item {
+        0 => Some(GameStatus::Game),
+        2 => Some(GameStatus::Instruct),
+        3 => Some(GameStatus::HighScores),
+        4 => Some(GameStatus::Settings),
+        5 => Some(GameStatus::Controls),
+        6 => Some(GameStatus::Credits),
+        _ => None,
+    }
+}
@@ -0,0 +1,394 @@
+// Tricks the player can pull off, and chaining them into combos
+//
+// Tricks are picked out of what the player does tick by tick rather than
+// from input, so a flip carried on by momentum counts the same as one held
+// down. Rotation is added up as it happens, since theta wraps at a full turn
+// and can't tell a double flip from no flip at all.
+//
+// Everything done before the player is back on the ground, rails and manuals
+// included, goes into one combo. Its points are banked on landing, times a
+// multiplier that goes up with every trick in it, and lost if the landing
+// is a bad one.
+
+use crate::physics::MAX_UPRIGHT_ANGLE;
+
+use std::f64::consts::PI;
+
+// Points for a single flip. Each extra turn is worth one more of them than
+// the last, so a double is 1 + 2 of them
+const FLIP_POINTS: i32 = 100;
+// Ticks a grab has to be held to count, and points for each tick it's held
+const GRAB_MIN: u32 = 10;
+const GRAB_POINTS: i32 = 5;
+// Ticks a manual has to be held to count, and points for each tick it's held
+const MANUAL_MIN: u32 = 20;
+const MANUAL_POINTS: i32 = 2;
+// Steepest ground a manual can be held on
+pub const MANUAL_SLOPE: f64 = PI / 36.0;
+// Highest the multiplier goes
+const MAX_MULTIPLIER: i32 = 10;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Trick {
+    Flip { rotations: u32, forward: bool }, // Whole turns, forward is clockwise
+    Grab,                                   // Board held in the air
+    Manual,                                 // Rolled on the back wheels on flat ground
+    Grind(&'static str),                    // Rode a rail, by its name
+}
+
+impl Trick {
+    // Name shown on the HUD, e.g. "Double backflip"
+    pub fn name(&self) -> String {
+        match self {
+            Trick::Flip { rotations, forward } => {
+                let flip = if *forward { "frontflip" } else { "backflip" };
+                match rotations {
+                    1 => format!("{}{}", flip[..1].to_uppercase(), &flip[1..]),
+                    2 => format!("Double {}", flip),
+                    3 => format!("Triple {}", flip),
+                    _ => format!("{}x {}", rotations, flip),
+                }
+            }
+            Trick::Grab => String::from("Grab"),
+            Trick::Manual => String::from("Manual"),
+            Trick::Grind(rail) => format!("{} grind", rail),
+        }
+    }
+}
+
+// Tricks chained together without touching the ground
+#[derive(Clone, Debug, Default)]
+pub struct Combo {
+    tricks: Vec<(Trick, i32)>, // Each trick with its points, in order
+}
+
+impl Combo {
+    pub fn tricks(&self) -> impl Iterator<Item = Trick> + '_ {
+        self.tricks.iter().map(|(trick, _)| *trick)
+    }
+
+    // Points before the multiplier
+    pub fn points(&self) -> i32 {
+        self.tricks.iter().map(|(_, points)| points).sum()
+    }
+
+    // One for each trick in the combo
+    pub fn multiplier(&self) -> i32 {
+        (self.tricks.len() as i32).clamp(1, MAX_MULTIPLIER)
+    }
+
+    // Points the combo banks on landing
+    pub fn total(&self) -> i32 {
+        self.points() * self.multiplier()
+    }
+
+    // Every trick's name, e.g. "Backflip + Grab"
+    pub fn name(&self) -> String {
+        self.tricks()
+            .map(|trick| trick.name())
+            .collect::<Vec<String>>()
+            .join(" + ")
+    }
+
+    // Returns: where the trick went, to change it later
+    fn add(&mut self, trick: Trick, points: i32) -> usize {
+        self.tricks.push((trick, points));
+        self.tricks.len() - 1
+    }
+
+    // Changes a trick already in the combo, e.g. a flip going round again
+    fn set(&mut self, at: usize, trick: Trick, points: i32) {
+        self.tricks[at] = (trick, points);
+    }
+}
+
+// Keeps track of the player's combo and whatever tricks they're part way
+// through
+#[derive(Default)]
+pub struct Tricks {
+    combo: Option<Combo>,
+
+    last_theta: f64,        // Player's angle last tick
+    spin: f64,              // How far they've turned in the air, forward is positive
+    flip_at: Option<usize>, // Flip they're doing this time in the air, in the combo
+
+    grab_ticks: u32,
+    grab_at: Option<usize>,
+    manual_ticks: u32,
+    manual_at: Option<usize>,
+}
+
+impl Tricks {
+    pub fn new() -> Tricks {
+        Tricks::default()
+    }
+
+    // Combo in progress, if any
+    pub fn combo(&self) -> Option<&Combo> {
+        self.combo.as_ref()
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.manual_ticks > 0
+    }
+
+    /*  Tracks a tick in the air
+     *
+     *  - Takes in `theta` which is the player's angle
+     *  - Takes in `grabbing` which is whether grab is held
+     *
+     *  - Returns a trick if one was just done, or a flip went round again
+     */
+    pub fn air(&mut self, theta: f64, grabbing: bool) -> Option<Trick> {
+        self.end_manual();
+
+        // Short way round from last tick, theta wraps at a full turn
+        self.spin += (theta - self.last_theta + PI).rem_euclid(2.0 * PI) - PI;
+        self.last_theta = theta;
+
+        // Close enough to all the way round to land on their feet counts
+        let rotations = ((self.spin.abs() + MAX_UPRIGHT_ANGLE) / (2.0 * PI)) as u32;
+        let counted = match self
+            .flip_at
+            .and_then(|at| self.combo.as_ref()?.tricks.get(at))
+        {
+            Some((Trick::Flip { rotations, .. }, _)) => *rotations,
+            _ => 0,
+        };
+        if rotations > counted {
+            let trick = Trick::Flip {
+                rotations,
+                forward: self.spin > 0.0,
+            };
+            let points = FLIP_POINTS * (rotations * (rotations + 1) / 2) as i32;
+            let combo = self.combo.get_or_insert_with(Combo::default);
+            match self.flip_at {
+                Some(at) => combo.set(at, trick, points),
+                None => self.flip_at = Some(combo.add(trick, points)),
+            }
+            return Some(trick);
+        }
+
+        if !grabbing {
+            self.grab_ticks = 0;
+            self.grab_at = None;
+            return None;
+        }
+        self.grab_ticks += 1;
+        if self.grab_ticks < GRAB_MIN {
+            return None;
+        }
+        let points = self.grab_ticks as i32 * GRAB_POINTS;
+        let combo = self.combo.get_or_insert_with(Combo::default);
+        match self.grab_at {
+            Some(at) => {
+                combo.set(at, Trick::Grab, points);
+                None
+            }
+            None => {
+                self.grab_at = Some(combo.add(Trick::Grab, points));
+                Some(Trick::Grab)
+            }
+        }
+    }
+
+    /*  Tracks a tick in a manual, which keeps the combo going on the ground
+     *
+     *  - Takes in `theta` which is the player's angle
+     *
+     *  - Returns the manual once it's been held long enough to count
+     */
+    pub fn manual(&mut self, theta: f64) -> Option<Trick> {
+        self.end_air(theta);
+
+        self.manual_ticks += 1;
+        if self.manual_ticks < MANUAL_MIN {
+            return None;
+        }
+        let points = self.manual_ticks as i32 * MANUAL_POINTS;
+        let combo = self.combo.get_or_insert_with(Combo::default);
+        match self.manual_at {
+            Some(at) => {
+                combo.set(at, Trick::Manual, points);
+                None
+            }
+            None => {
+                self.manual_at = Some(combo.add(Trick::Manual, points));
+                Some(Trick::Manual)
+            }
+        }
+    }
+
+    // Tracks a tick on a rail, where nothing counts until the grind ends
+    // Params: player's angle
+    pub fn ride(&mut self, theta: f64) {
+        self.end_air(theta);
+        self.end_manual();
+    }
+
+    // Adds a grind that ended with the player on their feet
+    // Params: name of the rail, points for the grind
+    // Returns: the grind, None if it didn't last long enough to score
+    pub fn grind(&mut self, rail: &'static str, points: i32) -> Option<Trick> {
+        if points <= 0 {
+            return None;
+        }
+        let trick = Trick::Grind(rail);
+        self.combo
+            .get_or_insert_with(Combo::default)
+            .add(trick, points);
+        Some(trick)
+    }
+
+    // Ends the combo with the player back on the ground on their feet
+    // Params: player's angle
+    // Returns: the combo to bank, if there was one
+    pub fn land(&mut self, theta: f64) -> Option<Combo> {
+        self.ride(theta);
+        self.combo.take()
+    }
+
+    // Loses the combo to a bad landing
+    pub fn bail(&mut self) {
+        self.end_manual();
+        self.grab_ticks = 0;
+        self.grab_at = None;
+        self.spin = 0.0;
+        self.flip_at = None;
+        self.combo = None;
+    }
+
+    // Stops counting anything done in the air, from the given angle on
+    fn end_air(&mut self, theta: f64) {
+        self.last_theta = theta;
+        self.spin = 0.0;
+        self.flip_at = None;
+        self.grab_ticks = 0;
+        self.grab_at = None;
+    }
+
+    fn end_manual(&mut self) {
+        self.manual_ticks = 0;
+        self.manual_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // How far the player turns in a tick while flipping
+    const STEP: f64 = PI / 18.0;
+
+    /*  Spins the player through the air from upright
+     *
+     *  - Takes in `tricks` which is tracking them, on the ground so far
+     *  - Takes in `turns` which is how far to spin, forward is positive
+     *  - Takes in `grabbing` which is whether grab is held the whole time
+     *
+     *  - Returns every trick reported along the way
+     */
+    fn spin(tricks: &mut Tricks, turns: f64, grabbing: bool) -> Vec<Trick> {
+        tricks.ride(0.0);
+        let ticks = (turns.abs() * 2.0 * PI / STEP).round() as i32;
+        // Theta wraps at a full turn, the same as the player's
+        (1..=ticks)
+            .filter_map(|tick| {
+                let theta = (tick as f64 * STEP * turns.signum()).rem_euclid(2.0 * PI);
+                tricks.air(theta, grabbing)
+            })
+            .collect()
+    }
+
+    fn flip(rotations: u32, forward: bool) -> Trick {
+        Trick::Flip { rotations, forward }
+    }
+
+    #[test]
+    fn backflip() {
+        let mut tricks = Tricks::new();
+        assert_eq!(spin(&mut tricks, -1.0, false), vec![flip(1, false)]);
+
+        let combo = tricks.land(0.0).unwrap();
+        assert_eq!(combo.tricks().collect::<Vec<_>>(), vec![flip(1, false)]);
+        assert_eq!(combo.name(), "Backflip");
+        assert_eq!(combo.total(), FLIP_POINTS);
+    }
+
+    #[test]
+    fn double_backflip() {
+        let mut tricks = Tricks::new();
+        assert_eq!(
+            spin(&mut tricks, -2.0, false),
+            vec![flip(1, false), flip(2, false)]
+        );
+
+        // Going round again replaces the flip rather than adding another
+        let combo = tricks.land(0.0).unwrap();
+        assert_eq!(combo.tricks().collect::<Vec<_>>(), vec![flip(2, false)]);
+        assert_eq!(combo.name(), "Double backflip");
+        assert_eq!(combo.multiplier(), 1);
+        assert_eq!(combo.total(), 3 * FLIP_POINTS);
+    }
+
+    #[test]
+    fn frontflip() {
+        let mut tricks = Tricks::new();
+        assert_eq!(spin(&mut tricks, 1.0, false), vec![flip(1, true)]);
+        assert_eq!(tricks.land(0.0).unwrap().name(), "Frontflip");
+    }
+
+    #[test]
+    fn nearly_round_counts() {
+        // Short of upright by less than a landing allows
+        let mut tricks = Tricks::new();
+        let short = (MAX_UPRIGHT_ANGLE - STEP) / (2.0 * PI);
+        assert_eq!(
+            spin(&mut tricks, -(1.0 - short), false),
+            vec![flip(1, false)]
+        );
+    }
+
+    #[test]
+    fn half_flip_isnt_a_trick() {
+        let mut tricks = Tricks::new();
+        assert!(spin(&mut tricks, -0.5, false).is_empty());
+        assert!(tricks.land(PI).is_none());
+    }
+
+    #[test]
+    fn tricks_multiply() {
+        let mut tricks = Tricks::new();
+        assert_eq!(
+            spin(&mut tricks, -1.0, true),
+            vec![Trick::Grab, flip(1, false)]
+        );
+
+        let combo = tricks.land(0.0).unwrap();
+        assert_eq!(combo.name(), "Grab + Backflip");
+        assert_eq!(combo.multiplier(), 2);
+        assert_eq!(combo.total(), combo.points() * 2);
+    }
+
+    #[test]
+    fn multiplier_is_capped() {
+        let mut tricks = Tricks::new();
+        for _ in 0..MAX_MULTIPLIER + 5 {
+            assert_eq!(tricks.grind("Ledge", 10), Some(Trick::Grind("Ledge")));
+        }
+        let combo = tricks.land(0.0).unwrap();
+        assert_eq!(combo.multiplier(), MAX_MULTIPLIER);
+        assert_eq!(combo.total(), combo.points() * MAX_MULTIPLIER);
+    }
+
+    #[test]
+    fn bail_loses_the_combo() {
+        let mut tricks = Tricks::new();
+        spin(&mut tricks, -1.0, true);
+        assert!(tricks.combo().is_some());
+
+        tricks.bail();
+        assert!(tricks.combo().is_none());
+        assert!(tricks.land(0.0).is_none());
+    }
+}
@@ -11,3 +11,49 @@ macro_rules! rect(
 pub fn print_type_of<T>(_: &T) {
     println!("{}", std::any::type_name::<T>())
 }
+
+// Folder the game keeps its saved files in, following each platform's
+// convention for per-user application data:
+//   Linux:   $XDG_DATA_HOME/urban-odyssey or ~/.local/share/urban-odyssey
+//   macOS:   ~/Library/Application Support/urban-odyssey
+//   Windows: %APPDATA%\urban-odyssey
+// Falls back to the working directory if none of these can be found
+pub fn data_dir() -> std::path::PathBuf {
+    use std::env;
+    use std::path::PathBuf;
+
+    const APP_DIR: &str = "urban-odyssey";
+
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+
+    match base {
+        Some(base) => base.join(APP_DIR),
+        None => PathBuf::from("."),
+    }
+}
+
+// Finds which row of a vertical menu a point in CAM_W x CAM_H space is over
+// Params: y of the point, y of the first row, distance between rows, height
+//         of a row, number of rows
+// Returns: index of the row, None if the point is between or outside rows
+pub fn menu_row_at(y: i32, top: i32, spacing: i32, height: u32, rows: usize) -> Option<usize> {
+    if y < top {
+        return None;
+    }
+    let row = ((y - top) / spacing) as usize;
+    let within_row = (y - top) % spacing < height as i32;
+    if row < rows && within_row {
+        Some(row)
+    } else {
+        None
+    }
+}
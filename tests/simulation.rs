@@ -0,0 +1,265 @@
+// Runs whole games headless through the Simulation, the way a bot or a
+// batch of replays would
+
+use inf_runner::bezier::CubicBezier;
+
+use inf_runner::biome::Biome;
+
+use inf_runner::difficulty::Difficulty;
+
+use inf_runner::physics::Entity;
+
+use inf_runner::proceduralgen::TerrainSegment;
+use inf_runner::proceduralgen::CLIFF_MAX_DROP;
+
+use inf_runner::replay::Replay;
+use inf_runner::replay::ReplayPlayer;
+
+use inf_runner::simulation;
+use inf_runner::simulation::GameEvent;
+use inf_runner::simulation::Simulation;
+use inf_runner::simulation::TickInput;
+use inf_runner::simulation::PLAYER_X;
+use inf_runner::simulation::TILE_SIZE;
+
+// Longer than any run lasts without the player doing anything
+const MAX_TICKS: u64 = 20_000;
+
+// What a run ended with
+#[derive(PartialEq, Debug)]
+struct Outcome {
+    ticks: u64,
+    score: i32,
+    distance: u64,
+    coins: u32,
+    jumps: usize,
+    game_over: bool,
+}
+
+// Plays a run from `seed` until it's finished or `ticks` have passed
+// Params: seed, ticks to run for at most, input for each tick by number
+// Returns: how the run ended
+fn play(seed: u64, ticks: u64, input: impl Fn(u64) -> TickInput) -> Outcome {
+    let mut sim = Simulation::new(seed, Difficulty::Normal);
+    let mut jumps = 0;
+    for t in 0..ticks {
+        sim.tick(&input(t));
+        jumps += sim
+            .events()
+            .iter()
+            .filter(|e| **e == GameEvent::Jump)
+            .count();
+        if sim.is_finished() {
+            break;
+        }
+    }
+    Outcome {
+        ticks: sim.tick_count(),
+        score: sim.total_score,
+        distance: sim.distance(),
+        coins: sim.coins_collected(),
+        jumps,
+        game_over: sim.game_over(),
+    }
+}
+
+// Everything about a segment of terrain that doesn't change as it scrolls
+#[derive(PartialEq, Debug)]
+struct Segment {
+    curve: CubicBezier,
+    terrain_type: String,
+    biome: Biome,
+    set_piece: Option<String>,
+    heights: Vec<i32>, // World y of the ground in each column
+    pits: Vec<bool>,   // Which columns have no ground
+}
+
+impl Segment {
+    fn of(segment: &TerrainSegment) -> Segment {
+        let columns = 0..segment.w() as usize;
+        Segment {
+            curve: *segment.curve(),
+            terrain_type: format!("{:?}", segment.get_type()),
+            biome: segment.biome(),
+            set_piece: segment.set_piece().map(String::from),
+            heights: columns
+                .clone()
+                .map(|i| segment.ground_y(i) - segment.view().1)
+                .collect(),
+            pits: columns.map(|i| !segment.has_ground(i)).collect(),
+        }
+    }
+}
+
+// Plays a run from `seed` until `count` segments of terrain have been
+// generated, or the run is finished
+// Params: seed, number of segments, input for each tick by number
+// Returns: the segments in the order they were generated
+fn terrain(seed: u64, count: usize, input: impl Fn(u64) -> TickInput) -> Vec<Segment> {
+    let mut sim = Simulation::new(seed, Difficulty::Normal);
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut t = 0;
+    while segments.len() < count && !sim.is_finished() {
+        for segment in sim.all_terrain.iter() {
+            let segment = Segment::of(segment);
+            if segments.iter().all(|s| s.curve != segment.curve) {
+                segments.push(segment);
+            }
+        }
+        sim.tick(&input(t));
+        t += 1;
+    }
+    segments.truncate(count);
+    segments
+}
+
+// Jumps every `every` ticks and does nothing else
+fn jump_every(every: u64) -> impl Fn(u64) -> TickInput {
+    move |t| TickInput {
+        jump: t % every == 0,
+        ..TickInput::default()
+    }
+}
+
+#[test]
+fn idle_run_scores_then_ends() {
+    let outcome = play(1, MAX_TICKS, |_| TickInput::default());
+    assert!(outcome.game_over, "run never ended: {:?}", outcome);
+    assert!(outcome.ticks < MAX_TICKS);
+    assert!(outcome.distance > 0);
+    assert!(outcome.score > 0);
+    assert_eq!(outcome.jumps, 0);
+}
+
+#[test]
+fn short_run_is_still_going() {
+    let outcome = play(1, 60, |_| TickInput::default());
+    assert!(!outcome.game_over);
+    assert_eq!(outcome.ticks, 60);
+}
+
+#[test]
+fn scripted_jumps_leave_the_ground() {
+    let outcome = play(2, MAX_TICKS, jump_every(40));
+    assert!(outcome.jumps > 0);
+    assert!(outcome.game_over);
+}
+
+#[test]
+fn same_seed_and_input_play_out_the_same() {
+    for seed in 0..4 {
+        assert_eq!(
+            play(seed, MAX_TICKS, jump_every(40)),
+            play(seed, MAX_TICKS, jump_every(40))
+        );
+    }
+}
+
+#[test]
+fn same_seed_builds_the_same_terrain_whatever_the_input() {
+    for seed in 0..8 {
+        let idle = terrain(seed, 4, |_| TickInput::default());
+        let jumping = terrain(seed, 4, jump_every(25));
+        assert_eq!(idle.len(), 4, "run ended before 4 segments");
+        assert_eq!(idle, jumping, "seed {}", seed);
+    }
+}
+
+#[test]
+fn different_seeds_build_different_terrain() {
+    assert_ne!(
+        terrain(1, 3, |_| TickInput::default()),
+        terrain(2, 3, |_| TickInput::default())
+    );
+}
+
+#[test]
+fn leaving_water_while_under_it_comes_back_up_onto_the_ground() {
+    // Seed 13 comes up out of water onto grass with the player well under
+    // the surface
+    let mut sim = Simulation::new(13, Difficulty::Normal);
+    let (mut submerged, mut left_water) = (false, false);
+    while !sim.is_finished() && !sim.game_over() {
+        sim.tick(&TickInput::default());
+        let middle_x = PLAYER_X + TILE_SIZE as i32 / 2;
+        let Some(ground) = simulation::get_ground_coord(&sim.all_terrain, middle_x) else {
+            continue;
+        };
+        let bottom = sim.player.hitbox().bottom();
+        if simulation::get_ground_type(&sim.all_terrain, PLAYER_X)
+            .physics()
+            .floats
+        {
+            submerged |= bottom > ground.y() + 20;
+        } else {
+            left_water |= submerged;
+            submerged = false;
+            assert!(
+                sim.game_over() || bottom <= ground.y() + 5,
+                "tick {}: bottom {} under ground {}",
+                sim.tick_count(),
+                bottom,
+                ground.y()
+            );
+        }
+    }
+    assert!(left_water, "never left water while under it");
+}
+
+#[test]
+fn idle_player_rolls_off_the_deepest_cliff() {
+    // A cliff partway along the flat ground the run starts on
+    let cliff_at = 640;
+    let mut sim = Simulation::new(0, Difficulty::Normal);
+    sim.all_terrain[0].add_cliff(cliff_at, CLIFF_MAX_DROP);
+
+    let mut past = 0;
+    while past < 30 {
+        sim.tick(&TickInput::default());
+        assert!(!sim.game_over(), "lost on tick {}", sim.tick_count());
+        // Counted from once the player is all the way off the edge
+        if sim.all_terrain[0].x() + (cliff_at as i32) < PLAYER_X {
+            past += 1;
+        }
+    }
+    assert!(!sim.player.is_jumping(), "never landed at the bottom");
+}
+
+#[test]
+fn replay_saved_and_loaded_plays_out_the_same() {
+    // Jumps, flips in the air and grabs, recorded the way the game does it
+    let input = |t: u64| TickInput {
+        jump: t.is_multiple_of(40),
+        flip: t % 40 == 8,
+        release: t % 40 == 20,
+        grab: t % 80 == 4,
+        grab_release: t % 80 == 16,
+        ..TickInput::default()
+    };
+    let mut sim = Simulation::new(3, Difficulty::Normal);
+    let mut recording = Replay::new(3, Difficulty::Normal);
+    while !sim.is_finished() && sim.tick_count() < MAX_TICKS {
+        let airborne = sim.player.is_jumping() && !sim.player.is_grinding();
+        recording.record_input(sim.tick_count(), &input(sim.tick_count()), airborne);
+        sim.tick(&input(sim.tick_count()));
+    }
+
+    assert!(!recording.events().is_empty());
+
+    let path = std::env::temp_dir().join(format!("round-trip-{}.replay", std::process::id()));
+    recording.save(&path).unwrap();
+    let loaded = Replay::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.seed(), 3);
+    assert_eq!(loaded.events(), recording.events());
+
+    let mut played = Simulation::new(loaded.seed(), loaded.difficulty());
+    let mut player = ReplayPlayer::new(&loaded);
+    while !played.is_finished() && played.tick_count() < MAX_TICKS {
+        played.tick(&player.input_for(played.tick_count()));
+    }
+    assert!(sim.game_over());
+    assert_eq!(played.tick_count(), sim.tick_count());
+    assert_eq!(played.total_score, sim.total_score);
+}